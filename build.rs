@@ -1,13 +1,24 @@
-use std::fs::write;
-
+// the shaders are only needed by the GPU renderer of the emulator window
+#[cfg(feature = "gui")]
 fn main() {
-    println!("cargo:rerun-if-changed=src/shader.vert");
-    println!("cargo:rerun-if-changed=src/shader.frag");
-    let vertex_shader_glsl = include_str!("src/shader.vert");
-    let fragment_shader_glsl = include_str!("src/shader.frag");
-    let mut shader_compiler = shaderc::Compiler::new().unwrap(); 
-    let vertex_shader_spirv = shader_compiler.compile_into_spirv(vertex_shader_glsl, shaderc::ShaderKind::Vertex, "src/shader.vert", "main", None).unwrap();
-    let fragment_shader_spirv = shader_compiler.compile_into_spirv(fragment_shader_glsl, shaderc::ShaderKind::Fragment, "src/shader.frag", "main", None).unwrap();
-    write("src/shader.vert.spv", vertex_shader_spirv.as_binary_u8()).unwrap();
-    write("src/shader.frag.spv", fragment_shader_spirv.as_binary_u8()).unwrap();
+    use std::fs::{read_to_string, write};
+
+    const SHADERS: [&str; 5] = ["shader.vert", "passthrough.frag", "scanlines.frag", "aperture_mask.frag", "sharp_bilinear.frag"];
+
+    let mut shader_compiler = shaderc::Compiler::new().unwrap();
+    for shader in SHADERS.iter() {
+        let path = format!("src/shaders/{}", shader);
+        println!("cargo:rerun-if-changed={}", path);
+        let glsl = read_to_string(&path).unwrap();
+        let kind = if shader.ends_with(".vert") {
+            shaderc::ShaderKind::Vertex
+        } else {
+            shaderc::ShaderKind::Fragment
+        };
+        let spirv = shader_compiler.compile_into_spirv(&glsl, kind, &path, "main", None).unwrap();
+        write(format!("{}.spv", path), spirv.as_binary_u8()).unwrap();
+    }
 }
+
+#[cfg(not(feature = "gui"))]
+fn main() {}
@@ -0,0 +1,70 @@
+use std::{fmt, mem};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bus {
+	Cpu,
+	// accesses through PPUDATA
+	Ppu
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AccessKind {
+	Read,
+	Write,
+	Execute
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MemoryAccess {
+	pub bus: Bus,
+	pub kind: AccessKind,
+	pub address: u16,
+	pub value: u8
+}
+
+impl fmt::Display for MemoryAccess {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		let bus = match self.bus {
+			Bus::Cpu => "",
+			Bus::Ppu => "PPU "
+		};
+		match self.kind {
+			AccessKind::Read => write!(formatter, "{}read {:02X} from {:04X}", bus, self.value, self.address),
+			AccessKind::Write => write!(formatter, "{}write {:02X} to {:04X}", bus, self.value, self.address),
+			AccessKind::Execute => write!(formatter, "execute {:02X} at {:04X}", self.value, self.address)
+		}
+	}
+}
+
+// the accesses of the current instruction, only recorded while a watchpoint or a script callback needs them
+#[derive(Clone, Default)]
+pub struct AccessLog {
+	enabled: bool,
+	accesses: Vec<MemoryAccess>
+}
+
+impl AccessLog {
+	pub fn set_enabled(&mut self, enabled: bool) {
+		self.enabled = enabled;
+		self.accesses.clear();
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	pub fn record(&mut self, bus: Bus, kind: AccessKind, address: u16, value: u8) {
+		if self.enabled {
+			self.accesses.push(MemoryAccess {
+				bus,
+				kind,
+				address,
+				value
+			});
+		}
+	}
+
+	pub fn take(&mut self) -> Vec<MemoryAccess> {
+		mem::take(&mut self.accesses)
+	}
+}
@@ -1,14 +1,61 @@
+use region::*;
+
+const NTSC_NOISE_PERIODS: [u16; 16] = [4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068];
+const PAL_NOISE_PERIODS: [u16; 16] = [4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778];
+
+const NTSC_DMC_RATES: [u16; 16] = [428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54];
+const PAL_DMC_RATES: [u16; 16] = [398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50];
+
+// CPU cycles of the frame counter steps in 4-step mode
+const NTSC_FRAME_COUNTER_STEPS: [u16; 4] = [7457, 14913, 22371, 29829];
+const PAL_FRAME_COUNTER_STEPS: [u16; 4] = [8313, 16627, 24939, 33253];
+
+#[derive(Clone)]
 pub struct Apu {
+    noise_periods: &'static [u16; 16],
+    dmc_rates: &'static [u16; 16],
+    frame_counter_steps: &'static [u16; 4]
     // TODO
 }
 
 impl Apu {
     pub fn new() -> Self {
         Self {
-           // TODO 
+            noise_periods: &NTSC_NOISE_PERIODS,
+            dmc_rates: &NTSC_DMC_RATES,
+            frame_counter_steps: &NTSC_FRAME_COUNTER_STEPS
+            // TODO
+        }
+    }
+
+    pub fn set_region(&mut self, region: Region) {
+        // the Dendy APU keeps the NTSC timings
+        if region == Region::Pal {
+            self.noise_periods = &PAL_NOISE_PERIODS;
+            self.dmc_rates = &PAL_DMC_RATES;
+            self.frame_counter_steps = &PAL_FRAME_COUNTER_STEPS;
+        } else {
+            self.noise_periods = &NTSC_NOISE_PERIODS;
+            self.dmc_rates = &NTSC_DMC_RATES;
+            self.frame_counter_steps = &NTSC_FRAME_COUNTER_STEPS;
         }
     }
 
+    // CPU cycles between two clocks of the noise shift register, for the period index written to $400E
+    pub fn get_noise_period(&self, index: u8) -> u16 {
+        self.noise_periods[(index & 0x0f) as usize]
+    }
+
+    // CPU cycles between two bits of the DMC output, for the rate index written to $4010
+    pub fn get_dmc_rate(&self, index: u8) -> u16 {
+        self.dmc_rates[(index & 0x0f) as usize]
+    }
+
+    // CPU cycle of the step of the frame counter, from 0 to 3
+    pub fn get_frame_counter_step(&self, step: usize) -> u16 {
+        self.frame_counter_steps[step]
+    }
+
     pub fn do_cycle(&mut self) {
         // TODO
     }
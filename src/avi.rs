@@ -0,0 +1,183 @@
+use std::{
+	fs::File,
+	io::{BufWriter, Error, ErrorKind, Result, Seek, SeekFrom, Write}
+};
+
+const PIXEL_SIZE: usize = 4;
+const BYTES_PER_PIXEL: usize = 3;
+
+// the RIFF sizes are 32-bit
+const MAX_FILE_SIZE: u64 = 0xffff_ffff;
+
+const AVIF_HASINDEX: u32 = 0x10;
+const AVIIF_KEYFRAME: u32 = 0x10;
+const INDEX_ENTRY_SIZE: u32 = 16;
+
+// writes every frame as an uncompressed 24-bit bitmap, so that recordings are lossless
+// there is a single video stream, the APU doesn't produce samples to record yet
+pub struct AviRecorder {
+	file: BufWriter<File>,
+	width: usize,
+	height: usize,
+	frame_size: u32,
+	frame_count: u32,
+	// positions of the fields only known once the recording is finished
+	total_frames_position: u64,
+	length_position: u64,
+	movi_position: u64,
+	file_size: u64,
+	row: Vec<u8>
+}
+
+impl AviRecorder {
+	pub fn new(filename: &str, width: usize, height: usize, frame_rate: f64) -> Result<Self> {
+		// bitmap rows are padded to 4 bytes
+		let row_size = (width * BYTES_PER_PIXEL + 3) & !3;
+		let frame_size = (row_size * height) as u32;
+		let mut header = Vec::new();
+
+		header.extend_from_slice(b"RIFF");
+		push32(&mut header, 0);
+		header.extend_from_slice(b"AVI ");
+		header.extend_from_slice(b"LIST");
+		let hdrl_size_position = header.len();
+		push32(&mut header, 0);
+		header.extend_from_slice(b"hdrl");
+
+		header.extend_from_slice(b"avih");
+		push32(&mut header, 56);
+		push32(&mut header, (1_000_000.0 / frame_rate).round() as u32); // microseconds per frame
+		push32(&mut header, (frame_size as f64 * frame_rate) as u32); // maximum bytes per second
+		push32(&mut header, 0); // padding granularity
+		push32(&mut header, AVIF_HASINDEX);
+		let total_frames_position = header.len() as u64;
+		push32(&mut header, 0); // total frames
+		push32(&mut header, 0); // initial frames
+		push32(&mut header, 1); // streams
+		push32(&mut header, frame_size); // suggested buffer size
+		push32(&mut header, width as u32);
+		push32(&mut header, height as u32);
+		header.extend_from_slice(&[0; 16]); // reserved
+
+		header.extend_from_slice(b"LIST");
+		push32(&mut header, 4 + 8 + 56 + 8 + 40);
+		header.extend_from_slice(b"strl");
+
+		header.extend_from_slice(b"strh");
+		push32(&mut header, 56);
+		header.extend_from_slice(b"vids");
+		header.extend_from_slice(b"DIB ");
+		push32(&mut header, 0); // flags
+		push32(&mut header, 0); // priority and language
+		push32(&mut header, 0); // initial frames
+		// the frame rate as a fraction, precise enough for 60.0988 FPS
+		push32(&mut header, 1_000_000); // scale
+		push32(&mut header, (frame_rate * 1_000_000.0).round() as u32); // rate
+		push32(&mut header, 0); // start
+		let length_position = header.len() as u64;
+		push32(&mut header, 0); // length in frames
+		push32(&mut header, frame_size); // suggested buffer size
+		push32(&mut header, 0xffff_ffff); // default quality
+		push32(&mut header, 0); // sample size, 0 for video
+		header.extend_from_slice(&0u16.to_le_bytes());
+		header.extend_from_slice(&0u16.to_le_bytes());
+		header.extend_from_slice(&(width as u16).to_le_bytes());
+		header.extend_from_slice(&(height as u16).to_le_bytes());
+
+		// BITMAPINFOHEADER, a positive height stores the rows bottom-up
+		header.extend_from_slice(b"strf");
+		push32(&mut header, 40);
+		push32(&mut header, 40);
+		push32(&mut header, width as u32);
+		push32(&mut header, height as u32);
+		header.extend_from_slice(&1u16.to_le_bytes()); // planes
+		header.extend_from_slice(&((BYTES_PER_PIXEL * 8) as u16).to_le_bytes());
+		push32(&mut header, 0); // no compression
+		push32(&mut header, frame_size);
+		header.extend_from_slice(&[0; 16]); // resolution and color table
+
+		let hdrl_size = (header.len() - hdrl_size_position - 4) as u32;
+		header[hdrl_size_position..hdrl_size_position + 4].copy_from_slice(&hdrl_size.to_le_bytes());
+
+		header.extend_from_slice(b"LIST");
+		push32(&mut header, 0);
+		let movi_position = header.len() as u64;
+		header.extend_from_slice(b"movi");
+
+		let mut file = BufWriter::new(File::create(filename)?);
+		file.write_all(&header)?;
+		Ok(Self {
+			file,
+			width,
+			height,
+			frame_size,
+			frame_count: 0,
+			total_frames_position,
+			length_position,
+			movi_position,
+			file_size: header.len() as u64,
+			row: vec![0; row_size]
+		})
+	}
+
+	pub fn get_frame_count(&self) -> u32 {
+		self.frame_count
+	}
+
+	// the frame buffer is RGBA and must keep the size the recording started with
+	pub fn record_frame(&mut self, frame_buffer: &[u8], width: usize, height: usize) -> Result<()> {
+		if width != self.width || height != self.height {
+			return Err(Error::new(ErrorKind::InvalidInput, "The frame size changed during the recording"));
+		}
+		// the frame, the index entry written at the end and the index header must fit
+		let chunk_size = 8 + self.frame_size as u64;
+		let index_size = 8 + (self.frame_count as u64 + 1) * INDEX_ENTRY_SIZE as u64;
+		if self.file_size + chunk_size + index_size > MAX_FILE_SIZE {
+			return Err(Error::other("The recording reached the maximum AVI file size"));
+		}
+
+		self.file.write_all(b"00db")?;
+		self.file.write_all(&self.frame_size.to_le_bytes())?;
+		for source_row in frame_buffer.chunks(width * PIXEL_SIZE).take(height).rev() {
+			for (pixel, destination) in source_row.chunks(PIXEL_SIZE).zip(self.row.chunks_mut(BYTES_PER_PIXEL)) {
+				destination.copy_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+			}
+			self.file.write_all(&self.row)?;
+		}
+		self.file_size += chunk_size;
+		self.frame_count += 1;
+		Ok(())
+	}
+
+	// writes the index and the sizes, the file can't be played without them
+	pub fn finish(mut self) -> Result<()> {
+		let movi_size = (self.file_size - self.movi_position) as u32;
+		self.file.write_all(b"idx1")?;
+		self.file.write_all(&(self.frame_count * INDEX_ENTRY_SIZE).to_le_bytes())?;
+		for frame in 0..self.frame_count {
+			// offsets are relative to the "movi" identifier
+			let offset = 4 + frame * (8 + self.frame_size);
+			self.file.write_all(b"00db")?;
+			self.file.write_all(&AVIIF_KEYFRAME.to_le_bytes())?;
+			self.file.write_all(&offset.to_le_bytes())?;
+			self.file.write_all(&self.frame_size.to_le_bytes())?;
+		}
+		let file_size = self.file_size + 8 + (self.frame_count * INDEX_ENTRY_SIZE) as u64;
+
+		let frame_count = self.frame_count.to_le_bytes();
+		self.write_at(4, &((file_size - 8) as u32).to_le_bytes())?;
+		self.write_at(self.total_frames_position, &frame_count)?;
+		self.write_at(self.length_position, &frame_count)?;
+		self.write_at(self.movi_position - 4, &movi_size.to_le_bytes())?;
+		self.file.flush()
+	}
+
+	fn write_at(&mut self, position: u64, data: &[u8]) -> Result<()> {
+		self.file.seek(SeekFrom::Start(position))?;
+		self.file.write_all(data)
+	}
+}
+
+fn push32(data: &mut Vec<u8>, value: u32) {
+	data.extend_from_slice(&value.to_le_bytes());
+}
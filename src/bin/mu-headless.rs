@@ -0,0 +1,241 @@
+extern crate mu;
+
+#[macro_use]
+extern crate log;
+
+use std::{
+	fs,
+	process,
+	str::FromStr
+};
+
+use mu::{
+	emulator::*,
+	cpu::*,
+	screen::*,
+	region::*,
+	checksum::*,
+	png::*,
+	avi::*,
+	movie::*
+};
+
+#[cfg(feature = "scripting")]
+use mu::scripting::*;
+
+// "<address>==<value>" or "<address>!=<value>", checked after every frame
+struct Condition {
+	address: u16,
+	value: u8,
+	equal: bool
+}
+
+impl FromStr for Condition {
+	type Err = String;
+
+	fn from_str(condition: &str) -> Result<Self, Self::Err> {
+		let (operator, equal) = if condition.contains("!=") {
+			("!=", false)
+		} else {
+			("==", true)
+		};
+		let mut operands = condition.split(operator);
+		match (operands.next(), operands.next()) {
+			(Some(address), Some(value)) => Ok(Self {
+				address: parse_number(address)? as u16,
+				value: parse_number(value)? as u8,
+				equal
+			}),
+			_ => Err(format!("Wrong condition: {}", condition))
+		}
+	}
+}
+
+impl Condition {
+	fn is_met(&self, emulator: &Emulator) -> bool {
+		(Cpu::read8_debug(emulator, self.address) == self.value) == self.equal
+	}
+}
+
+// "$" or "0x" prefixes hexadecimal numbers
+fn parse_number(number: &str) -> Result<u32, String> {
+	let number = number.trim();
+	let result = if let Some(hexadecimal) = number.strip_prefix('$').or_else(|| number.strip_prefix("0x")) {
+		u32::from_str_radix(hexadecimal, 16)
+	} else {
+		number.parse()
+	};
+	result.map_err(|_| format!("Wrong number: {}", number))
+}
+
+struct Options {
+	filename: String,
+	region: Option<Region>,
+	frames: Option<usize>,
+	movie: Option<String>,
+	condition: Option<Condition>,
+	png: Option<String>,
+	ram: Option<String>,
+	hashes: Option<String>,
+	avi: Option<String>,
+	code_data_log: Option<String>,
+	profile: Option<String>,
+	events: Option<String>,
+	#[cfg(feature = "scripting")]
+	script: Option<String>
+}
+
+impl Options {
+	fn parse() -> Self {
+		let mut filename = None;
+		let mut region = None;
+		let mut frames = None;
+		let mut movie = None;
+		let mut condition = None;
+		let mut png = None;
+		let mut ram = None;
+		let mut hashes = None;
+		let mut avi = None;
+		let mut code_data_log = None;
+		let mut profile = None;
+		let mut events = None;
+		#[cfg(feature = "scripting")]
+		let mut script = None;
+		let mut arguments = std::env::args().skip(1);
+		while let Some(argument) = arguments.next() {
+			match argument.as_str() {
+				"--region" => region = Some(get_value(&mut arguments, &argument).parse().unwrap()),
+				"--frames" => frames = Some(get_value(&mut arguments, &argument).parse().unwrap()),
+				"--movie" => movie = Some(get_value(&mut arguments, &argument)),
+				"--until" => condition = Some(get_value(&mut arguments, &argument).parse().unwrap()),
+				"--png" => png = Some(get_value(&mut arguments, &argument)),
+				"--ram" => ram = Some(get_value(&mut arguments, &argument)),
+				"--hashes" => hashes = Some(get_value(&mut arguments, &argument)),
+				"--avi" => avi = Some(get_value(&mut arguments, &argument)),
+				"--cdl" => code_data_log = Some(get_value(&mut arguments, &argument)),
+				"--profile" => profile = Some(get_value(&mut arguments, &argument)),
+				"--events" => events = Some(get_value(&mut arguments, &argument)),
+				#[cfg(feature = "scripting")]
+				"--script" => script = Some(get_value(&mut arguments, &argument)),
+				_ => filename = Some(argument)
+			}
+		}
+		Self {
+			filename: filename.expect("No ROM file given"),
+			region,
+			frames,
+			movie,
+			condition,
+			png,
+			ram,
+			hashes,
+			avi,
+			code_data_log,
+			profile,
+			events,
+			#[cfg(feature = "scripting")]
+			script
+		}
+	}
+}
+
+fn get_value(arguments: &mut dyn Iterator<Item = String>, option: &str) -> String {
+	arguments.next().unwrap_or_else(|| panic!("Missing value for {}", option))
+}
+
+fn main() {
+	env_logger::Builder::new().filter_level(log::LevelFilter::Info).init();
+
+	let options = Options::parse();
+	let mut emulator = Emulator::new();
+	emulator.load_file(&options.filename);
+	if let Some(region) = options.region {
+		emulator.set_region(region);
+	}
+
+	let movie = options.movie.as_ref().map(|filename| Movie::load(filename).unwrap());
+	// without a frame count, a movie runs until its end and a condition until it is met
+	let frame_count = options.frames.or_else(|| movie.as_ref().map(|movie| movie.get_frame_count()));
+	if frame_count.is_none() && options.condition.is_none() {
+		error!("Give a frame count, a movie or a condition");
+		process::exit(2);
+	}
+	let frame_count = frame_count.unwrap_or(usize::MAX);
+
+	if let Some(ref filename) = options.code_data_log {
+		emulator.start_code_data_log(filename).unwrap();
+	}
+	if let Some(ref filename) = options.profile {
+		emulator.start_profiler(filename).unwrap();
+	}
+	emulator.set_event_logging(options.events.is_some());
+	#[cfg(feature = "scripting")]
+	let mut script = options.script.as_ref().map(|filename| Script::load(filename, &mut emulator).unwrap_or_else(|error| {
+		error!("Couldn't load the script {}: {}", filename, error);
+		process::exit(2);
+	}));
+
+	let mut recorder = options.avi.as_ref().map(|filename| {
+		AviRecorder::new(filename, emulator.screen.get_frame_width(), FRAME_HEIGHT, emulator.region.get_frame_rate()).unwrap()
+	});
+	let mut hashes = String::new();
+	let mut frame = 0;
+	let mut condition_met = false;
+	while frame < frame_count {
+		if let Some(ref movie) = movie {
+			movie.apply(frame, &mut emulator.joypad);
+		}
+		#[cfg(not(feature = "scripting"))]
+		emulator.run_frame();
+		#[cfg(feature = "scripting")]
+		match script {
+			Some(ref mut script) => script.run_frame(&mut emulator),
+			None => emulator.run_frame()
+		}
+		hashes += &format!("{} {:08x}\n", frame, crc32(emulator.screen.get_frame_buffer()));
+		if let Some(ref mut recorder) = recorder {
+			recorder.record_frame(emulator.screen.get_frame_buffer(), emulator.screen.get_frame_width(), FRAME_HEIGHT).unwrap();
+		}
+		emulator.screen.finish_draw();
+		frame += 1;
+
+		if let Some(ref condition) = options.condition {
+			if condition.is_met(&emulator) {
+				condition_met = true;
+				break;
+			}
+		}
+	}
+	info!("Ran {} frames", frame);
+
+	if let Some(ref filename) = options.png {
+		save_png(filename, emulator.screen.get_frame_width(), FRAME_HEIGHT, emulator.screen.get_frame_buffer()).unwrap();
+	}
+	if let Some(ref filename) = options.ram {
+		fs::write(filename, &emulator.ram[..]).unwrap();
+	}
+	if let Some(ref filename) = options.hashes {
+		fs::write(filename, hashes).unwrap();
+	}
+	if let Some(ref filename) = options.events {
+		let event_log = emulator.event_log.as_ref().unwrap();
+		let events: String = event_log.get_last_frame(emulator.ppu.get_frame_counter()).iter().map(|event| format!("{}\n", event)).collect();
+		fs::write(filename, events).unwrap();
+	}
+	if let Some(recorder) = recorder {
+		recorder.finish().unwrap();
+	}
+	if let Some(ref code_data_log) = emulator.code_data_log {
+		info!("{}", code_data_log.describe());
+	}
+	emulator.stop_code_data_log().unwrap();
+	if let Some(ref profiler) = emulator.profiler {
+		info!("{}", profiler.describe());
+	}
+	emulator.stop_profiler().unwrap();
+
+	if options.condition.is_some() && !condition_met {
+		error!("The condition wasn't met");
+		process::exit(1);
+	}
+}
@@ -0,0 +1,96 @@
+use std::{
+	fs,
+	io::{Error, ErrorKind, Result},
+	path::{Path, PathBuf}
+};
+
+// flags of the PRG ROM bytes, the bits 2 and 3 being the 8KB window ($8000, $A000, $C000 or $E000) the byte was last accessed in
+pub const CODE: u8 = 0x01;
+pub const DATA: u8 = 0x02;
+// the target of an indirect jump
+pub const INDIRECT_CODE: u8 = 0x10;
+// read by a (zp,X) or (zp),Y instruction
+pub const INDIRECT_DATA: u8 = 0x20;
+const WINDOW_MASK: u8 = 0x0c;
+
+// flags of the CHR ROM bytes
+pub const RENDERED: u8 = 0x01;
+// read through PPUDATA
+pub const READ: u8 = 0x02;
+
+const CHR_ROM_END: u16 = 0x1fff;
+
+// code/data log of FCEUX (.cdl): a byte of flags for each byte of PRG ROM, then each byte of CHR ROM
+pub struct CodeDataLog {
+	filename: PathBuf,
+	prg_rom: Vec<u8>,
+	chr_rom: Vec<u8>
+}
+
+impl CodeDataLog {
+	// continues the log saved in the file if there is one
+	pub fn open<P: AsRef<Path>>(filename: P, prg_rom_size: usize, chr_rom_size: usize) -> Result<Self> {
+		let filename = filename.as_ref().to_path_buf();
+		let mut log = Self {
+			filename,
+			prg_rom: vec![0; prg_rom_size],
+			chr_rom: vec![0; chr_rom_size]
+		};
+		if log.filename.exists() {
+			let contents = fs::read(&log.filename)?;
+			if contents.len() != prg_rom_size + chr_rom_size {
+				return Err(Error::new(ErrorKind::InvalidData, format!("The log has {} bytes instead of {} for this ROM", contents.len(), prg_rom_size + chr_rom_size)));
+			}
+			let (prg_rom, chr_rom) = contents.split_at(prg_rom_size);
+			log.prg_rom.copy_from_slice(prg_rom);
+			log.chr_rom.copy_from_slice(chr_rom);
+		}
+		Ok(log)
+	}
+
+	pub fn save(&self) -> Result<()> {
+		fs::write(&self.filename, [&self.prg_rom[..], &self.chr_rom[..]].concat())
+	}
+
+	pub fn get_filename(&self) -> &Path {
+		&self.filename
+	}
+
+	pub fn get_prg_rom_flags(&self) -> &[u8] {
+		&self.prg_rom
+	}
+
+	// an access to the byte of PRG ROM at the offset, through the CPU address
+	pub fn log_prg_rom(&mut self, offset: usize, address: u16, flags: u8) {
+		if let Some(byte) = self.prg_rom.get_mut(offset) {
+			let window = ((address >> 13) & 0b11) as u8;
+			*byte = (*byte & !WINDOW_MASK) | (window << 2) | flags;
+		}
+	}
+
+	// an access to a pattern table, CHR ROM not being banked yet
+	pub fn log_chr_rom(&mut self, address: u16, flags: u8) {
+		if address <= CHR_ROM_END {
+			if let Some(byte) = self.chr_rom.get_mut(address as usize) {
+				*byte |= flags;
+			}
+		}
+	}
+
+	// "code: 1234 bytes (7.5%), data: ..." to tell how much of the ROM has been covered
+	pub fn describe(&self) -> String {
+		let count = |bytes: &[u8], flags: u8| bytes.iter().filter(|&&byte| byte & flags != 0).count();
+		let percentage = |count: usize, total: usize| 100.0 * count as f64 / total.max(1) as f64;
+		let code = count(&self.prg_rom, CODE);
+		let data = count(&self.prg_rom, DATA);
+		let prg_rom = count(&self.prg_rom, CODE | DATA);
+		let mut description = format!("PRG ROM code: {} bytes, data: {} bytes, {:.1}% logged", code, data, percentage(prg_rom, self.prg_rom.len()));
+		if !self.chr_rom.is_empty() {
+			let rendered = count(&self.chr_rom, RENDERED);
+			let read = count(&self.chr_rom, READ);
+			let chr_rom = count(&self.chr_rom, RENDERED | READ);
+			description += &format!(", CHR ROM rendered: {} bytes, read: {} bytes, {:.1}% logged", rendered, read, percentage(chr_rom, self.chr_rom.len()));
+		}
+		description
+	}
+}
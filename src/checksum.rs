@@ -0,0 +1,14 @@
+pub fn crc32(data: &[u8]) -> u32 {
+	let mut crc = 0xffff_ffffu32;
+	for byte in data {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			crc = if (crc & 1) != 0 {
+				(crc >> 1) ^ 0xedb8_8320
+			} else {
+				crc >> 1
+			};
+		}
+	}
+	!crc
+}
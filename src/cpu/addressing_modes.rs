@@ -67,7 +67,9 @@ impl AddressingMode for AbsoluteY {
 impl AddressingMode for IndirectX {
 	fn get_address(emulator: &mut Emulator) -> u16 {
 		let address = read_next8(emulator).wrapping_add(emulator.cpu.x);
-		read16_zeropage(emulator, address)
+		let effective_address = read16_zeropage(emulator, address);
+		emulator.cpu.indirect_addressing = true;
+		effective_address
 	}
 }
 
@@ -77,6 +79,7 @@ impl AddressingMode for IndirectY {
 		let address = read16_zeropage(emulator, immediate);
 		let effective_address = address.wrapping_add(emulator.cpu.y as _);
 		emulator.cpu.check_page_crossing(address, effective_address);
+		emulator.cpu.indirect_addressing = true;
 		effective_address
 	}
 }
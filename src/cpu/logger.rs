@@ -1,13 +1,57 @@
 use std::{
-	io::prelude::Write,
+	io::{self, prelude::Write, BufWriter},
 	fs::File,
-	cell::RefCell
+	path::Path,
+	collections::VecDeque,
+	cell::RefCell,
+	str::FromStr
 };
 
 use emulator::*;
+use disasm::*;
 use super::memory::*;
 
-const MAX_LOGS: usize = 44_000;
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TraceFormat {
+	Nestest,
+	Fceux,
+	Mesen
+}
+
+impl FromStr for TraceFormat {
+	type Err = String;
+
+	fn from_str(format: &str) -> Result<Self, Self::Err> {
+		match format.to_lowercase().as_str() {
+			"nestest" => Ok(TraceFormat::Nestest),
+			"fceux" => Ok(TraceFormat::Fceux),
+			"mesen" => Ok(TraceFormat::Mesen),
+			_ => Err(format!("Unknown trace format: {}", format))
+		}
+	}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TraceOptions {
+	pub format: TraceFormat,
+	// keeps the last instructions and writes them when the trace stops, instead of writing every instruction
+	pub ring_buffer_size: Option<usize>,
+	// PPU scanline and dot at the start of every instruction
+	pub ppu_position: bool,
+	// CPU cycles since the power on
+	pub cpu_cycles: bool
+}
+
+impl Default for TraceOptions {
+	fn default() -> Self {
+		Self {
+			format: TraceFormat::Nestest,
+			ring_buffer_size: None,
+			ppu_position: false,
+			cpu_cycles: false
+		}
+	}
+}
 
 struct Data {
 	pc: u16,
@@ -17,439 +61,118 @@ struct Data {
 	x: u8,
 	y: u8,
 	p: u8,
-	s: u8
-}
-
-struct Buffer {
-	data: Vec<Data>,
-	index: usize
-}
-
-impl Buffer {
-	fn new() -> Self {
-		Self {
-			data: Vec::with_capacity(MAX_LOGS),
-			index: 0
+	s: u8,
+	cycles: u64,
+	scanline: u16,
+	dot: u16,
+	// with the labels of the banks mapped when the instruction was executed
+	labelled_instruction: Option<String>
+}
+
+struct Output {
+	writer: BufWriter<File>,
+	ring_buffer: VecDeque<Data>,
+	// the first write error, reported when the trace stops
+	error: Option<io::Error>
+}
+
+impl Output {
+	fn write(&mut self, options: &TraceOptions, data: &Data) {
+		if self.error.is_none() {
+			let line = format_line(options, data);
+			if let Err(error) = self.writer.write_all(line.as_bytes()) {
+				self.error = Some(error);
+			}
 		}
 	}
 
-	fn push(&mut self, data: Data) {
-		if self.data.len() < MAX_LOGS {
-			self.data.push(data);
-		} else {
-			self.data[self.index] = data;
-			self.index = (self.index + 1) % MAX_LOGS;
+	fn finish(&mut self, options: &TraceOptions) -> io::Result<()> {
+		while let Some(data) = self.ring_buffer.pop_front() {
+			self.write(options, &data);
+		}
+		match self.error.take() {
+			Some(error) => Err(error),
+			None => self.writer.flush()
 		}
 	}
 }
 
-impl Drop for Buffer {
+pub(super) struct Logger {
+	options: TraceOptions,
+	output: RefCell<Output>
+}
+
+impl Drop for Logger {
 	fn drop(&mut self) {
-		let mut file = File::create("trace.log").unwrap();
-		for i in 0..self.data.len() {
-			let index = (self.index + i) % MAX_LOGS;
-			let data = &self.data[index];
-			let instruction = format_instruction(data);
-			let log = format!("{:04X}  {:02X} {:<38} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}\n", data.pc, data.opcode, instruction, data.a, data.x, data.y, data.p, data.s);
-			file.write_all(log.as_bytes()).unwrap();
+		if let Err(error) = self.output.get_mut().finish(&self.options) {
+			error!("Couldn't write the trace: {}", error);
 		}
-		file.flush().unwrap();
 	}
 }
 
-pub(super) struct Logger {
-	buffer: RefCell<Buffer>
-}
-
 impl Logger {
-	pub(super) fn new() -> Self {
-		Self {
-			buffer: RefCell::new(Buffer::new())
+	pub(super) fn new(path: &Path, options: TraceOptions) -> io::Result<Self> {
+		Ok(Self {
+			options,
+			output: RefCell::new(Output {
+				writer: BufWriter::new(File::create(path)?),
+				ring_buffer: VecDeque::with_capacity(options.ring_buffer_size.unwrap_or(0)),
+				error: None
+			})
+		})
+	}
+
+	// writes what is left in the ring buffer
+	pub(super) fn finish(mut self) -> io::Result<()> {
+		self.output.get_mut().finish(&self.options)
+	}
+
+	fn push(&self, data: Data) {
+		let mut output = self.output.borrow_mut();
+		match self.options.ring_buffer_size {
+			Some(size) => {
+				if output.ring_buffer.len() >= size {
+					output.ring_buffer.pop_front();
+				}
+				if size > 0 {
+					output.ring_buffer.push_back(data);
+				}
+			},
+			None => output.write(&self.options, &data)
 		}
 	}
 
 	pub(super) fn get_trace_function(opcode: u8) -> fn(&Emulator, u8) {
-		match opcode {
-			// NOPs
-			0x1a | 0x3a | 0x5a | 0x7a | 0xda | 0xea | 0xfa => trace_function,
-			0x80 => trace_function_immediate,
-			0x04 | 0x44 | 0x64 | 0x82 | 0x89 | 0xc2 | 0xe2 => trace_function_zero_page,
-			0x14 | 0x34 | 0x54 | 0x74 | 0xd4 | 0xf4 => trace_function_zero_page_x,
-			0x0c => trace_function_absolute,
-			0x1c | 0x3c | 0x5c | 0x7c | 0xdc | 0xfc => trace_function_absolute_x,
-	
-			// LDA
-			0xa9 => trace_function_immediate,
-			0xa5 => trace_function_zero_page,
-			0xb5 => trace_function_zero_page_x,
-			0xad => trace_function_absolute,
-			0xbd => trace_function_absolute_x,
-			0xb9 => trace_function_absolute_y,
-			0xa1 => trace_function_indirect_x,
-			0xb1 => trace_function_indirect_y,
-	
-			// LDX
-			0xa2 => trace_function_immediate,
-			0xa6 => trace_function_zero_page,
-			0xb6 => trace_function_zero_page_y,
-			0xae => trace_function_absolute,
-			0xbe => trace_function_absolute_y,
-	
-			// LDY
-			0xa0 => trace_function_immediate,
-			0xa4 => trace_function_zero_page,
-			0xb4 => trace_function_zero_page_x,
-			0xac => trace_function_absolute,
-			0xbc => trace_function_absolute_x,
-	
-			// LAX
-			0xab => trace_function_immediate,
-			0xa7 => trace_function_zero_page,
-			0xb7 => trace_function_zero_page_y,
-			0xaf => trace_function_absolute,
-			0xbf => trace_function_absolute_y,
-			0xa3 => trace_function_indirect_x,
-			0xb3 => trace_function_indirect_y,
-	
-			// STA
-			0x85 => trace_function_zero_page,
-			0x95 => trace_function_zero_page_x,
-			0x8d => trace_function_absolute,
-			0x9d => trace_function_absolute_x,
-			0x99 => trace_function_absolute_y,
-			0x81 => trace_function_indirect_x,
-			0x91 => trace_function_indirect_y,
-	
-			// STX
-			0x86 => trace_function_zero_page,
-			0x96 => trace_function_zero_page_y,
-			0x8e => trace_function_absolute,
-	
-			// STY
-			0x84 => trace_function_zero_page,
-			0x94 => trace_function_zero_page_x,
-			0x8c => trace_function_absolute,
-	
-			// SAX
-			0x87 => trace_function_zero_page,
-			0x97 => trace_function_zero_page_y,
-			0x8f => trace_function_absolute,
-			0x83 => trace_function_indirect_x,
-	
-			// SXA
-			0x9e => trace_function_absolute_y,
-	
-			// SYA
-			0x9c => trace_function_absolute_x,
-	
-			// TAX
-			0xaa => trace_function,
-			
-			// TXA
-			0x8a => trace_function,
-	
-			// TAY
-			0xa8 => trace_function,
-	
-			// TYA
-			0x98 => trace_function,
-	
-			// TSX
-			0xba => trace_function,
-	
-			// TXS
-			0x9a => trace_function,
-	
-			// AND
-			0x29 => trace_function_immediate,
-			0x25 => trace_function_zero_page,
-			0x35 => trace_function_zero_page_x,
-			0x2d => trace_function_absolute,
-			0x3d => trace_function_absolute_x,
-			0x39 => trace_function_absolute_y,
-			0x21 => trace_function_indirect_x,
-			0x31 => trace_function_indirect_y,
-	
-			// AAC
-			0x0b | 0x2b => trace_function_immediate,
-	
-			// ASR
-			0x4b => trace_function_immediate,
-	
-			// ARR
-			0x6b => trace_function_immediate,
-	
-			// AXS
-			0xcb => trace_function_immediate,
-	
-			// ORA
-			0x09 => trace_function_immediate,
-			0x05 => trace_function_zero_page,
-			0x15 => trace_function_zero_page_x,
-			0x0d => trace_function_absolute,
-			0x1d => trace_function_absolute_x,
-			0x19 => trace_function_absolute_y,
-			0x01 => trace_function_indirect_x,
-			0x11 => trace_function_indirect_y,
-	
-			// EOR
-			0x49 => trace_function_immediate,
-			0x45 => trace_function_zero_page,
-			0x55 => trace_function_zero_page_x,
-			0x4d => trace_function_absolute,
-			0x5d => trace_function_absolute_x,
-			0x59 => trace_function_absolute_y,
-			0x41 => trace_function_indirect_x,
-			0x51 => trace_function_indirect_y,
-	
-			// BIT
-			0x24 => trace_function_zero_page,
-			0x2c => trace_function_absolute,
-	
-			// LSR
-			0x4a => trace_function,
-			0x46 => trace_function_zero_page,
-			0x56 => trace_function_zero_page_x,
-			0x4e => trace_function_absolute,
-			0x5e => trace_function_absolute_x,
-	
-			// SRE
-			0x47 => trace_function_zero_page,
-			0x57 => trace_function_zero_page_x,
-			0x4f => trace_function_absolute,
-			0x5f => trace_function_absolute_x,
-			0x5b => trace_function_absolute_y,
-			0x43 => trace_function_indirect_x,
-			0x53 => trace_function_indirect_y,		
-	
-			// ASL
-			0x0a => trace_function,
-			0x06 => trace_function_zero_page,
-			0x16 => trace_function_zero_page_x,
-			0x0e => trace_function_absolute,
-			0x1e => trace_function_absolute_x,
-	
-			// SLO
-			0x07 => trace_function_zero_page,
-			0x17 => trace_function_zero_page_x,
-			0x0f => trace_function_absolute,
-			0x1f => trace_function_absolute_x,
-			0x1b => trace_function_absolute_y,
-			0x03 => trace_function_indirect_x,
-			0x13 => trace_function_indirect_y,
-	
-			// ROR
-			0x6a => trace_function,
-			0x66 => trace_function_zero_page,
-			0x76 => trace_function_zero_page_x,
-			0x6e => trace_function_absolute,
-			0x7e => trace_function_absolute_x,
-	
-			// RRA
-			0x67 => trace_function_zero_page,
-			0x77 => trace_function_zero_page_x,
-			0x6f => trace_function_absolute,
-			0x7f => trace_function_absolute_x,
-			0x7b => trace_function_absolute_y,
-			0x63 => trace_function_indirect_x,
-			0x73 => trace_function_indirect_y,
-	
-			// ROL
-			0x2a => trace_function,
-			0x26 => trace_function_zero_page,
-			0x36 => trace_function_zero_page_x,
-			0x2e => trace_function_absolute,
-			0x3e => trace_function_absolute_x,
-	
-			// RLA
-			0x27 => trace_function_zero_page, 
-			0x37 => trace_function_zero_page_x,
-			0x2f => trace_function_absolute,
-			0x3f => trace_function_absolute_x,
-			0x3b => trace_function_absolute_y,
-			0x23 => trace_function_indirect_x,
-			0x33 => trace_function_indirect_y,
-	
-			// ADC
-			0x69 => trace_function_immediate,
-			0x65 => trace_function_zero_page,
-			0x75 => trace_function_zero_page_x,
-			0x6d => trace_function_absolute,
-			0x7d => trace_function_absolute_x,
-			0x79 => trace_function_absolute_y,
-			0x61 => trace_function_indirect_x,
-			0x71 => trace_function_indirect_y,
-	
-			// SBC
-			0xe9 | 0xeb => trace_function_immediate,
-			0xe5 => trace_function_zero_page,
-			0xf5 => trace_function_zero_page_x,
-			0xed => trace_function_absolute,
-			0xfd => trace_function_absolute_x,
-			0xf9 => trace_function_absolute_y,
-			0xe1 => trace_function_indirect_x,
-			0xf1 => trace_function_indirect_y,
-	
-			// INX
-			0xe8 => trace_function,
-	
-			// INY
-			0xc8 => trace_function,
-	
-			// INC
-			0xe6 => trace_function_zero_page,
-			0xf6 => trace_function_zero_page_x,
-			0xee => trace_function_absolute,
-			0xfe => trace_function_absolute_x,
-	
-			// ISB
-			0xe7 => trace_function_zero_page,
-			0xf7 => trace_function_zero_page_x,
-			0xef => trace_function_absolute,
-			0xff => trace_function_absolute_x,
-			0xfb => trace_function_absolute_y,
-			0xe3 => trace_function_indirect_x,
-			0xf3 => trace_function_indirect_y,
-	
-			// DEX
-			0xca => trace_function,
-	
-			// DEY
-			0x88 => trace_function,
-	
-			// DEC
-			0xc6 => trace_function_zero_page,
-			0xd6 => trace_function_zero_page_x,
-			0xce => trace_function_absolute,
-			0xde => trace_function_absolute_x,
-	
-			// DCP
-			0xc7 => trace_function_zero_page,
-			0xd7 => trace_function_zero_page_x,
-			0xcf => trace_function_absolute,
-			0xdf => trace_function_absolute_x,
-			0xdb => trace_function_absolute_y,
-			0xc3 => trace_function_indirect_x,
-			0xd3 => trace_function_indirect_y,
-	
-			// CPX
-			0xe0 => trace_function_immediate,
-			0xe4 => trace_function_zero_page,
-			0xec => trace_function_absolute,
-	
-			// CPY
-			0xc0 => trace_function_immediate,
-			0xc4 => trace_function_zero_page,
-			0xcc => trace_function_absolute,
-	
-			// CMP
-			0xc9 => trace_function_immediate,
-			0xc5 => trace_function_zero_page,
-			0xd5 => trace_function_zero_page_x,
-			0xcd => trace_function_absolute,
-			0xdd => trace_function_absolute_x,
-			0xd9 => trace_function_absolute_y,
-			0xc1 => trace_function_indirect_x,
-			0xd1 => trace_function_indirect_y,
-	
-			// PHA
-			0x48 => trace_function,
-	
-			// PLA
-			0x68 => trace_function,
-	
-			// PHP
-			0x08 => trace_function,
-	
-			// PLP
-			0x28 => trace_function,
-	
-			// CLC
-			0x18 => trace_function,
-	
-			// SEC
-			0x38 => trace_function,
-	
-			// CLI
-			0x58 => trace_function,
-	
-			// SEI
-			0x78 => trace_function,
-	
-			// CLD
-			0xd8 => trace_function,
-	
-			// SED
-			0xf8 => trace_function,
-	
-			// CLV
-			0xb8 => trace_function,
-	
-			// JMP
-			0x4c => trace_function_jump_absolute,
-	
-			// JSR
-			0x20 => trace_function_jump_absolute,
-	
-			// JMP (indirect)
-			0x6c => |emulator, opcode| {
-				let address = read16_debug(emulator, emulator.cpu.pc);
-				let low_byte = read8_debug(emulator, address);
-				let high_byte = read8_debug(emulator, (address & 0xff00) | (address.wrapping_add(1) & 0x00ff));
-				let mut opcode_data = vec![0u16; 3];
-				opcode_data[0] = address;
-				opcode_data[1] = low_byte as _;
-				opcode_data[2] = high_byte as _;
-				create_trace_data(emulator, opcode, opcode_data);
+		let opcode = get_opcode(opcode);
+		match opcode.mode {
+			AddressingMode::Implied | AddressingMode::Accumulator => trace_function,
+			AddressingMode::Immediate => trace_function_immediate,
+			AddressingMode::ZeroPage => trace_function_zero_page,
+			AddressingMode::ZeroPageX => trace_function_zero_page_x,
+			AddressingMode::ZeroPageY => trace_function_zero_page_y,
+			AddressingMode::Absolute => if is_jump(opcode) {
+				trace_function_jump_absolute
+			} else {
+				trace_function_absolute
 			},
-	
-			// BPL
-			0x10 => trace_function_jump_relative,
-	
-			// BMI
-			0x30 => trace_function_jump_relative,
-	
-			// BVC
-			0x50 => trace_function_jump_relative,
-	
-			// BVS
-			0x70 => trace_function_jump_relative,
-	
-			// BCC
-			0x90 => trace_function_jump_relative,
-	
-			// BCS
-			0xb0 => trace_function_jump_relative,
-	
-			// BNE
-			0xd0 => trace_function_jump_relative,
-	
-			// BEQ
-			0xf0 => trace_function_jump_relative,
-	
-			// BRK
-			0x00 => trace_function,
-	
-			// RTI
-			0x40 => trace_function,
-	
-			// RTS
-			0x60 => trace_function,
-	
-			// KIL
-			0x02 | 0x32 => trace_function,
-	
-			_ => |emulator, opcode| {
-				let pc = emulator.cpu.pc.wrapping_sub(1);
-				warn!("Unknown opcode {:02X} at {:04X}", opcode, pc);
-				trace_function(emulator, opcode);
-			}
+			AddressingMode::AbsoluteX => trace_function_absolute_x,
+			AddressingMode::AbsoluteY => trace_function_absolute_y,
+			AddressingMode::Indirect => trace_function_indirect,
+			AddressingMode::IndirectX => trace_function_indirect_x,
+			AddressingMode::IndirectY => trace_function_indirect_y,
+			AddressingMode::Relative => trace_function_jump_relative
 		}
 	}
 }
 
+// JMP and JSR show the address they jump to, not the byte there
+fn is_jump(opcode: Opcode) -> bool {
+	opcode.mnemonic == "JMP" || opcode.mnemonic == "JSR"
+}
+
 fn create_trace_data(emulator: &Emulator, opcode: u8, opcode_data: Vec<u16>) {
-	let data = Data {
+	let (scanline, dot) = emulator.ppu.get_position();
+	let mut data = Data {
 		pc: emulator.cpu.pc.wrapping_sub(1),
 		opcode,
 		opcode_data,
@@ -457,9 +180,20 @@ fn create_trace_data(emulator: &Emulator, opcode: u8, opcode_data: Vec<u16>) {
 		x: emulator.cpu.x,
 		y: emulator.cpu.y,
 		p: emulator.cpu.p,
-		s: emulator.cpu.s
+		s: emulator.cpu.s,
+		// the opcode has been read
+		cycles: emulator.cpu.total_cycles + emulator.cpu.cycle_counter as u64 - 1,
+		scanline,
+		dot,
+		labelled_instruction: None
 	};
-	emulator.cpu.logger.buffer.borrow_mut().push(data);
+	if let Some(ref logger) = emulator.cpu.logger {
+		// the nestest format has no labels
+		if logger.options.format != TraceFormat::Nestest && !emulator.symbols.is_empty() {
+			data.labelled_instruction = Some(get_instruction(&data).format(&emulator.get_labels()));
+		}
+		logger.push(data);
+	}
 }
 
 fn trace_function(emulator: &Emulator, opcode: u8) {
@@ -575,283 +309,122 @@ fn trace_function_jump_relative(emulator: &Emulator, opcode: u8) {
 	create_trace_data(emulator, opcode, opcode_data);
 }
 
-fn format_instruction(data: &Data) -> String {
-	match data.opcode {
-		0xea => format("NOP"),
-		0x1a | 0x3a | 0x5a | 0x7a | 0xda | 0xfa => format("*NOP"),
-		0x80 => format_immediate(data, "*NOP"),
-		0x04 | 0x44 | 0x64 | 0x82 | 0x89 | 0xc2 | 0xe2 => format_zero_page(data, "*NOP"),
-		0x14 | 0x34 | 0x54 | 0x74 | 0xd4 | 0xf4 => format_zero_page_x(data, "*NOP"),
-		0x0c => format_absolute(data, "*NOP"),
-		0x1c | 0x3c | 0x5c | 0x7c | 0xdc | 0xfc => format_absolute_x(data, "*NOP"),
-			
-		0xa9 => format_immediate(data, "LDA"),
-		0xa5 => format_zero_page(data, "LDA"),
-		0xb5 => format_zero_page_x(data, "LDA"),
-		0xad => format_absolute(data, "LDA"),
-		0xbd => format_absolute_x(data, "LDA"),
-		0xb9 => format_absolute_y(data, "LDA"),
-		0xa1 => format_indirect_x(data, "LDA"),
-		0xb1 => format_indirect_y(data, "LDA"),
-
-		0xa2 => format_immediate(data, "LDX"),
-		0xa6 => format_zero_page(data, "LDX"),
-		0xb6 => format_zero_page_y(data, "LDX"),
-		0xae => format_absolute(data, "LDX"),
-		0xbe => format_absolute_y(data, "LDX"),
-
-		0xa0 => format_immediate(data, "LDY"),
-		0xa4 => format_zero_page(data, "LDY"),
-		0xb4 => format_zero_page_x(data, "LDY"),
-		0xac => format_absolute(data, "LDY"),
-		0xbc => format_absolute_x(data, "LDY"),
-
-		0xab => format_immediate(data, "*LAX"),
-		0xa7 => format_zero_page(data, "*LAX"),
-		0xb7 => format_zero_page_y(data, "*LAX"),
-		0xaf => format_absolute(data, "*LAX"),
-		0xbf => format_absolute_y(data, "*LAX"),
-		0xa3 => format_indirect_x(data, "*LAX"),
-		0xb3 => format_indirect_y(data, "*LAX"),
-
-		0x85 => format_zero_page(data, "STA"),
-		0x95 => format_zero_page_x(data, "STA"),
-		0x8d => format_absolute(data, "STA"),
-		0x9d => format_absolute_x(data, "STA"),
-		0x99 => format_absolute_y(data, "STA"),
-		0x81 => format_indirect_x(data, "STA"),
-		0x91 => format_indirect_y(data, "STA"),
-
-		0x86 => format_zero_page(data, "STX"),
-		0x96 => format_zero_page_y(data, "STX"),
-		0x8e => format_absolute(data, "STX"),
-
-		0x84 => format_zero_page(data, "STY"),
-		0x94 => format_zero_page_x(data, "STY"),
-		0x8c => format_absolute(data, "STY"),
-
-		0x87 => format_zero_page(data, "*SAX"),
-		0x97 => format_zero_page_y(data, "*SAX"),
-		0x8f => format_absolute(data, "*SAX"),
-		0x83 => format_indirect_x(data, "*SAX"),
-
-		0x9e => format_absolute_y(data, "SXA"),
-		0x9c => format_absolute_x(data, "SYA"),
-
-		0xaa => format("TAX"),
-		0x8a => format("TXA"),
-		0xa8 => format("TAY"),
-		0x98 => format("TYA"),
-		0xba => format("TSX"),
-		0x9a => format("TXS"),
-
-		0x29 => format_immediate(data, "AND"),
-		0x25 => format_zero_page(data, "AND"),
-		0x35 => format_zero_page_x(data, "AND"),
-		0x2d => format_absolute(data, "AND"),
-		0x3d => format_absolute_x(data, "AND"),
-		0x39 => format_absolute_y(data, "AND"),
-		0x21 => format_indirect_x(data, "AND"),
-		0x31 => format_indirect_y(data, "AND"),
-
-		0x0b | 0x2b => format_immediate(data, "AAC"),
-		0x4b => format_immediate(data, "ASR"),
-		0x6b => format_immediate(data, "ARR"),
-		0xcb => format_immediate(data, "AXS"),
-
-		0x09 => format_immediate(data, "ORA"),
-		0x05 => format_zero_page(data, "ORA"),
-		0x15 => format_zero_page_x(data, "ORA"),
-		0x0d => format_absolute(data, "ORA"),
-		0x1d => format_absolute_x(data, "ORA"),
-		0x19 => format_absolute_y(data, "ORA"),
-		0x01 => format_indirect_x(data, "ORA"),
-		0x11 => format_indirect_y(data, "ORA"),
-
-		0x49 => format_immediate(data, "EOR"),
-		0x45 => format_zero_page(data, "EOR"),
-		0x55 => format_zero_page_x(data, "EOR"),
-		0x4d => format_absolute(data, "EOR"),
-		0x5d => format_absolute_x(data, "EOR"),
-		0x59 => format_absolute_y(data, "EOR"),
-		0x41 => format_indirect_x(data, "EOR"),
-		0x51 => format_indirect_y(data, "EOR"),
-
-		0x24 => format_zero_page(data, "BIT"),
-		0x2c => format_absolute(data, "BIT"),
-
-		0x4a => format_a("LSR"),
-		0x46 => format_zero_page(data, "LSR"),
-		0x56 => format_zero_page_x(data, "LSR"),
-		0x4e => format_absolute(data, "LSR"),
-		0x5e => format_absolute_x(data, "LSR"),
-
-		0x47 => format_zero_page(data, "*SRE"),
-		0x57 => format_zero_page_x(data, "*SRE"),
-		0x4f => format_absolute(data, "*SRE"),
-		0x5f => format_absolute_x(data, "*SRE"),
-		0x5b => format_absolute_y(data, "*SRE"),
-		0x43 => format_indirect_x(data, "*SRE"),
-		0x53 => format_indirect_y(data, "*SRE"),		
-
-		0x0a => format_a("ASL"),
-		0x06 => format_zero_page(data, "ASL"),
-		0x16 => format_zero_page_x(data, "ASL"),
-		0x0e => format_absolute(data, "ASL"),
-		0x1e => format_absolute_x(data, "ASL"),
-
-		0x07 => format_zero_page(data, "*SLO"),
-		0x17 => format_zero_page_x(data, "*SLO"),
-		0x0f => format_absolute(data, "*SLO"),
-		0x1f => format_absolute_x(data, "*SLO"),
-		0x1b => format_absolute_y(data, "*SLO"),
-		0x03 => format_indirect_x(data, "*SLO"),
-		0x13 => format_indirect_y(data, "*SLO"),
-
-		0x6a => format_a("ROR"),
-		0x66 => format_zero_page(data, "ROR"),
-		0x76 => format_zero_page_x(data, "ROR"),
-		0x6e => format_absolute(data, "ROR"),
-		0x7e => format_absolute_x(data, "ROR"),
-
-		0x67 => format_zero_page(data, "*RRA"),
-		0x77 => format_zero_page_x(data, "*RRA"),
-		0x6f => format_absolute(data, "*RRA"),
-		0x7f => format_absolute_x(data, "*RRA"),
-		0x7b => format_absolute_y(data, "*RRA"),
-		0x63 => format_indirect_x(data, "*RRA"),
-		0x73 => format_indirect_y(data, "*RRA"),
-
-		0x2a => format_a("ROL"),
-		0x26 => format_zero_page(data, "ROL"),
-		0x36 => format_zero_page_x(data, "ROL"),
-		0x2e => format_absolute(data, "ROL"),
-		0x3e => format_absolute_x(data, "ROL"),
-
-		0x27 => format_zero_page(data, "*RLA"), 
-		0x37 => format_zero_page_x(data, "*RLA"),
-		0x2f => format_absolute(data, "*RLA"),
-		0x3f => format_absolute_x(data, "*RLA"),
-		0x3b => format_absolute_y(data, "*RLA"),
-		0x23 => format_indirect_x(data, "*RLA"),
-		0x33 => format_indirect_y(data, "*RLA"),
-
-		0x69 => format_immediate(data, "ADC"),
-		0x65 => format_zero_page(data, "ADC"),
-		0x75 => format_zero_page_x(data, "ADC"),
-		0x6d => format_absolute(data, "ADC"),
-		0x7d => format_absolute_x(data, "ADC"),
-		0x79 => format_absolute_y(data, "ADC"),
-		0x61 => format_indirect_x(data, "ADC"),
-		0x71 => format_indirect_y(data, "ADC"),
-
-		0xe9 => format_immediate(data, "SBC"),
-		0xe5 => format_zero_page(data, "SBC"),
-		0xf5 => format_zero_page_x(data, "SBC"),
-		0xed => format_absolute(data, "SBC"),
-		0xfd => format_absolute_x(data, "SBC"),
-		0xf9 => format_absolute_y(data, "SBC"),
-		0xe1 => format_indirect_x(data, "SBC"),
-		0xf1 => format_indirect_y(data, "SBC"),
-			
-		0xeb => format_immediate(data, "*SBC"),
-
-		0xe8 => format("INX"),
-		0xc8 => format("INY"),
-
-		0xe6 => format_zero_page(data, "INC"),
-		0xf6 => format_zero_page_x(data, "INC"),
-		0xee => format_absolute(data, "INC"),
-		0xfe => format_absolute_x(data, "INC"),
-
-		0xe7 => format_zero_page(data, "*ISB"),
-		0xf7 => format_zero_page_x(data, "*ISB"),
-		0xef => format_absolute(data, "*ISB"),
-		0xff => format_absolute_x(data, "*ISB"),
-		0xfb => format_absolute_y(data, "*ISB"),
-		0xe3 => format_indirect_x(data, "*ISB"),
-		0xf3 => format_indirect_y(data, "*ISB"),
-
-		0xca => format("DEX"),
-		0x88 => format("DEY"),
-
-		0xc6 => format_zero_page(data, "DEC"),
-		0xd6 => format_zero_page_x(data, "DEC"),
-		0xce => format_absolute(data, "DEC"),
-		0xde => format_absolute_x(data, "DEC"),
-
-		0xc7 => format_zero_page(data, "*DCP"),
-		0xd7 => format_zero_page_x(data, "*DCP"),
-		0xcf => format_absolute(data, "*DCP"),
-		0xdf => format_absolute_x(data, "*DCP"),
-		0xdb => format_absolute_y(data, "*DCP"),
-		0xc3 => format_indirect_x(data, "*DCP"),
-		0xd3 => format_indirect_y(data, "*DCP"),
-
-		0xe0 => format_immediate(data, "CPX"),
-		0xe4 => format_zero_page(data, "CPX"),
-		0xec => format_absolute(data, "CPX"),
-
-		0xc0 => format_immediate(data, "CPY"),
-		0xc4 => format_zero_page(data, "CPY"),
-		0xcc => format_absolute(data, "CPY"),
-
-		0xc9 => format_immediate(data, "CMP"),
-		0xc5 => format_zero_page(data, "CMP"),
-		0xd5 => format_zero_page_x(data, "CMP"),
-		0xcd => format_absolute(data, "CMP"),
-		0xdd => format_absolute_x(data, "CMP"),
-		0xd9 => format_absolute_y(data, "CMP"),
-		0xc1 => format_indirect_x(data, "CMP"),
-		0xd1 => format_indirect_y(data, "CMP"),
-
-		0x48 => format("PHA"),
-		0x68 => format("PLA"),
-		0x08 => format("PHP"),
-		0x28 => format("PLP"),
-		0x18 => format("CLC"),
-		0x38 => format("SEC"),
-		0x58 => format("CLI"),
-		0x78 => format("SEI"),
-		0xd8 => format("CLD"),
-		0xf8 => format("SED"),
-		0xb8 => format("CLV"),
-
-		0x4c => format_jump_absolute(data, "JMP"),
-		0x20 => format_jump_absolute(data, "JSR"),
-
-		// JMP (indirect)
-		0x6c => {
-			let address = data.opcode_data[0];
-			let address_low = address & 0xff;
-			let address_high = address >> 8;
-			let effective_address_low = data.opcode_data[1];
-			let effective_address_high = data.opcode_data[2];
-			let effective_address = (effective_address_high << 8) | effective_address_low;
-			format!("{:02X} {:02X}  JMP (${:04X}) = {:04X}", address_low, address_high, address, effective_address)
-		},
-
-		0x10 => format_jump_relative(data, "BPL"),
-		0x30 => format_jump_relative(data, "BMI"),
-		0x50 => format_jump_relative(data, "BVC"),
-		0x70 => format_jump_relative(data, "BVS"),
-		0x90 => format_jump_relative(data, "BCC"),
-		0xb0 => format_jump_relative(data, "BCS"),
-		0xd0 => format_jump_relative(data, "BNE"),
-		0xf0 => format_jump_relative(data, "BEQ"),
-
-		0x00 => format("BRK"),
-
-		0x40 => format("RTI"),
-		0x60 => format("RTS"),
-
-		0x02 | 0x32 => format("KIL"),
+fn trace_function_indirect(emulator: &Emulator, opcode: u8) {
+	let address = read16_debug(emulator, emulator.cpu.pc);
+	let low_byte = read8_debug(emulator, address);
+	let high_byte = read8_debug(emulator, (address & 0xff00) | (address.wrapping_add(1) & 0x00ff));
+	let mut opcode_data = vec![0u16; 3];
+	opcode_data[0] = address;
+	opcode_data[1] = low_byte as _;
+	opcode_data[2] = high_byte as _;
+	create_trace_data(emulator, opcode, opcode_data);
+}
 
-		_ => {
-			warn!("Unknown opcode {:02X} at {:04X}", data.opcode, data.pc);
-			format("???")
+fn format_line(options: &TraceOptions, data: &Data) -> String {
+	let mut line = String::new();
+	match options.format {
+		TraceFormat::Nestest => {
+			line += &format!("{:04X}  {:02X} {:<38} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}", data.pc, data.opcode, format_instruction(data), data.a, data.x, data.y, data.p, data.s);
+			if options.ppu_position {
+				line += &format!(" PPU:{:>3},{:>3}", data.dot, data.scanline);
+			}
+			if options.cpu_cycles {
+				line += &format!(" CYC:{}", data.cycles);
+			}
+		},
+		TraceFormat::Fceux => {
+			if options.cpu_cycles {
+				line += &format!("c{:<11}", data.cycles);
+			}
+			if options.ppu_position {
+				line += &format!("PPU:{:>3},{:>3} ", data.dot, data.scanline);
+			}
+			let instruction = get_instruction(data);
+			line += &format!("A:{:02X} X:{:02X} Y:{:02X} S:{:02X} P:{}  ${:04X}: {:<9} {}", data.a, data.x, data.y, data.s, format_flags(data.p), data.pc, instruction.format_bytes(), format_access(data, &instruction, TraceFormat::Fceux));
+		},
+		TraceFormat::Mesen => {
+			let instruction = get_instruction(data);
+			line += &format!("{:04X}  {:<40} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}", data.pc, format_access(data, &instruction, TraceFormat::Mesen), data.a, data.x, data.y, data.p, data.s);
+			if options.ppu_position {
+				line += &format!(" CYC:{:>3} SL:{:>3}", data.dot, data.scanline);
+			}
+			if options.cpu_cycles {
+				line += &format!(" CPU Cycle:{}", data.cycles);
+			}
 		}
 	}
+	line + "\n"
+}
+
+fn get_instruction(data: &Data) -> Instruction {
+	// the operand is the first value traced in every addressing mode
+	let operand = data.opcode_data.first().cloned().unwrap_or(0);
+	let bytes = [data.opcode, operand as u8, (operand >> 8) as u8];
+	Instruction::decode(data.pc, &bytes).unwrap()
+}
+
+// NVUBDIZC, in uppercase when set
+fn format_flags(p: u8) -> String {
+	"NVUBDIZC".chars().enumerate().map(|(index, flag)| if (p << index) & 0x80 != 0 {
+		flag
+	} else {
+		flag.to_ascii_lowercase()
+	}).collect()
+}
+
+// the instruction with the address it accesses after indexing and the value there, in the style of the format
+fn format_access(data: &Data, instruction: &Instruction, format: TraceFormat) -> String {
+	let text = data.labelled_instruction.clone().unwrap_or_else(|| instruction.format(&NoLabels));
+	let (effective_address, value) = match instruction.opcode.mode {
+		AddressingMode::ZeroPage => (None, data.opcode_data[1]),
+		AddressingMode::Absolute if !is_jump(instruction.opcode) => (None, data.opcode_data[1]),
+		AddressingMode::ZeroPageX | AddressingMode::ZeroPageY | AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => (Some(data.opcode_data[1]), data.opcode_data[2]),
+		AddressingMode::IndirectX | AddressingMode::IndirectY => (Some(data.opcode_data[2]), data.opcode_data[3]),
+		AddressingMode::Indirect => {
+			let target = (data.opcode_data[2] << 8) | data.opcode_data[1];
+			return format!("{} = ${:04X}", text, target);
+		},
+		_ => return text
+	};
+	match format {
+		TraceFormat::Mesen => match effective_address {
+			Some(address) => format!("{} [${:04X}] = ${:02X}", text, address, value),
+			None => format!("{} = ${:02X}", text, value)
+		},
+		_ => match effective_address {
+			Some(address) => format!("{} @ ${:04X} = #${:02X}", text, address, value),
+			None => format!("{} = #${:02X}", text, value)
+		}
+	}
+}
+
+fn format_instruction(data: &Data) -> String {
+	let opcode = get_opcode(data.opcode);
+	let mnemonic = if opcode.official {
+		opcode.mnemonic.to_string()
+	} else {
+		format!("*{}", opcode.mnemonic)
+	};
+	let mnemonic = mnemonic.as_str();
+	match opcode.mode {
+		AddressingMode::Implied => format(mnemonic),
+		AddressingMode::Accumulator => format_a(mnemonic),
+		AddressingMode::Immediate => format_immediate(data, mnemonic),
+		AddressingMode::ZeroPage => format_zero_page(data, mnemonic),
+		AddressingMode::ZeroPageX => format_zero_page_x(data, mnemonic),
+		AddressingMode::ZeroPageY => format_zero_page_y(data, mnemonic),
+		AddressingMode::Absolute => if is_jump(opcode) {
+			format_jump_absolute(data, mnemonic)
+		} else {
+			format_absolute(data, mnemonic)
+		},
+		AddressingMode::AbsoluteX => format_absolute_x(data, mnemonic),
+		AddressingMode::AbsoluteY => format_absolute_y(data, mnemonic),
+		AddressingMode::Indirect => format_indirect(data, mnemonic),
+		AddressingMode::IndirectX => format_indirect_x(data, mnemonic),
+		AddressingMode::IndirectY => format_indirect_y(data, mnemonic),
+		AddressingMode::Relative => format_jump_relative(data, mnemonic)
+	}
 }
 
 fn format(mnemonic: &str) -> String {
@@ -912,6 +485,15 @@ fn format_absolute_y(data: &Data, mnemonic: &str) -> String {
 	let operand = data.opcode_data[2];
 	format!("{:02X} {:02X}{:>5} ${:04X},Y @ {:04X} = {:02X}", low_byte, high_byte, mnemonic, address, effective_address, operand)
 }
+fn format_indirect(data: &Data, mnemonic: &str) -> String {
+	let address = data.opcode_data[0];
+	let low_byte = address & 0xff;
+	let high_byte = address >> 8;
+	let effective_address_low = data.opcode_data[1];
+	let effective_address_high = data.opcode_data[2];
+	let effective_address = (effective_address_high << 8) | effective_address_low;
+	format!("{:02X} {:02X}{:>5} (${:04X}) = {:04X}", low_byte, high_byte, mnemonic, address, effective_address)
+}
 
 fn format_indirect_x(data: &Data, mnemonic: &str) -> String {
 	let immediate = data.opcode_data[0];
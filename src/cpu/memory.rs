@@ -1,5 +1,10 @@
 use emulator::*;
 use ppu::registers::*;
+use cdl;
+use events::{self, Event, EventKind};
+
+#[cfg(any(feature = "debugger", feature = "scripting"))]
+use accesses::{Bus, AccessKind};
 
 const RAM_START: u16 = 0;
 const RAM_END: u16 = 0x1fff;
@@ -19,6 +24,62 @@ const MAPPER_START: u16 = 0x6000;
 const MAPPER_END: u16 = 0xffff;
 
 pub(super) fn read8(emulator: &mut Emulator, address: u16) -> u8 {
+	let address = get_effective_address(address);
+	begin_cycle(emulator, address);
+	let value = read_effective_address(emulator, address);
+	#[cfg(feature = "debugger")]
+	emulator.memory_accesses.record(Bus::Cpu, AccessKind::Read, address, value);
+	#[cfg(feature = "scripting")]
+	emulator.script_accesses.record(Bus::Cpu, AccessKind::Read, address, value);
+	if emulator.code_data_log.is_some() {
+		log_data(emulator, address);
+	}
+	if emulator.event_log.is_some() && events::is_register(EventKind::Read, address) {
+		log_event(emulator, EventKind::Read, address, value);
+	}
+	value
+}
+
+// the bytes of an instruction are logged when it starts
+pub(super) fn log_code(emulator: &mut Emulator, address: u16, size: u16, flags: u8) {
+	for address in (0..size).map(|offset| address.wrapping_add(offset)) {
+		if let Some(offset) = emulator.get_prg_rom_offset(address) {
+			emulator.code_data_log.as_mut().unwrap().log_prg_rom(offset, address, flags);
+		}
+	}
+}
+
+fn log_data(emulator: &mut Emulator, address: u16) {
+	if address.wrapping_sub(emulator.cpu.instruction_address) < emulator.cpu.instruction_size {
+		return;
+	}
+	let flags = if emulator.cpu.indirect_addressing {
+		cdl::DATA | cdl::INDIRECT_DATA
+	} else {
+		cdl::DATA
+	};
+	if let Some(offset) = emulator.get_prg_rom_offset(address) {
+		emulator.code_data_log.as_mut().unwrap().log_prg_rom(offset, address, flags);
+	}
+}
+
+// the position of the PPU is the one of the cycle accessing the register
+fn log_event(emulator: &mut Emulator, kind: EventKind, address: u16, value: u8) {
+	emulator.synchronize_ppu(emulator.cpu.cycle_counter - 1);
+	let (scanline, dot) = emulator.ppu.get_position();
+	let frame = emulator.ppu.get_frame_counter();
+	let event = Event {
+		kind,
+		address,
+		value,
+		pc: emulator.cpu.instruction_address,
+		scanline,
+		dot
+	};
+	emulator.event_log.as_mut().unwrap().record(frame, event);
+}
+
+fn read_effective_address(emulator: &mut Emulator, address: u16) -> u8 {
 	match address {
 		RAM_START ..= RAM_END => emulator.ram[(address - RAM_START) as usize % RAM_SIZE],
 		PPUCTRL_ADDRESS => 0, // write only
@@ -28,8 +89,13 @@ pub(super) fn read8(emulator: &mut Emulator, address: u16) -> u8 {
 		OAMDATA_ADDRESS => read_oamdata(&mut emulator.ppu),
 		PPUSCROLL_ADDRESS => 0, // write only
 		PPUADDR_ADDRESS => 0, // write only
-		PPUDATA_ADDRESS => read_ppudata(&mut emulator.ppu),
-		0x2008 ..= 0x3fff => read8(emulator, 0x2000 + (address - 0x2000) % 8), // mirrors of 0x2000-0x2007
+		PPUDATA_ADDRESS => {
+			if let Some(ref mut code_data_log) = emulator.code_data_log {
+				code_data_log.log_chr_rom(emulator.ppu.get_ppuaddr(), cdl::READ);
+			}
+			read_ppudata(&mut emulator.ppu)
+		},
+		0x2008 ..= 0x3fff => unreachable!(), // mirrored by get_effective_address
 		OAMDMA_ADDRESS => 0, // write only
 		0x4000 ..= 0x4013 | 0x4015 | 0x4017 => {
 			// TODO: implement APU registers
@@ -58,7 +124,6 @@ pub(super) fn read16_zeropage(emulator: &mut Emulator, address: u8) -> u16 {
 	(high_byte << 8) | low_byte
 }
 
-#[cfg(any(feature = "trace", test))]
 pub(super) fn read8_debug(emulator: &Emulator, address: u16) -> u8 {
 	match address {
 		RAM_START ..= RAM_END => emulator.ram[(address - RAM_START) as usize % RAM_SIZE],
@@ -78,14 +143,12 @@ pub(super) fn read8_debug(emulator: &Emulator, address: u16) -> u8 {
 	}
 }
 
-#[cfg(feature = "trace")]
 pub(super) fn read16_debug(emulator: &Emulator, address: u16) -> u16 {
 	let low_byte = read8_debug(emulator, address) as u16;
 	let high_byte = read8_debug(emulator, address.wrapping_add(1)) as u16;
 	(high_byte << 8) | low_byte
 }
 
-#[cfg(feature = "trace")]
 pub(super) fn read16_zeropage_debug(emulator: &Emulator, address: u8) -> u16 {
 	let low_byte = read8_debug(emulator, address as _) as u16;
 	let high_byte = read8_debug(emulator, address.wrapping_add(1) as _) as u16;
@@ -93,6 +156,24 @@ pub(super) fn read16_zeropage_debug(emulator: &Emulator, address: u8) -> u16 {
 }
 
 pub(super) fn write(emulator: &mut Emulator, address: u16, value: u8) {
+	let address = get_effective_address(address);
+	begin_cycle(emulator, address);
+	#[cfg(feature = "debugger")]
+	emulator.memory_accesses.record(Bus::Cpu, AccessKind::Write, address, value);
+	#[cfg(feature = "scripting")]
+	emulator.script_accesses.record(Bus::Cpu, AccessKind::Write, address, value);
+	if emulator.event_log.is_some() && events::is_register(EventKind::Write, address) {
+		log_event(emulator, EventKind::Write, address, value);
+	}
+	write_effective_address(emulator, address, value);
+}
+
+// the write doesn't take a CPU cycle, but writes to registers keep their side effects
+pub(super) fn write_debug(emulator: &mut Emulator, address: u16, value: u8) {
+	write_effective_address(emulator, get_effective_address(address), value);
+}
+
+fn write_effective_address(emulator: &mut Emulator, address: u16, value: u8) {
 	match address {
 		RAM_START ..= RAM_END => emulator.ram[(address - RAM_START) as usize % RAM_SIZE] = value,
 		PPUCTRL_ADDRESS => write_ppuctrl(&mut emulator.ppu, value),
@@ -103,7 +184,7 @@ pub(super) fn write(emulator: &mut Emulator, address: u16, value: u8) {
 		PPUSCROLL_ADDRESS => write_ppuscroll(&mut emulator.ppu, value),
 		PPUADDR_ADDRESS => write_ppuaddr(&mut emulator.ppu, value),
 		PPUDATA_ADDRESS => write_ppudata(&mut emulator.ppu, value),
-		0x2008 ..= 0x3fff => write(emulator, 0x2000 + (address - 0x2000) % 8, value), // mirrors of 0x2000-0x2007
+		0x2008 ..= 0x3fff => unreachable!(), // mirrored by get_effective_address
 		OAMDMA_ADDRESS => write_oamdma(emulator, value),
 		0x4000 ..= 0x4013 | 0x4015 | 0x4017 => {
 			// TODO: implement APU registers
@@ -117,3 +198,18 @@ pub(super) fn write(emulator: &mut Emulator, address: u16, value: u8) {
 		MAPPER_START ..= MAPPER_END => emulator.mapper.as_mut().unwrap().write(address, value)
 	}
 }
+
+fn get_effective_address(address: u16) -> u16 {
+	match address {
+		0x2008 ..= 0x3fff => 0x2000 + (address - 0x2000) % 8, // mirrors of 0x2000-0x2007
+		_ => address
+	}
+}
+
+// every bus access takes one CPU cycle; the PPU has to catch up before one of its registers is accessed
+fn begin_cycle(emulator: &mut Emulator, address: u16) {
+	emulator.cpu.cycle_counter += 1;
+	if let PPUCTRL_ADDRESS ..= PPUDATA_ADDRESS = address {
+		emulator.synchronize_ppu(emulator.cpu.cycle_counter - 1);
+	}
+}
@@ -4,25 +4,38 @@ mod addressing_modes;
 #[cfg(test)]
 pub mod tests;
 
-#[cfg(feature = "trace")]
 mod logger;
 
-use std::mem::{self, MaybeUninit};
+use std::{
+	mem::{self, MaybeUninit},
+	io,
+	path::Path
+};
 use emulator::*;
+use disasm::get_opcode;
+use cdl;
+use profiler::CallKind;
 
 use self::{
 	memory::*,
 	addressing_modes::*
 };
 
-#[cfg(feature = "trace")]
 use self::logger::*;
 
+pub use self::logger::{TraceFormat, TraceOptions};
+
 const STACK_ADDRESS: u16 = 0x100;
 const NMI_VECTOR_ADDRESS: u16 = 0xfffa;
 const RESET_VECTOR_ADDRESS: u16 = 0xfffc;
 const IRQ_VECTOR_ADDRESS: u16 = 0xfffe;
 
+const INTERRUPT_CYCLES: u8 = 7;
+
+const BRK_OPCODE: u8 = 0x00;
+const JSR_OPCODE: u8 = 0x20;
+const JMP_INDIRECT_OPCODE: u8 = 0x6c;
+
 const OPCODE_COUNT: usize = 0x100;
 
                                         // 0  1  2  3  4  5  6  7  8  9  a  b  c  d  e  f
@@ -71,7 +84,18 @@ enum Flag {
 	N = 1 << 7
 }
 
-#[derive(PartialEq)]
+// CPU state for tools
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Registers {
+	pub a: u8,
+	pub x: u8,
+	pub y: u8,
+	pub pc: u16,
+	pub s: u8,
+	pub p: u8
+}
+
+#[derive(Copy, Clone, PartialEq)]
 pub enum Interrupt {
 	Irq,
 	Nmi
@@ -82,8 +106,6 @@ struct LookupTableEntry {
 	instruction: fn(&mut Emulator),
 	cycles: u8,
 	page_crossing_cycles: u8,
-
-	#[cfg(feature = "trace")]
 	trace_function: fn(&Emulator, u8)
 }
 
@@ -95,12 +117,19 @@ pub struct Cpu {
 	s: u8,
 	p: u8,
 	pending_interrupt: Option<Interrupt>,
+	interrupt_polled: bool,
 	lookup_table: [LookupTableEntry; OPCODE_COUNT],
 	page_crossed: bool,
 	branch_taken: bool,
-
-	#[cfg(feature = "trace")]
-	logger: Logger
+	cycle_counter: u8,
+	// cycles of the instructions executed since the power on
+	total_cycles: u64,
+	// the bytes of the current instruction, which the code/data log doesn't count as data, the address being the PC of the register events
+	instruction_address: u16,
+	instruction_size: u16,
+	// the data is read through a pointer
+	indirect_addressing: bool,
+	logger: Option<Logger>
 }
 
 impl Cpu {
@@ -115,8 +144,6 @@ impl Cpu {
 					instruction: get_instruction(opcode as _),
 					cycles: OPCODE_CYCLES[opcode],
 					page_crossing_cycles: PAGE_CROSSING_OPCODE_CYCLES[opcode],
-
-					#[cfg(feature = "trace")]
 					trace_function: Logger::get_trace_function(opcode as _)
 				});
 			}
@@ -132,18 +159,107 @@ impl Cpu {
 			s: 0xfd,
 			p: 0x24,
 			pending_interrupt: None,
+			interrupt_polled: false,
 			lookup_table,
 			page_crossed: false,
 			branch_taken: false,
+			cycle_counter: 0,
+			// the reset sequence
+			total_cycles: INTERRUPT_CYCLES as _,
+			instruction_address: 0,
+			instruction_size: 0,
+			indirect_addressing: false,
+			logger: None
+		}
+	}
+
+	pub fn init_pc(emulator: &mut Emulator) {
+		emulator.cpu.pc = read16(emulator, RESET_VECTOR_ADDRESS);
+		info!("PC: {:04X}", emulator.cpu.pc);
+	}
 
-			#[cfg(feature = "trace")]
-			logger: Logger::new()
+	// the reset button keeps A, X and Y, the reset sequence taking 3 bytes from the stack without writing them and disabling the interrupts
+	pub fn reset(emulator: &mut Emulator) {
+		let cpu = &mut emulator.cpu;
+		cpu.s = cpu.s.wrapping_sub(3);
+		cpu.set_flag(Flag::I, true);
+		cpu.pending_interrupt = None;
+		cpu.interrupt_polled = false;
+		cpu.total_cycles += INTERRUPT_CYCLES as u64;
+		Self::init_pc(emulator);
+	}
+
+	// reads memory without the side effects of a CPU read, for tools
+	pub fn read8_debug(emulator: &Emulator, address: u16) -> u8 {
+		read8_debug(emulator, address)
+	}
+
+	// writes memory without taking a CPU cycle, for tools
+	pub fn write_debug(emulator: &mut Emulator, address: u16, value: u8) {
+		write_debug(emulator, address, value)
+	}
+
+	// a copy of the CPU for save states, without the trace logger
+	pub fn save_state(&self) -> Self {
+		Self {
+			a: self.a,
+			x: self.x,
+			y: self.y,
+			pc: self.pc,
+			s: self.s,
+			p: self.p,
+			pending_interrupt: self.pending_interrupt,
+			interrupt_polled: self.interrupt_polled,
+			lookup_table: self.lookup_table,
+			page_crossed: self.page_crossed,
+			branch_taken: self.branch_taken,
+			cycle_counter: self.cycle_counter,
+			total_cycles: self.total_cycles,
+			instruction_address: self.instruction_address,
+			instruction_size: self.instruction_size,
+			indirect_addressing: self.indirect_addressing,
+			logger: None
 		}
 	}
 
-	pub fn init_pc(&mut self) {
-		self.pc = read16(emulator, RESET_VECTOR_ADDRESS);
-		info!("PC: {:04X}", self.pc);
+	// the trace in progress goes on
+	pub fn load_state(&mut self, state: &Self) {
+		let logger = self.logger.take();
+		*self = state.save_state();
+		self.logger = logger;
+	}
+
+	pub fn get_registers(&self) -> Registers {
+		Registers {
+			a: self.a,
+			x: self.x,
+			y: self.y,
+			pc: self.pc,
+			s: self.s,
+			p: self.p
+		}
+	}
+
+	pub fn set_registers(&mut self, registers: Registers) {
+		self.a = registers.a;
+		self.x = registers.x;
+		self.y = registers.y;
+		self.pc = registers.pc;
+		self.s = registers.s;
+		self.p = registers.p;
+	}
+
+	// the next step services the polled interrupt before executing the first instruction of its handler
+	pub fn get_next_instruction_address(emulator: &Emulator) -> u16 {
+		let cpu = &emulator.cpu;
+		if cpu.interrupt_polled {
+			match cpu.pending_interrupt {
+				Some(Interrupt::Nmi) => return read16_debug(emulator, NMI_VECTOR_ADDRESS),
+				Some(Interrupt::Irq) if !cpu.get_flag(Flag::I) => return read16_debug(emulator, IRQ_VECTOR_ADDRESS),
+				_ => {}
+			}
+		}
+		cpu.pc
 	}
 
 	fn check_page_crossing(&mut self, address_a: u16, address_b: u16) {
@@ -233,31 +349,86 @@ impl Cpu {
 		}
 	}
 
-	pub fn execute_next_instruction(&mut self) -> u8 {
-		/*match self.pending_interrupt {
-			Some(Interrupt::Nmi) => perform_interrupt(emulator, NMI_VECTOR_ADDRESS),
-			Some(Interrupt::Irq) => if !self.get_flag(Flag::I) {
-				perform_interrupt(emulator, IRQ_VECTOR_ADDRESS);
-			},
-			None => {}
+	// interrupts requested during the last cycle of an instruction are only noticed after the next one
+	pub fn poll_interrupts(&mut self) {
+		self.interrupt_polled = self.pending_interrupt.is_some();
+	}
+
+	pub fn execute_next_instruction(emulator: &mut Emulator) -> u8 {
+		emulator.cpu.cycle_counter = 0;
+		let mut interrupt = None;
+		if emulator.cpu.interrupt_polled {
+			match emulator.cpu.pending_interrupt {
+				Some(Interrupt::Nmi) => {
+					perform_interrupt(emulator, NMI_VECTOR_ADDRESS);
+					interrupt = Some(CallKind::Nmi);
+				},
+				Some(Interrupt::Irq) if !emulator.cpu.get_flag(Flag::I) => {
+					perform_interrupt(emulator, IRQ_VECTOR_ADDRESS);
+					interrupt = Some(CallKind::Irq);
+				},
+				_ => {}
+			}
+			emulator.cpu.interrupt_polled = false;
 		}
+		let interrupt_cycles = emulator.cpu.cycle_counter;
+		// the handler and the stack pointer once the return address and the flags are pushed
+		let interrupt = interrupt.map(|kind| (kind, emulator.cpu.pc, emulator.cpu.s));
 		
+		// the opcode fetch isn't logged as data
+		emulator.cpu.instruction_address = emulator.cpu.pc;
+		emulator.cpu.instruction_size = 1;
 		let opcode = read_next8(emulator);
-		let entry = self.lookup_table[opcode as usize];
+		let entry = emulator.cpu.lookup_table[opcode as usize];
 
-		#[cfg(feature = "trace")]
-		(entry.trace_function)(emulator, opcode);
+		if emulator.code_data_log.is_some() {
+			emulator.cpu.instruction_size = get_opcode(opcode).mode.get_size();
+			log_code(emulator, emulator.cpu.instruction_address, emulator.cpu.instruction_size, cdl::CODE);
+		}
 
-		self.branch_taken = false;
-		self.page_crossed = false;
+		if emulator.cpu.logger.is_some() {
+			// the PPU position is traced at the start of the instruction
+			let cycle = emulator.cpu.cycle_counter - 1;
+			emulator.synchronize_ppu(cycle);
+			(entry.trace_function)(emulator, opcode);
+		}
+
+		emulator.cpu.branch_taken = false;
+		emulator.cpu.page_crossed = false;
+		emulator.cpu.indirect_addressing = false;
 		(entry.instruction)(emulator);
+		if opcode == JMP_INDIRECT_OPCODE && emulator.code_data_log.is_some() {
+			log_code(emulator, emulator.cpu.pc, 1, cdl::INDIRECT_CODE);
+		}
 		
-		(if self.page_crossed {
+		let cycles = interrupt_cycles + (if emulator.cpu.page_crossed {
 			entry.page_crossing_cycles
 		} else {
 			entry.cycles
-		}) + self.branch_taken as u8*/
-		0
+		}) + emulator.cpu.branch_taken as u8;
+		emulator.cpu.total_cycles += cycles as u64;
+		if emulator.profiler.is_some() {
+			profile(emulator, opcode, interrupt, cycles);
+		}
+		cycles
+	}
+
+	// logs every instruction executed until stop_trace is called, replacing any trace in progress
+	pub fn start_trace<P: AsRef<Path>>(&mut self, path: P, options: TraceOptions) -> io::Result<()> {
+		self.stop_trace()?;
+		self.logger = Some(Logger::new(path.as_ref(), options)?);
+		Ok(())
+	}
+
+	pub fn stop_trace(&mut self) -> io::Result<()> {
+		match self.logger.take() {
+			Some(logger) => logger.finish(),
+			None => Ok(())
+		}
+	}
+
+	pub fn is_tracing(&self) -> bool {
+		self.logger.is_some()
 	}
 }
 
@@ -689,6 +860,34 @@ fn perform_interrupt(emulator: &mut Emulator, address: u16) {
 	emulator.cpu.pc = read16(emulator, address);
 	emulator.cpu.set_flag(Flag::I, true);
 	emulator.cpu.pending_interrupt = None;
+	emulator.cpu.cycle_counter = INTERRUPT_CYCLES;
+}
+
+// attributes the cycles of an instruction, and of the interrupt serviced before it, then follows the call it made or returned from
+fn profile(emulator: &mut Emulator, opcode: u8, interrupt: Option<(CallKind, u16, u8)>, cycles: u8) {
+	let frame = emulator.ppu.get_frame_counter();
+	let interrupt = interrupt.map(|(kind, address, stack_pointer)| (kind, address, emulator.get_prg_rom_offset(address), stack_pointer));
+	let (pc, s) = (emulator.cpu.pc, emulator.cpu.s);
+	let prg_rom_offset = emulator.get_prg_rom_offset(pc);
+
+	let profiler = emulator.profiler.as_mut().unwrap();
+	profiler.set_frame(frame);
+	if let Some((kind, address, offset, stack_pointer)) = interrupt {
+		profiler.enter(address, offset, kind, stack_pointer);
+	}
+	profiler.add_cycles(cycles as u64);
+	match opcode {
+		JSR_OPCODE => profiler.enter(pc, prg_rom_offset, CallKind::Subroutine, s),
+		BRK_OPCODE => profiler.enter(pc, prg_rom_offset, CallKind::Irq, s),
+		_ => {}
+	}
+
+	if let Some(frame) = profiler.leave(s) {
+		// the position of the PPU at the last cycle of the RTI
+		emulator.synchronize_ppu(cycles - 1);
+		let overrun = !emulator.ppu.is_in_vblank();
+		emulator.profiler.as_mut().unwrap().end_nmi(frame, emulator.ppu.get_position(), overrun);
+	}
 }
 
 fn push8(emulator: &mut Emulator, value: u8) {
@@ -1,4 +1,5 @@
 use super::*;
+use disasm::*;
 
 use std::{
 	fs::File,
@@ -51,17 +52,93 @@ fn nestest() {
 	}
 }
 
-fn run_test(filename: &str) {
+#[test]
+fn nestest_disassembly() {
 	let mut emulator = Emulator::new();
-	emulator.load_file(filename);
-	Cpu::init_pc(&mut emulator);
-	while read8_debug(&emulator, 0x6000) != 0x80 {
+	emulator.load_file("tests/cpu/nestest/nestest.nes");
+	emulator.cpu.pc = 0xc000;
+
+	// some instructions are written to RAM while the test runs
+	let log_file = File::open("tests/cpu/nestest/nestest.log").unwrap();
+	for log in BufReader::new(log_file).lines().map(|line| line.unwrap()) {
+		let instruction = &disassemble_memory(|address| read8_debug(&emulator, address), emulator.cpu.pc, 1)[0];
+
+		let expected_bytes = log[6..14].trim_end();
+		assert_eq!(instruction.format_bytes(), expected_bytes);
+
+		let official = &log[15..16] != "*";
+		assert_eq!(instruction.opcode.official, official, "{}", log);
+
+		let expected_mnemonic = &log[16..19];
+		assert_eq!(instruction.opcode.mnemonic, expected_mnemonic);
+
 		Cpu::execute_next_instruction(&mut emulator);
 	}
-	while read8_debug(&emulator, 0x6000) == 0x80 {
+}
+
+#[test]
+fn nestest_code_data_log() {
+	let mut emulator = Emulator::new();
+	emulator.load_file("tests/cpu/nestest/nestest.nes");
+	emulator.cpu.pc = 0xc000;
+	// the log is never saved
+	emulator.start_code_data_log("tests/cpu/nestest/nestest-test.cdl").unwrap();
+
+	// JMP $C5F5, LDX #$00, STX $00 and STX $10, the 16KB of PRG ROM being mapped at $C000
+	for _ in 0..4 {
 		Cpu::execute_next_instruction(&mut emulator);
 	}
-	assert_eq!(read8_debug(&emulator, 0x6000), 0);
+	let flags = emulator.code_data_log.as_ref().unwrap().get_prg_rom_flags();
+	for &address in &[0xc000, 0xc001, 0xc002, 0xc5f5, 0xc5f6, 0xc5f7, 0xc5f8, 0xc5f9, 0xc5fa] {
+		assert_eq!(flags[address - 0xc000] & (cdl::CODE | cdl::DATA), cdl::CODE, "{:04X}", address);
+	}
+	assert_eq!(flags[0x0003], 0);
+}
+
+// the protocol of blargg's test ROMs, the status at $6000 being valid once the signature follows it
+const STATUS_ADDRESS: u16 = 0x6000;
+const SIGNATURE_ADDRESS: u16 = 0x6001;
+const SIGNATURE: [u8; 3] = [0xde, 0xb0, 0x61];
+const TEXT_ADDRESS: u16 = 0x6004;
+const STATUS_RUNNING: u8 = 0x80;
+const STATUS_RESET_REQUIRED: u8 = 0x81;
+
+// the reset button is pressed 100 ms after the test asks for it, and a test still running after a minute has hung
+const RESET_DELAY_CYCLES: u64 = 1_789_773 / 10;
+const TIMEOUT_CYCLES: u64 = 1_789_773 * 60;
+
+// the zero-terminated text the test writes at $6004
+fn read_test_text(emulator: &Emulator) -> String {
+	(TEXT_ADDRESS..0x8000).map(|address| read8_debug(emulator, address)).take_while(|&character| character != 0).map(|character| character as char).collect()
+}
+
+// runs a test ROM until it writes its result code at $6000, 0 meaning it passed, with its text in the failure message
+pub fn run_test(filename: &str) {
+	let mut emulator = Emulator::new();
+	emulator.load_file(filename);
+	let mut reset_cycles = None;
+	loop {
+		emulator.step();
+		assert!(emulator.cpu.total_cycles < TIMEOUT_CYCLES, "{} timed out\n{}", filename, read_test_text(&emulator));
+		if (0..SIGNATURE.len()).any(|index| read8_debug(&emulator, SIGNATURE_ADDRESS + index as u16) != SIGNATURE[index]) {
+			continue;
+		}
+		match read8_debug(&emulator, STATUS_ADDRESS) {
+			STATUS_RUNNING => {},
+			STATUS_RESET_REQUIRED => match reset_cycles {
+				None => reset_cycles = Some(emulator.cpu.total_cycles + RESET_DELAY_CYCLES),
+				Some(cycles) if emulator.cpu.total_cycles >= cycles => {
+					emulator.reset();
+					reset_cycles = None;
+				},
+				Some(_) => {}
+			},
+			status => {
+				assert_eq!(status, 0, "{} failed\n{}", filename, read_test_text(&emulator));
+				return;
+			}
+		}
+	}
 }
 
 #[test]
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use super::{Bus, AccessKind, MemoryAccess, Condition, parse_hex};
+
+// names accepted instead of the addresses of the registers
+const REGISTER_ADDRESSES: [(&str, u16); 12] = [
+	("PPUCTRL", 0x2000),
+	("PPUMASK", 0x2001),
+	("PPUSTATUS", 0x2002),
+	("OAMADDR", 0x2003),
+	("OAMDATA", 0x2004),
+	("PPUSCROLL", 0x2005),
+	("PPUADDR", 0x2006),
+	("PPUDATA", 0x2007),
+	("OAMDMA", 0x4014),
+	("SND_CHN", 0x4015),
+	("JOY1", 0x4016),
+	("JOY2", 0x4017)
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Breakpoint {
+	pub bus: Bus,
+	pub start: u16,
+	pub end: u16,
+	pub read: bool,
+	pub write: bool,
+	pub execute: bool,
+	pub condition: Option<Condition>,
+	// prints the accesses instead of pausing the emulation
+	pub log: bool,
+	// accesses in the range, whether the condition was true or not
+	pub hits: usize
+}
+
+impl Breakpoint {
+	// from "<range> [rwx] [if <condition>]", the range being an address, a register name or "start-end", prefixed by "ppu:" for the PPU bus
+	pub fn parse(arguments: &[&str], log: bool) -> Result<Self, String> {
+		let (range, arguments) = arguments.split_first().ok_or("Missing address range")?;
+		let (bus, range) = match range.to_lowercase().strip_prefix("ppu:") {
+			Some(range) => (Bus::Ppu, range.to_string()),
+			None => (Bus::Cpu, range.to_string())
+		};
+		let (start, end) = match range.split_once('-') {
+			Some((start, end)) => (parse_address(start)?, parse_address(end)?),
+			None => {
+				let address = parse_address(&range)?;
+				(address, address)
+			}
+		};
+		if start > end {
+			return Err(format!("Empty address range: {}", range));
+		}
+
+		let (kinds, arguments) = match arguments.split_first() {
+			Some((kinds, arguments)) if *kinds != "if" => (kinds.to_lowercase(), arguments),
+			_ => ("x".to_string(), arguments)
+		};
+		if kinds.is_empty() || !kinds.chars().all(|kind| "rwx".contains(kind)) {
+			return Err(format!("Not a combination of r, w and x: {}", kinds));
+		}
+		if bus == Bus::Ppu && kinds.contains('x') {
+			return Err("The PPU bus can only be read or written".to_string());
+		}
+
+		let condition = match arguments.split_first() {
+			Some((&"if", condition)) if !condition.is_empty() => Some(condition.join(" ").parse()?),
+			None => None,
+			_ => return Err("Expected if <condition> after the address range".to_string())
+		};
+
+		Ok(Self {
+			bus,
+			start,
+			end,
+			read: kinds.contains('r'),
+			write: kinds.contains('w'),
+			execute: kinds.contains('x'),
+			condition,
+			log,
+			hits: 0
+		})
+	}
+
+	pub fn matches(&self, access: &MemoryAccess) -> bool {
+		let kind = match access.kind {
+			AccessKind::Read => self.read,
+			AccessKind::Write => self.write,
+			AccessKind::Execute => self.execute
+		};
+		kind && access.bus == self.bus && access.address >= self.start && access.address <= self.end
+	}
+
+	pub fn is_watchpoint(&self) -> bool {
+		self.read || self.write
+	}
+}
+
+impl fmt::Display for Breakpoint {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		if self.bus == Bus::Ppu {
+			write!(formatter, "PPU ")?;
+		}
+		write!(formatter, "{:04X}", self.start)?;
+		if self.end != self.start {
+			write!(formatter, "-{:04X}", self.end)?;
+		}
+		let kinds: String = [(self.read, 'r'), (self.write, 'w'), (self.execute, 'x')].iter().filter(|(enabled, _)| *enabled).map(|(_, kind)| *kind).collect();
+		write!(formatter, " {}", kinds)?;
+		if let Some(ref condition) = self.condition {
+			write!(formatter, " if {}", condition)?;
+		}
+		if self.log {
+			write!(formatter, ", logged")?;
+		}
+		write!(formatter, ", {} hits", self.hits)
+	}
+}
+
+fn parse_address(address: &str) -> Result<u16, String> {
+	match REGISTER_ADDRESSES.iter().find(|(name, _)| name.eq_ignore_ascii_case(address)) {
+		Some(&(_, address)) => Ok(address),
+		None => parse_hex(address)
+	}
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use super::Breakpoint;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Register {
+	A,
+	X,
+	Y,
+	S,
+	P,
+	Pc,
+	// a bit of P
+	Flag(u8)
+}
+
+impl FromStr for Register {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name.to_lowercase().as_str() {
+			"a" => Ok(Register::A),
+			"x" => Ok(Register::X),
+			"y" => Ok(Register::Y),
+			"s" | "sp" => Ok(Register::S),
+			"p" => Ok(Register::P),
+			"pc" => Ok(Register::Pc),
+			"c" => Ok(Register::Flag(1 << 0)),
+			"z" => Ok(Register::Flag(1 << 1)),
+			"i" => Ok(Register::Flag(1 << 2)),
+			"d" => Ok(Register::Flag(1 << 3)),
+			"b" => Ok(Register::Flag(1 << 4)),
+			"v" => Ok(Register::Flag(1 << 6)),
+			"n" => Ok(Register::Flag(1 << 7)),
+			_ => Err(format!("Unknown register: {}", name))
+		}
+	}
+}
+
+const DISASSEMBLE_COUNT: usize = 10;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+	Help,
+	Continue,
+	Pause,
+	Step(usize),
+	StepOver,
+	StepOut,
+	RunTo(u16),
+	AddBreakpoint(Breakpoint),
+	DeleteBreakpoint(usize),
+	ListBreakpoints,
+	ShowRegisters,
+	SetRegister(Register, u16),
+	Examine(u16, usize),
+	Modify(u16, Vec<u8>),
+	// from the next instruction without an address
+	Disassemble(Option<u16>, usize)
+}
+
+pub const HELP: &str = "\
+c, continue                 resume the emulation
+p, pause                    pause the emulation
+s, step [count]             execute one or several instructions
+n, next                     step over a JSR
+o, out                      run until the current subroutine returns
+g, goto <address>           run until an address is reached
+b, break <range> [rwx] [if <condition>] pause on execution, reads or writes
+l, log <range> [rwx] [if <condition>] print the accesses without pausing
+d, delete <number>          delete a breakpoint
+bl, breakpoints             list the breakpoints with their numbers and hits
+r, registers                show the registers and flags
+r <register> <value>        set a, x, y, s, p, pc or a flag among n, v, b, d, i, z and c
+x, examine <address> [count] dump memory, 16 bytes by default
+w, write <address> <bytes>  write bytes to memory
+u, disassemble [address] [count] disassemble instructions, 10 from the next one by default
+h, help                     show this help
+An empty line repeats the last command. Addresses, values and bytes are hexadecimal, counts are decimal,
+and the labels of the symbol files can replace addresses.
+A range is an address, a register name like PPUDATA or start-end, prefixed by ppu: for the PPU bus, and
+breaks on execution by default. Conditions use the registers, flags, value, address, hits, [address] for
+a byte of memory, decimal or $ hexadecimal numbers, and the operators of C, like A == #$10 && [$0300] > 3.
+";
+
+impl FromStr for Command {
+	type Err = String;
+
+	fn from_str(line: &str) -> Result<Self, Self::Err> {
+		let mut words = line.split_whitespace();
+		let name = words.next().unwrap_or("");
+		let arguments: Vec<&str> = words.collect();
+		let command = match (name, arguments.as_slice()) {
+			("h", []) | ("help", []) => Command::Help,
+			("c", []) | ("continue", []) => Command::Continue,
+			("p", []) | ("pause", []) => Command::Pause,
+			("s", []) | ("step", []) => Command::Step(1),
+			("s", [count]) | ("step", [count]) => Command::Step(parse_count(count)?),
+			("n", []) | ("next", []) => Command::StepOver,
+			("o", []) | ("out", []) => Command::StepOut,
+			("g", [address]) | ("goto", [address]) => Command::RunTo(parse_hex(address)?),
+			("b", arguments) | ("break", arguments) => Command::AddBreakpoint(Breakpoint::parse(arguments, false)?),
+			("l", arguments) | ("log", arguments) => Command::AddBreakpoint(Breakpoint::parse(arguments, true)?),
+			("d", [number]) | ("delete", [number]) => Command::DeleteBreakpoint(parse_count(number)?),
+			("bl", []) | ("breakpoints", []) => Command::ListBreakpoints,
+			("r", []) | ("registers", []) => Command::ShowRegisters,
+			("r", [register, value]) | ("registers", [register, value]) => Command::SetRegister(register.parse()?, parse_hex(value)?),
+			("x", [address]) | ("examine", [address]) => Command::Examine(parse_hex(address)?, 16),
+			("x", [address, count]) | ("examine", [address, count]) => Command::Examine(parse_hex(address)?, parse_count(count)?),
+			("w", [address, bytes @ ..]) | ("write", [address, bytes @ ..]) if !bytes.is_empty() => {
+				let bytes = bytes.iter().map(|byte| parse_hex(byte).and_then(|value| {
+					if value <= 0xff {
+						Ok(value as u8)
+					} else {
+						Err(format!("Not a byte: {}", byte))
+					}
+				})).collect::<Result<_, _>>()?;
+				Command::Modify(parse_hex(address)?, bytes)
+			},
+			("u", []) | ("disassemble", []) => Command::Disassemble(None, DISASSEMBLE_COUNT),
+			("u", [address]) | ("disassemble", [address]) => Command::Disassemble(Some(parse_hex(address)?), DISASSEMBLE_COUNT),
+			("u", [address, count]) | ("disassemble", [address, count]) => Command::Disassemble(Some(parse_hex(address)?), parse_count(count)?),
+			_ => return Err(format!("Unknown command or wrong arguments: {}, type help for the list of commands", line.trim()))
+		};
+		Ok(command)
+	}
+}
+
+// with or without a "$" or "0x" prefix
+pub fn parse_hex(number: &str) -> Result<u16, String> {
+	let digits = number.strip_prefix('$').or_else(|| number.strip_prefix("0x")).unwrap_or(number);
+	u16::from_str_radix(digits, 16).map_err(|_| format!("Not a hexadecimal number: {}", number))
+}
+
+fn parse_count(count: &str) -> Result<usize, String> {
+	count.parse().map_err(|_| format!("Not a count: {}", count))
+}
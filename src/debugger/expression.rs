@@ -0,0 +1,253 @@
+use std::{fmt, str::FromStr};
+
+use emulator::*;
+use cpu::*;
+use super::{Register, MemoryAccess};
+
+// from the lowest to the highest precedence
+const OPERATORS: [&[&str]; 7] = [
+	&["||"],
+	&["&&"],
+	&["==", "!=", "<=", ">=", "<", ">"],
+	&["|"],
+	&["^"],
+	&["&"],
+	&["+", "-"]
+];
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+	Number(i64),
+	Name(String),
+	Operator(&'static str),
+	Open(char),
+	Close(char)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Variable {
+	Register(Register),
+	// the value read or written by the access that triggered the breakpoint
+	Value,
+	Address,
+	Hits
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Node {
+	Number(i64),
+	Variable(Variable),
+	// a byte of the CPU address space
+	Memory(Box<Node>),
+	Not(Box<Node>),
+	Binary(&'static str, Box<Node>, Box<Node>)
+}
+
+// what a condition is evaluated against
+pub struct Context<'a> {
+	pub emulator: &'a Emulator,
+	pub access: MemoryAccess,
+	pub hits: usize
+}
+
+// a condition like "A == #$10 && [$0300] > 3", true when it isn't zero
+#[derive(Clone, PartialEq, Debug)]
+pub struct Condition {
+	source: String,
+	node: Node
+}
+
+impl Condition {
+	pub fn is_true(&self, context: &Context) -> bool {
+		evaluate(&self.node, context) != 0
+	}
+}
+
+impl fmt::Display for Condition {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		write!(formatter, "{}", self.source)
+	}
+}
+
+impl FromStr for Condition {
+	type Err = String;
+
+	fn from_str(source: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser {
+			tokens: tokenize(source)?,
+			position: 0
+		};
+		let node = parser.parse_binary(0)?;
+		match parser.next() {
+			None => Ok(Self {
+				source: source.trim().to_string(),
+				node
+			}),
+			Some(token) => Err(format!("Unexpected {:?} in the condition", token))
+		}
+	}
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+	let mut tokens = Vec::new();
+	let mut characters = source.chars().peekable();
+	while let Some(&character) = characters.peek() {
+		if character.is_whitespace() || character == '#' {
+			// "#" only marks immediate values, as in assembly
+			characters.next();
+		} else if character.is_ascii_alphanumeric() || character == '$' || character == '_' {
+			let mut word = String::new();
+			while let Some(&character) = characters.peek() {
+				if character.is_ascii_alphanumeric() || character == '$' || character == '_' {
+					word.push(character);
+					characters.next();
+				} else {
+					break;
+				}
+			}
+			tokens.push(parse_word(&word)?);
+		} else if character == '(' || character == '[' {
+			tokens.push(Token::Open(character));
+			characters.next();
+		} else if character == ')' || character == ']' {
+			tokens.push(Token::Close(character));
+			characters.next();
+		} else {
+			characters.next();
+			let next = characters.peek().cloned();
+			let operator = OPERATORS.iter().flat_map(|operators| operators.iter()).chain(&["!"]).find(|operator| {
+				let mut operator_characters = operator.chars();
+				operator_characters.next() == Some(character) && operator_characters.next().is_none_or(|second| Some(second) == next)
+			}).ok_or_else(|| format!("Unexpected character in the condition: {}", character))?;
+			if operator.len() == 2 {
+				characters.next();
+			}
+			tokens.push(Token::Operator(operator));
+		}
+	}
+	Ok(tokens)
+}
+
+// numbers are decimal, or hexadecimal with a "$" or "0x" prefix
+fn parse_word(word: &str) -> Result<Token, String> {
+	let number = if let Some(digits) = word.strip_prefix('$').or_else(|| word.strip_prefix("0x")) {
+		i64::from_str_radix(digits, 16).ok()
+	} else if word.starts_with(|character: char| character.is_ascii_digit()) {
+		word.parse().ok()
+	} else {
+		return Ok(Token::Name(word.to_string()));
+	};
+	number.map(Token::Number).ok_or_else(|| format!("Not a number: {}", word))
+}
+
+struct Parser {
+	tokens: Vec<Token>,
+	position: usize
+}
+
+impl Parser {
+	fn next(&mut self) -> Option<Token> {
+		let token = self.tokens.get(self.position).cloned();
+		self.position += 1;
+		token
+	}
+
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.position)
+	}
+
+	fn parse_binary(&mut self, level: usize) -> Result<Node, String> {
+		if level == OPERATORS.len() {
+			return self.parse_unary();
+		}
+		let mut node = self.parse_binary(level + 1)?;
+		while let Some(&Token::Operator(operator)) = self.peek() {
+			if !OPERATORS[level].contains(&operator) {
+				break;
+			}
+			self.position += 1;
+			let right = self.parse_binary(level + 1)?;
+			node = Node::Binary(operator, Box::new(node), Box::new(right));
+		}
+		Ok(node)
+	}
+
+	fn parse_unary(&mut self) -> Result<Node, String> {
+		match self.next() {
+			Some(Token::Operator("!")) => Ok(Node::Not(Box::new(self.parse_unary()?))),
+			Some(Token::Number(number)) => Ok(Node::Number(number)),
+			Some(Token::Name(name)) => {
+				let variable = match name.to_lowercase().as_str() {
+					"value" => Variable::Value,
+					"address" => Variable::Address,
+					"hits" => Variable::Hits,
+					_ => Variable::Register(name.parse()?)
+				};
+				Ok(Node::Variable(variable))
+			},
+			Some(Token::Open(open)) => {
+				let node = self.parse_binary(0)?;
+				let close = if open == '[' { ']' } else { ')' };
+				if self.next() != Some(Token::Close(close)) {
+					return Err(format!("Missing {} in the condition", close));
+				}
+				if open == '[' {
+					Ok(Node::Memory(Box::new(node)))
+				} else {
+					Ok(node)
+				}
+			},
+			Some(token) => Err(format!("Unexpected {:?} in the condition", token)),
+			None => Err("Unexpected end of the condition".to_string())
+		}
+	}
+}
+
+fn evaluate(node: &Node, context: &Context) -> i64 {
+	match *node {
+		Node::Number(number) => number,
+		Node::Variable(ref variable) => match *variable {
+			Variable::Register(register) => {
+				let registers = context.emulator.cpu.get_registers();
+				match register {
+					Register::A => registers.a as _,
+					Register::X => registers.x as _,
+					Register::Y => registers.y as _,
+					Register::S => registers.s as _,
+					Register::P => registers.p as _,
+					Register::Pc => registers.pc as _,
+					Register::Flag(mask) => (registers.p & mask != 0) as _
+				}
+			},
+			Variable::Value => context.access.value as _,
+			Variable::Address => context.access.address as _,
+			Variable::Hits => context.hits as _
+		},
+		Node::Memory(ref address) => Cpu::read8_debug(context.emulator, evaluate(address, context) as u16) as _,
+		Node::Not(ref node) => (evaluate(node, context) == 0) as _,
+		Node::Binary(operator, ref left, ref right) => {
+			let left = evaluate(left, context);
+			// like in C, the right side of a logical operator is only evaluated when it matters
+			match operator {
+				"||" => return (left != 0 || evaluate(right, context) != 0) as _,
+				"&&" => return (left != 0 && evaluate(right, context) != 0) as _,
+				_ => {}
+			}
+			let right = evaluate(right, context);
+			match operator {
+				"==" => (left == right) as _,
+				"!=" => (left != right) as _,
+				"<=" => (left <= right) as _,
+				">=" => (left >= right) as _,
+				"<" => (left < right) as _,
+				">" => (left > right) as _,
+				"|" => left | right,
+				"^" => left ^ right,
+				"&" => left & right,
+				"+" => left.wrapping_add(right),
+				"-" => left.wrapping_sub(right),
+				_ => unreachable!()
+			}
+		}
+	}
+}
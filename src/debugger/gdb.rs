@@ -0,0 +1,361 @@
+use std::{
+	collections::{HashMap, hash_map::Entry},
+	io::{self, Read, Write},
+	net::{TcpListener, TcpStream}
+};
+
+use emulator::*;
+use cpu::*;
+use super::{Debugger, Command, Breakpoint, Bus};
+
+const INTERRUPT: u8 = 0x03;
+const MAX_PACKET_SIZE: usize = 0x1000;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+// a, x, y, p and sp take a byte each, pc two, in this order
+const REGISTER_COUNT: usize = 6;
+
+const TARGET_DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+<feature name="org.mu.6502">
+<reg name="a" bitsize="8" regnum="0"/>
+<reg name="x" bitsize="8"/>
+<reg name="y" bitsize="8"/>
+<reg name="p" bitsize="8"/>
+<reg name="sp" bitsize="8"/>
+<reg name="pc" bitsize="16" type="code_ptr"/>
+</feature>
+</target>
+"#;
+
+// server of the GDB remote serial protocol, for one client at a time
+pub(super) struct GdbStub {
+	listener: TcpListener,
+	connection: Option<TcpStream>,
+	input: Vec<u8>,
+	// the client waits for a stop reply after a continue or a step
+	running: bool,
+	// the debugger's numbers of the breakpoints inserted by the client, by type, address and length
+	breakpoints: HashMap<(u8, u16, u16), usize>
+}
+
+impl GdbStub {
+	pub(super) fn new(port: u16) -> io::Result<Self> {
+		let listener = TcpListener::bind(("127.0.0.1", port))?;
+		listener.set_nonblocking(true)?;
+		info!("Waiting for a GDB client on port {}", port);
+		Ok(Self {
+			listener,
+			connection: None,
+			input: Vec::new(),
+			running: false,
+			breakpoints: HashMap::new()
+		})
+	}
+
+	// accepts a client and handles its packets without blocking
+	pub(super) fn poll(&mut self, debugger: &mut Debugger, emulator: &mut Emulator) {
+		if self.connection.is_none() {
+			if let Ok((connection, address)) = self.listener.accept() {
+				if connection.set_nonblocking(true).is_ok() {
+					info!("GDB client connected from {}", address);
+					self.connection = Some(connection);
+					self.input.clear();
+					self.running = false;
+				}
+			}
+		}
+
+		let mut buffer = [0; MAX_PACKET_SIZE];
+		loop {
+			let result = match self.connection {
+				Some(ref mut connection) => connection.read(&mut buffer),
+				None => break
+			};
+			match result {
+				Ok(0) => self.disconnect(),
+				Ok(size) => self.input.extend_from_slice(&buffer[..size]),
+				Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => break,
+				Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {},
+				Err(error) => {
+					warn!("GDB connection error: {}", error);
+					self.disconnect();
+				}
+			}
+		}
+
+		while let Some(packet) = self.next_packet() {
+			let reply = match packet {
+				Packet::Interrupt => {
+					debugger.execute(emulator, Command::Pause);
+					self.running = false;
+					Some(format!("S{:02x}", SIGINT))
+				},
+				Packet::Data(data) => self.handle_packet(debugger, emulator, &data)
+			};
+			if let Some(reply) = reply {
+				self.send(&reply);
+			}
+		}
+	}
+
+	// sends the stop reply the client waits for once the emulation pauses
+	pub(super) fn notify_stop(&mut self, paused: bool) {
+		if self.running && paused {
+			self.running = false;
+			self.send(&format!("S{:02x}", SIGTRAP));
+		}
+	}
+
+	fn disconnect(&mut self) {
+		info!("GDB client disconnected");
+		self.connection = None;
+		self.running = false;
+	}
+
+	fn next_packet(&mut self) -> Option<Packet> {
+		loop {
+			let start = self.input.iter().position(|&byte| byte == b'$' || byte == INTERRUPT)?;
+			if self.input[start] == INTERRUPT {
+				self.input.drain(..=start);
+				return Some(Packet::Interrupt);
+			}
+			// acknowledgements before the packet are dropped
+			self.input.drain(..start);
+			let end = self.input.iter().position(|&byte| byte == b'#')?;
+			if self.input.len() < end + 3 {
+				return None;
+			}
+			let packet: Vec<u8> = self.input.drain(..end + 3).collect();
+			let data = &packet[1..end];
+			let checksum = std::str::from_utf8(&packet[end + 1..]).ok().and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+			if checksum == Some(get_checksum(data)) {
+				self.write(b"+");
+				return Some(Packet::Data(String::from_utf8_lossy(data).into_owned()));
+			}
+			self.write(b"-");
+		}
+	}
+
+	fn send(&mut self, data: &str) {
+		let packet = format!("${}#{:02x}", data, get_checksum(data.as_bytes()));
+		self.write(packet.as_bytes());
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		if let Some(ref mut connection) = self.connection {
+			// the replies are short, waiting for them to be sent is simpler than buffering them
+			let result = connection.set_nonblocking(false).and_then(|_| connection.write_all(bytes)).and_then(|_| connection.set_nonblocking(true));
+			if let Err(error) = result {
+				warn!("GDB connection error: {}", error);
+				self.disconnect();
+			}
+		}
+	}
+
+	// returns the reply, if there is one to send right away
+	fn handle_packet(&mut self, debugger: &mut Debugger, emulator: &mut Emulator, packet: &str) -> Option<String> {
+		let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+		let reply = match command {
+			"?" => {
+				// the client expects the target to be stopped once attached
+				if !debugger.is_paused() {
+					debugger.execute(emulator, Command::Pause);
+				}
+				format!("S{:02x}", SIGTRAP)
+			},
+			"g" => {
+				let registers = emulator.cpu.get_registers();
+				encode_hex(&[registers.a, registers.x, registers.y, registers.p, registers.s, registers.pc as u8, (registers.pc >> 8) as u8])
+			},
+			"G" => match decode_hex(arguments) {
+				Some(ref bytes) if bytes.len() == REGISTER_COUNT + 1 => {
+					let mut registers = emulator.cpu.get_registers();
+					registers.a = bytes[0];
+					registers.x = bytes[1];
+					registers.y = bytes[2];
+					registers.p = bytes[3];
+					registers.s = bytes[4];
+					registers.pc = ((bytes[6] as u16) << 8) | bytes[5] as u16;
+					emulator.cpu.set_registers(registers);
+					"OK".to_string()
+				},
+				_ => "E01".to_string()
+			},
+			"p" => match usize::from_str_radix(arguments, 16) {
+				Ok(index) if index < REGISTER_COUNT => {
+					let registers = emulator.cpu.get_registers();
+					match index {
+						0 => encode_hex(&[registers.a]),
+						1 => encode_hex(&[registers.x]),
+						2 => encode_hex(&[registers.y]),
+						3 => encode_hex(&[registers.p]),
+						4 => encode_hex(&[registers.s]),
+						_ => encode_hex(&[registers.pc as u8, (registers.pc >> 8) as u8])
+					}
+				},
+				_ => "E01".to_string()
+			},
+			"P" => {
+				let mut parts = arguments.splitn(2, '=');
+				let index = parts.next().and_then(|index| usize::from_str_radix(index, 16).ok());
+				let bytes = parts.next().and_then(decode_hex);
+				match (index, bytes) {
+					(Some(index), Some(bytes)) if index < REGISTER_COUNT && !bytes.is_empty() => {
+						let mut registers = emulator.cpu.get_registers();
+						match index {
+							0 => registers.a = bytes[0],
+							1 => registers.x = bytes[0],
+							2 => registers.y = bytes[0],
+							3 => registers.p = bytes[0],
+							4 => registers.s = bytes[0],
+							_ => registers.pc = ((*bytes.get(1).unwrap_or(&0) as u16) << 8) | bytes[0] as u16
+						}
+						emulator.cpu.set_registers(registers);
+						"OK".to_string()
+					},
+					_ => "E01".to_string()
+				}
+			},
+			"m" => match parse_address_and_length(arguments) {
+				Some((address, length)) => {
+					let bytes: Vec<u8> = (0..length.min(MAX_PACKET_SIZE as u16 / 2)).map(|offset| Cpu::read8_debug(emulator, address.wrapping_add(offset))).collect();
+					encode_hex(&bytes)
+				},
+				None => "E01".to_string()
+			},
+			"M" => {
+				let mut parts = arguments.splitn(2, ':');
+				let range = parts.next().and_then(parse_address_and_length);
+				let bytes = parts.next().and_then(decode_hex);
+				match (range, bytes) {
+					(Some((address, length)), Some(bytes)) if bytes.len() == length as usize => {
+						for (offset, byte) in bytes.iter().enumerate() {
+							Cpu::write_debug(emulator, address.wrapping_add(offset as u16), *byte);
+						}
+						"OK".to_string()
+					},
+					_ => "E01".to_string()
+				}
+			},
+			"c" | "s" => {
+				if let Ok(address) = u16::from_str_radix(arguments, 16) {
+					let mut registers = emulator.cpu.get_registers();
+					registers.pc = address;
+					emulator.cpu.set_registers(registers);
+				}
+				let command = if command == "c" {
+					Command::Continue
+				} else {
+					Command::Step(1)
+				};
+				debugger.execute(emulator, command);
+				self.running = true;
+				return None;
+			},
+			"Z" | "z" => self.handle_breakpoint(debugger, emulator, command == "Z", arguments),
+			"D" => {
+				debugger.execute(emulator, Command::Continue);
+				"OK".to_string()
+			},
+			"k" => {
+				debugger.execute(emulator, Command::Continue);
+				self.disconnect();
+				return None;
+			},
+			"H" => "OK".to_string(),
+			"q" => if arguments.starts_with("Supported") {
+				format!("PacketSize={:x};qXfer:features:read+", MAX_PACKET_SIZE)
+			} else if let Some(range) = arguments.strip_prefix("Xfer:features:read:target.xml:") {
+				match parse_address_and_length(range) {
+					Some((offset, length)) => {
+						let offset = (offset as usize).min(TARGET_DESCRIPTION.len());
+						let end = (offset + length as usize).min(TARGET_DESCRIPTION.len());
+						let marker = if end == TARGET_DESCRIPTION.len() { "l" } else { "m" };
+						format!("{}{}", marker, &TARGET_DESCRIPTION[offset..end])
+					},
+					None => "E01".to_string()
+				}
+			} else if arguments == "Attached" {
+				"1".to_string()
+			} else if arguments == "fThreadInfo" {
+				"m1".to_string()
+			} else if arguments == "sThreadInfo" {
+				"l".to_string()
+			} else if arguments == "C" {
+				"QC1".to_string()
+			} else if arguments.starts_with("Symbol") {
+				"OK".to_string()
+			} else {
+				String::new()
+			},
+			// unsupported packets get an empty reply
+			_ => String::new()
+		};
+		Some(reply)
+	}
+
+	// type 0 and 1 are breakpoints, 2 write, 3 read and 4 access watchpoints
+	fn handle_breakpoint(&mut self, debugger: &mut Debugger, emulator: &mut Emulator, insert: bool, arguments: &str) -> String {
+		let mut parts = arguments.split(',');
+		let kind = parts.next().and_then(|kind| kind.parse::<u8>().ok());
+		let address = parts.next().and_then(|address| u16::from_str_radix(address, 16).ok());
+		let length = parts.next().and_then(|length| u16::from_str_radix(length, 16).ok());
+		let (kind, address, length) = match (kind, address, length) {
+			(Some(kind), Some(address), Some(length)) if kind <= 4 => (kind, address, length.max(1)),
+			_ => return String::new()
+		};
+		let key = (kind, address, length);
+		if insert {
+			if let Entry::Vacant(entry) = self.breakpoints.entry(key) {
+				let breakpoint = Breakpoint {
+					bus: Bus::Cpu,
+					start: address,
+					end: if kind <= 1 { address } else { address.saturating_add(length - 1) },
+					read: kind == 3 || kind == 4,
+					write: kind == 2 || kind == 4,
+					execute: kind <= 1,
+					condition: None,
+					log: false,
+					hits: 0
+				};
+				let number = debugger.add_breakpoint(emulator, breakpoint);
+				entry.insert(number);
+			}
+		} else if let Some(number) = self.breakpoints.remove(&key) {
+			debugger.delete_breakpoint(emulator, number);
+		}
+		"OK".to_string()
+	}
+}
+
+enum Packet {
+	Interrupt,
+	Data(String)
+}
+
+fn get_checksum(data: &[u8]) -> u8 {
+	data.iter().fold(0, |checksum: u8, byte| checksum.wrapping_add(*byte))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+	if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+		return None;
+	}
+	(0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok()).collect()
+}
+
+// "address,length" in hexadecimal
+fn parse_address_and_length(arguments: &str) -> Option<(u16, u16)> {
+	let mut parts = arguments.splitn(2, ',');
+	let address = u16::from_str_radix(parts.next()?, 16).ok()?;
+	let length = u16::from_str_radix(parts.next()?, 16).ok()?;
+	Some((address, length))
+}
@@ -0,0 +1,435 @@
+mod commands;
+mod expression;
+mod breakpoints;
+mod gdb;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+	commands::*,
+	expression::*,
+	breakpoints::*
+};
+pub use accesses::*;
+
+use std::{
+	collections::BTreeMap,
+	io::{self, BufRead, Write},
+	iter,
+	sync::mpsc::{self, Receiver},
+	thread
+};
+
+use emulator::*;
+use cpu::*;
+use disasm::*;
+use self::gdb::GdbStub;
+
+const JSR_OPCODE: u8 = 0x20;
+const RTS_OPCODE: u8 = 0x60;
+const RTI_OPCODE: u8 = 0x40;
+
+const EXAMINE_ROW_SIZE: usize = 16;
+const BYTES_COLUMN_WIDTH: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Mode {
+	Paused,
+	Running,
+	Step(usize),
+	// until the instruction after a JSR, with the stack pointer of before it
+	StepOver {
+		return_address: u16,
+		stack_pointer: u8
+	},
+	// until a RTS or a RTI pulls above this stack pointer
+	StepOut {
+		stack_pointer: u8
+	},
+	RunTo(u16)
+}
+
+// terminal debugger, reading commands from the standard input without blocking the emulation
+pub struct Debugger {
+	commands: Receiver<String>,
+	last_command: Option<Command>,
+	mode: Mode,
+	// by number, which the user deletes them with
+	breakpoints: BTreeMap<usize, Breakpoint>,
+	next_breakpoint_number: usize,
+	// a breakpoint at the current instruction doesn't stop the emulation again when it is resumed
+	resuming: bool,
+	gdb: Option<GdbStub>
+}
+
+impl Default for Debugger {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Debugger {
+	pub fn new() -> Self {
+		let (sender, commands) = mpsc::channel();
+		thread::spawn(move || {
+			let stdin = io::stdin();
+			for line in stdin.lock().lines() {
+				match line {
+					Ok(line) => if sender.send(line).is_err() {
+						break;
+					},
+					Err(_) => break
+				}
+			}
+		});
+		info!("Debugger ready, type help for the list of commands");
+		Self {
+			commands,
+			last_command: None,
+			mode: Mode::Running,
+			breakpoints: BTreeMap::new(),
+			next_breakpoint_number: 1,
+			resuming: false,
+			gdb: None
+		}
+	}
+
+	// lets a client of the GDB remote serial protocol connect to a localhost port
+	pub fn listen_gdb(&mut self, port: u16) -> io::Result<()> {
+		self.gdb = Some(GdbStub::new(port)?);
+		Ok(())
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.mode == Mode::Paused
+	}
+
+	// runs the emulator until the next frame like Emulator::run_frame, and returns false if the debugger paused it before
+	pub fn run_frame(&mut self, emulator: &mut Emulator) -> bool {
+		self.run_frame_with(emulator, &mut Emulator::step)
+	}
+
+	// executes the instructions with a function doing more than Emulator::step, like calling the memory callbacks of a script
+	pub fn run_frame_with(&mut self, emulator: &mut Emulator, step: &mut dyn FnMut(&mut Emulator)) -> bool {
+		while let Ok(line) = self.commands.try_recv() {
+			let output = self.execute_line(emulator, &line);
+			print!("{}", output);
+			io::stdout().flush().unwrap();
+		}
+
+		if let Some(mut gdb) = self.gdb.take() {
+			gdb.poll(self, emulator);
+			self.gdb = Some(gdb);
+		}
+
+		let running = self.run(emulator, step);
+		let paused = self.is_paused();
+		if let Some(ref mut gdb) = self.gdb {
+			gdb.notify_stop(paused);
+		}
+		running
+	}
+
+	fn run(&mut self, emulator: &mut Emulator, step: &mut dyn FnMut(&mut Emulator)) -> bool {
+		while !emulator.screen.is_draw_requested() {
+			if self.mode == Mode::Paused {
+				return false;
+			}
+			let address = Cpu::get_next_instruction_address(emulator);
+			let opcode = Cpu::read8_debug(emulator, address);
+			if !self.resuming {
+				let stop = match self.mode {
+					Mode::RunTo(target_address) => address == target_address,
+					Mode::StepOver {
+						return_address,
+						stack_pointer
+					} => address == return_address && emulator.cpu.get_registers().s == stack_pointer,
+					_ => false
+				};
+				if stop {
+					self.pause_and_print(emulator, "");
+					return false;
+				}
+				let execution = MemoryAccess {
+					bus: Bus::Cpu,
+					kind: AccessKind::Execute,
+					address,
+					value: opcode
+				};
+				if let Some(reason) = self.check_breakpoints(emulator, &[execution], address) {
+					self.pause_and_print(emulator, &reason);
+					return false;
+				}
+			}
+			self.resuming = false;
+
+			step(emulator);
+
+			// watchpoints pause after the instruction which accessed the memory
+			let mut accesses = emulator.memory_accesses.take();
+			accesses.extend(emulator.ppu.memory_accesses.take());
+			if let Some(reason) = self.check_breakpoints(emulator, &accesses, address) {
+				self.pause_and_print(emulator, &reason);
+				continue;
+			}
+
+			match self.mode {
+				Mode::Step(count) => if count <= 1 {
+					self.pause_and_print(emulator, "");
+				} else {
+					self.mode = Mode::Step(count - 1);
+				},
+				Mode::StepOut {
+					stack_pointer
+				} if (opcode == RTS_OPCODE || opcode == RTI_OPCODE) && emulator.cpu.get_registers().s > stack_pointer => {
+					self.pause_and_print(emulator, "");
+				},
+				_ => {}
+			}
+		}
+		!self.is_paused()
+	}
+
+	// counts the hits, prints the logged accesses and returns why the emulation has to pause if it does
+	fn check_breakpoints(&mut self, emulator: &Emulator, accesses: &[MemoryAccess], instruction_address: u16) -> Option<String> {
+		let mut reason: Option<String> = None;
+		for access in accesses {
+			for (number, breakpoint) in self.breakpoints.iter_mut().filter(|(_, breakpoint)| breakpoint.matches(access)) {
+				breakpoint.hits += 1;
+				let context = Context {
+					emulator,
+					access: *access,
+					hits: breakpoint.hits
+				};
+				if !breakpoint.condition.as_ref().is_none_or(|condition| condition.is_true(&context)) {
+					continue;
+				}
+				let mut line = format!("{} {}: {}", if breakpoint.log { "Log" } else { "Breakpoint" }, number, access);
+				if access.kind != AccessKind::Execute {
+					line += &format!(" by the instruction at {:04X}", instruction_address);
+				}
+				line += "\n";
+				if breakpoint.log {
+					print!("{}", line);
+				} else {
+					*reason.get_or_insert_with(String::new) += &line;
+				}
+			}
+		}
+		reason
+	}
+
+	fn add_breakpoint(&mut self, emulator: &mut Emulator, breakpoint: Breakpoint) -> usize {
+		let number = self.next_breakpoint_number;
+		self.next_breakpoint_number += 1;
+		self.breakpoints.insert(number, breakpoint);
+		self.update_access_logs(emulator);
+		number
+	}
+
+	fn delete_breakpoint(&mut self, emulator: &mut Emulator, number: usize) -> bool {
+		let deleted = self.breakpoints.remove(&number).is_some();
+		self.update_access_logs(emulator);
+		deleted
+	}
+
+	// the accesses are only recorded while there are watchpoints
+	fn update_access_logs(&self, emulator: &mut Emulator) {
+		let watchpoints = || self.breakpoints.values().filter(|breakpoint| breakpoint.is_watchpoint());
+		emulator.memory_accesses.set_enabled(watchpoints().any(|breakpoint| breakpoint.bus == Bus::Cpu));
+		emulator.ppu.memory_accesses.set_enabled(watchpoints().any(|breakpoint| breakpoint.bus == Bus::Ppu));
+	}
+
+	fn pause_and_print(&mut self, emulator: &Emulator, reason: &str) {
+		self.mode = Mode::Paused;
+		print!("{}{}", reason, Self::describe_state(emulator));
+		io::stdout().flush().unwrap();
+	}
+
+	// parses and executes a line typed by the user, and returns the text to show
+	pub fn execute_line(&mut self, emulator: &mut Emulator, line: &str) -> String {
+		let command = if line.trim().is_empty() {
+			match self.last_command {
+				Some(ref command) => command.clone(),
+				None => return String::new()
+			}
+		} else {
+			match replace_labels(emulator, line).parse() {
+				Ok(command) => command,
+				Err(error) => return format!("{}\n", error)
+			}
+		};
+		self.last_command = Some(command.clone());
+		self.execute(emulator, command)
+	}
+
+	pub fn execute(&mut self, emulator: &mut Emulator, command: Command) -> String {
+		match command {
+			Command::Help => HELP.to_string(),
+			Command::Continue => {
+				self.resume(Mode::Running);
+				String::new()
+			},
+			Command::Pause => {
+				self.mode = Mode::Paused;
+				Self::describe_state(emulator)
+			},
+			Command::Step(count) => {
+				if count > 0 {
+					self.resume(Mode::Step(count));
+				}
+				String::new()
+			},
+			Command::StepOver => {
+				let address = Cpu::get_next_instruction_address(emulator);
+				let registers = emulator.cpu.get_registers();
+				// anything else than a JSR is a single step
+				if Cpu::read8_debug(emulator, address) == JSR_OPCODE {
+					self.resume(Mode::StepOver {
+						return_address: address.wrapping_add(3),
+						stack_pointer: registers.s
+					});
+				} else {
+					self.resume(Mode::Step(1));
+				}
+				String::new()
+			},
+			Command::StepOut => {
+				let stack_pointer = emulator.cpu.get_registers().s;
+				self.resume(Mode::StepOut {
+					stack_pointer
+				});
+				String::new()
+			},
+			Command::RunTo(address) => {
+				self.resume(Mode::RunTo(address));
+				String::new()
+			},
+			Command::AddBreakpoint(breakpoint) => {
+				let output = format!("{}\n", breakpoint);
+				let number = self.add_breakpoint(emulator, breakpoint);
+				format!("Breakpoint {}: {}", number, output)
+			},
+			Command::DeleteBreakpoint(number) => if self.delete_breakpoint(emulator, number) {
+				format!("Deleted breakpoint {}\n", number)
+			} else {
+				format!("No breakpoint {}\n", number)
+			},
+			Command::ListBreakpoints => if self.breakpoints.is_empty() {
+				"No breakpoints\n".to_string()
+			} else {
+				self.breakpoints.iter().map(|(number, breakpoint)| format!("{}: {}\n", number, breakpoint)).collect()
+			},
+			Command::ShowRegisters => Self::describe_state(emulator),
+			Command::SetRegister(register, value) => {
+				let mut registers = emulator.cpu.get_registers();
+				match register {
+					Register::A => registers.a = value as u8,
+					Register::X => registers.x = value as u8,
+					Register::Y => registers.y = value as u8,
+					Register::S => registers.s = value as u8,
+					Register::P => registers.p = value as u8,
+					Register::Pc => registers.pc = value,
+					Register::Flag(mask) => if value != 0 {
+						registers.p |= mask;
+					} else {
+						registers.p &= !mask;
+					}
+				}
+				emulator.cpu.set_registers(registers);
+				Self::describe_state(emulator)
+			},
+			Command::Examine(address, count) => {
+				let mut output = String::new();
+				for row in 0..count.div_ceil(EXAMINE_ROW_SIZE) {
+					let row_address = address.wrapping_add((row * EXAMINE_ROW_SIZE) as u16);
+					output += &format!("{:04X}:", row_address);
+					for offset in 0..EXAMINE_ROW_SIZE.min(count - row * EXAMINE_ROW_SIZE) {
+						output += &format!(" {:02X}", Cpu::read8_debug(emulator, row_address.wrapping_add(offset as u16)));
+					}
+					output += "\n";
+				}
+				output
+			},
+			Command::Modify(address, bytes) => {
+				for (offset, byte) in bytes.iter().enumerate() {
+					Cpu::write_debug(emulator, address.wrapping_add(offset as u16), *byte);
+				}
+				format!("Wrote {} bytes at {:04X}\n", bytes.len(), address)
+			},
+			Command::Disassemble(address, count) => {
+				let address = address.unwrap_or_else(|| Cpu::get_next_instruction_address(emulator));
+				Self::disassemble(emulator, address, count)
+			}
+		}
+	}
+
+	fn resume(&mut self, mode: Mode) {
+		self.resuming = self.mode == Mode::Paused;
+		self.mode = mode;
+	}
+
+	// the registers and the next instruction
+	pub fn describe_state(emulator: &Emulator) -> String {
+		let registers = emulator.cpu.get_registers();
+		let flags: String = "NV-BDIZC".chars().enumerate().map(|(index, name)| {
+			if (registers.p << index) & 0x80 != 0 {
+				name
+			} else {
+				name.to_ascii_lowercase()
+			}
+		}).collect();
+		let mut output = format!("PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} {}\n", registers.pc, registers.a, registers.x, registers.y, registers.p, registers.s, flags);
+		let address = Cpu::get_next_instruction_address(emulator);
+		if address != registers.pc {
+			output += &format!("Interrupt pending, its handler starts at {:04X}\n", address);
+		}
+		output += &Self::disassemble(emulator, address, 1);
+		output
+	}
+
+	fn disassemble(emulator: &Emulator, address: u16, count: usize) -> String {
+		let labels = emulator.get_labels();
+		disassemble_memory(|address| Cpu::read8_debug(emulator, address), address, count).iter().map(|instruction| {
+			let label = labels.get_label(instruction.address).map(|label| format!("{}:\n", label)).unwrap_or_default();
+			format!("{}{:04X}  {:<width$}  {}\n", label, instruction.address, instruction.format_bytes(), instruction.format(&labels), width = BYTES_COLUMN_WIDTH)
+		}).collect()
+	}
+}
+
+// replaces the labels of the symbol files by their addresses, except in the command name and where a word is a register, a variable or a keyword
+fn replace_labels(emulator: &Emulator, line: &str) -> String {
+	let line = line.trim();
+	if emulator.symbols.is_empty() {
+		return line.to_string();
+	}
+	let is_reserved = |word: &str| {
+		let lowercase = word.to_lowercase();
+		word.parse::<Register>().is_ok() || ["value", "address", "hits", "if", "ppu"].contains(&lowercase.as_str()) || lowercase.chars().all(|kind| "rwx".contains(kind))
+	};
+	let (name, arguments) = line.split_at(line.find(char::is_whitespace).unwrap_or(line.len()));
+	let mut output = name.to_string();
+	let mut word = String::new();
+	// the space ends the last word
+	for character in arguments.chars().chain(iter::once(' ')) {
+		if character.is_ascii_alphanumeric() || "_@.".contains(character) {
+			word.push(character);
+			continue;
+		}
+		// numbers, and hexadecimal digits after a "$", aren't labels
+		let address = if output.ends_with('$') || word.starts_with(|character: char| character.is_ascii_digit()) || is_reserved(&word) {
+			None
+		} else {
+			emulator.get_symbol_address(&word)
+		};
+		match address {
+			Some(address) => output += &format!("${:04X}", address),
+			None => output += &word
+		}
+		word.clear();
+		output.push(character);
+	}
+	output.pop();
+	output
+}
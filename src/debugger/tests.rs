@@ -0,0 +1,111 @@
+use super::*;
+
+// evaluates a condition against an emulator with A = $10 and [$0300] = 4
+fn evaluate(condition: &str) -> bool {
+	let mut emulator = Emulator::new();
+	let mut registers = emulator.cpu.get_registers();
+	registers.a = 0x10;
+	emulator.cpu.set_registers(registers);
+	emulator.ram[0x300] = 4;
+	let context = Context {
+		emulator: &emulator,
+		access: MemoryAccess {
+			bus: Bus::Cpu,
+			kind: AccessKind::Write,
+			address: 0x0300,
+			value: 4
+		},
+		hits: 1
+	};
+	condition.parse::<Condition>().unwrap().is_true(&context)
+}
+
+fn parse_breakpoint(arguments: &str) -> Result<Breakpoint, String> {
+	Breakpoint::parse(&arguments.split_whitespace().collect::<Vec<_>>(), false)
+}
+
+#[test]
+fn condition_precedence() {
+	assert!(evaluate("A == #$10 && [$0300] > 3"));
+	assert!(!evaluate("A == #$10 && [$0300] > 4"));
+	assert!(evaluate("1 || 1 && 0"));
+	assert!(evaluate("1 | 2 == 3"));
+	assert!(evaluate("1 + 2 == 3"));
+	assert!(evaluate("(1 | 2) == 3"));
+	assert!(!evaluate("!(value == 4)"));
+	assert!(evaluate("address == $0300 && hits == 1"));
+}
+
+#[test]
+fn condition_operators() {
+	assert!(evaluate("1 <= 1"));
+	assert!(!evaluate("1 < 1"));
+	assert!(evaluate("2 >= 2"));
+	assert!(!evaluate("2 > 2"));
+	assert!(evaluate("2 != 1"));
+	assert!(!evaluate("!1"));
+	assert!(evaluate("(2 || 0) == 1"));
+	assert!(evaluate("(2 | 1) == 3"));
+	assert!(evaluate("(2 && 1) == 1"));
+	assert!(evaluate("(2 & 1) == 0"));
+	assert!(evaluate("(3 ^ 1) == 2"));
+	assert!(evaluate("1 - 2 < 0"));
+}
+
+#[test]
+fn condition_numbers() {
+	assert!(evaluate("#$10 == 16"));
+	assert!(evaluate("$ff == 255"));
+	assert!(evaluate("0x10 == #16"));
+	assert!(evaluate("[$300] == 4"));
+	assert!("$zz == 1".parse::<Condition>().is_err());
+	assert!("12ab == 1".parse::<Condition>().is_err());
+}
+
+#[test]
+fn condition_errors() {
+	for condition in &["[$0300 == 4", "(1 + 2", "1 + 2)", "1 2", "A ==", "== 1", "", "q == 1", "1 @ 2", "[$0300) == 4"] {
+		assert!(condition.parse::<Condition>().is_err(), "{}", condition);
+	}
+}
+
+#[test]
+fn breakpoint_ranges() {
+	let breakpoint = parse_breakpoint("c000").unwrap();
+	assert_eq!((breakpoint.bus, breakpoint.start, breakpoint.end), (Bus::Cpu, 0xc000, 0xc000));
+	assert_eq!((breakpoint.read, breakpoint.write, breakpoint.execute), (false, false, true));
+
+	let breakpoint = parse_breakpoint("0300-03ff rw").unwrap();
+	assert_eq!((breakpoint.start, breakpoint.end), (0x0300, 0x03ff));
+	assert_eq!((breakpoint.read, breakpoint.write, breakpoint.execute), (true, true, false));
+
+	let breakpoint = parse_breakpoint("ppuctrl w").unwrap();
+	assert_eq!((breakpoint.start, breakpoint.end), (0x2000, 0x2000));
+
+	assert!(parse_breakpoint("0400-0300").is_err());
+	assert!(parse_breakpoint("").is_err());
+	assert!(parse_breakpoint("zzzz").is_err());
+}
+
+#[test]
+fn breakpoint_kinds() {
+	assert!(parse_breakpoint("0300 wx").unwrap().execute);
+	assert!(parse_breakpoint("0300 q").is_err());
+	assert!(parse_breakpoint("0300 w something").is_err());
+	assert!(parse_breakpoint("0300 w if").is_err());
+
+	let breakpoint = parse_breakpoint("0300 w if A == #$10 && [$0300] > 3").unwrap();
+	assert_eq!(breakpoint.condition, Some("A == #$10 && [$0300] > 3".parse().unwrap()));
+	let breakpoint = parse_breakpoint("0300 if value == 1").unwrap();
+	assert!(breakpoint.execute && breakpoint.condition.is_some());
+}
+
+#[test]
+fn breakpoint_ppu_bus() {
+	let breakpoint = parse_breakpoint("ppu:2000-23ff w").unwrap();
+	assert_eq!((breakpoint.bus, breakpoint.start, breakpoint.end), (Bus::Ppu, 0x2000, 0x23ff));
+	assert!(parse_breakpoint("PPU:3f00 r").is_ok());
+	assert!(parse_breakpoint("ppu:2000 x").is_err());
+	assert!(parse_breakpoint("ppu:2000 rx").is_err());
+	assert!(parse_breakpoint("ppu:2000").is_err());
+}
@@ -0,0 +1,231 @@
+use std::{
+	collections::HashMap,
+	fmt
+};
+
+use self::AddressingMode::*;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AddressingMode {
+	Implied,
+	Accumulator,
+	Immediate,
+	ZeroPage,
+	ZeroPageX,
+	ZeroPageY,
+	Absolute,
+	AbsoluteX,
+	AbsoluteY,
+	Indirect,
+	IndirectX,
+	IndirectY,
+	Relative
+}
+
+impl AddressingMode {
+	// the size of an instruction, opcode included
+	pub fn get_size(self) -> u16 {
+		match self {
+			Implied | Accumulator => 1,
+			Immediate | ZeroPage | ZeroPageX | ZeroPageY | IndirectX | IndirectY | Relative => 2,
+			Absolute | AbsoluteX | AbsoluteY | Indirect => 3
+		}
+	}
+}
+
+// unofficial opcodes start with a "*", like in the trace logs of nestest
+const OPCODES: [(&str, AddressingMode); 256] = [
+	("BRK", Implied), ("ORA", IndirectX), ("*KIL", Implied), ("*SLO", IndirectX), ("*NOP", ZeroPage), ("ORA", ZeroPage), ("ASL", ZeroPage), ("*SLO", ZeroPage), // 00
+	("PHP", Implied), ("ORA", Immediate), ("ASL", Accumulator), ("*AAC", Immediate), ("*NOP", Absolute), ("ORA", Absolute), ("ASL", Absolute), ("*SLO", Absolute), // 08
+	("BPL", Relative), ("ORA", IndirectY), ("*KIL", Implied), ("*SLO", IndirectY), ("*NOP", ZeroPageX), ("ORA", ZeroPageX), ("ASL", ZeroPageX), ("*SLO", ZeroPageX), // 10
+	("CLC", Implied), ("ORA", AbsoluteY), ("*NOP", Implied), ("*SLO", AbsoluteY), ("*NOP", AbsoluteX), ("ORA", AbsoluteX), ("ASL", AbsoluteX), ("*SLO", AbsoluteX), // 18
+	("JSR", Absolute), ("AND", IndirectX), ("*KIL", Implied), ("*RLA", IndirectX), ("BIT", ZeroPage), ("AND", ZeroPage), ("ROL", ZeroPage), ("*RLA", ZeroPage), // 20
+	("PLP", Implied), ("AND", Immediate), ("ROL", Accumulator), ("*AAC", Immediate), ("BIT", Absolute), ("AND", Absolute), ("ROL", Absolute), ("*RLA", Absolute), // 28
+	("BMI", Relative), ("AND", IndirectY), ("*KIL", Implied), ("*RLA", IndirectY), ("*NOP", ZeroPageX), ("AND", ZeroPageX), ("ROL", ZeroPageX), ("*RLA", ZeroPageX), // 30
+	("SEC", Implied), ("AND", AbsoluteY), ("*NOP", Implied), ("*RLA", AbsoluteY), ("*NOP", AbsoluteX), ("AND", AbsoluteX), ("ROL", AbsoluteX), ("*RLA", AbsoluteX), // 38
+	("RTI", Implied), ("EOR", IndirectX), ("*KIL", Implied), ("*SRE", IndirectX), ("*NOP", ZeroPage), ("EOR", ZeroPage), ("LSR", ZeroPage), ("*SRE", ZeroPage), // 40
+	("PHA", Implied), ("EOR", Immediate), ("LSR", Accumulator), ("*ASR", Immediate), ("JMP", Absolute), ("EOR", Absolute), ("LSR", Absolute), ("*SRE", Absolute), // 48
+	("BVC", Relative), ("EOR", IndirectY), ("*KIL", Implied), ("*SRE", IndirectY), ("*NOP", ZeroPageX), ("EOR", ZeroPageX), ("LSR", ZeroPageX), ("*SRE", ZeroPageX), // 50
+	("CLI", Implied), ("EOR", AbsoluteY), ("*NOP", Implied), ("*SRE", AbsoluteY), ("*NOP", AbsoluteX), ("EOR", AbsoluteX), ("LSR", AbsoluteX), ("*SRE", AbsoluteX), // 58
+	("RTS", Implied), ("ADC", IndirectX), ("*KIL", Implied), ("*RRA", IndirectX), ("*NOP", ZeroPage), ("ADC", ZeroPage), ("ROR", ZeroPage), ("*RRA", ZeroPage), // 60
+	("PLA", Implied), ("ADC", Immediate), ("ROR", Accumulator), ("*ARR", Immediate), ("JMP", Indirect), ("ADC", Absolute), ("ROR", Absolute), ("*RRA", Absolute), // 68
+	("BVS", Relative), ("ADC", IndirectY), ("*KIL", Implied), ("*RRA", IndirectY), ("*NOP", ZeroPageX), ("ADC", ZeroPageX), ("ROR", ZeroPageX), ("*RRA", ZeroPageX), // 70
+	("SEI", Implied), ("ADC", AbsoluteY), ("*NOP", Implied), ("*RRA", AbsoluteY), ("*NOP", AbsoluteX), ("ADC", AbsoluteX), ("ROR", AbsoluteX), ("*RRA", AbsoluteX), // 78
+	("*NOP", Immediate), ("STA", IndirectX), ("*NOP", Immediate), ("*SAX", IndirectX), ("STY", ZeroPage), ("STA", ZeroPage), ("STX", ZeroPage), ("*SAX", ZeroPage), // 80
+	("DEY", Implied), ("*NOP", Immediate), ("TXA", Implied), ("*XAA", Immediate), ("STY", Absolute), ("STA", Absolute), ("STX", Absolute), ("*SAX", Absolute), // 88
+	("BCC", Relative), ("STA", IndirectY), ("*KIL", Implied), ("*AXA", IndirectY), ("STY", ZeroPageX), ("STA", ZeroPageX), ("STX", ZeroPageY), ("*SAX", ZeroPageY), // 90
+	("TYA", Implied), ("STA", AbsoluteY), ("TXS", Implied), ("*XAS", AbsoluteY), ("*SYA", AbsoluteX), ("STA", AbsoluteX), ("*SXA", AbsoluteY), ("*AXA", AbsoluteY), // 98
+	("LDY", Immediate), ("LDA", IndirectX), ("LDX", Immediate), ("*LAX", IndirectX), ("LDY", ZeroPage), ("LDA", ZeroPage), ("LDX", ZeroPage), ("*LAX", ZeroPage), // a0
+	("TAY", Implied), ("LDA", Immediate), ("TAX", Implied), ("*LAX", Immediate), ("LDY", Absolute), ("LDA", Absolute), ("LDX", Absolute), ("*LAX", Absolute), // a8
+	("BCS", Relative), ("LDA", IndirectY), ("*KIL", Implied), ("*LAX", IndirectY), ("LDY", ZeroPageX), ("LDA", ZeroPageX), ("LDX", ZeroPageY), ("*LAX", ZeroPageY), // b0
+	("CLV", Implied), ("LDA", AbsoluteY), ("TSX", Implied), ("*LAR", AbsoluteY), ("LDY", AbsoluteX), ("LDA", AbsoluteX), ("LDX", AbsoluteY), ("*LAX", AbsoluteY), // b8
+	("CPY", Immediate), ("CMP", IndirectX), ("*NOP", Immediate), ("*DCP", IndirectX), ("CPY", ZeroPage), ("CMP", ZeroPage), ("DEC", ZeroPage), ("*DCP", ZeroPage), // c0
+	("INY", Implied), ("CMP", Immediate), ("DEX", Implied), ("*AXS", Immediate), ("CPY", Absolute), ("CMP", Absolute), ("DEC", Absolute), ("*DCP", Absolute), // c8
+	("BNE", Relative), ("CMP", IndirectY), ("*KIL", Implied), ("*DCP", IndirectY), ("*NOP", ZeroPageX), ("CMP", ZeroPageX), ("DEC", ZeroPageX), ("*DCP", ZeroPageX), // d0
+	("CLD", Implied), ("CMP", AbsoluteY), ("*NOP", Implied), ("*DCP", AbsoluteY), ("*NOP", AbsoluteX), ("CMP", AbsoluteX), ("DEC", AbsoluteX), ("*DCP", AbsoluteX), // d8
+	("CPX", Immediate), ("SBC", IndirectX), ("*NOP", Immediate), ("*ISB", IndirectX), ("CPX", ZeroPage), ("SBC", ZeroPage), ("INC", ZeroPage), ("*ISB", ZeroPage), // e0
+	("INX", Implied), ("SBC", Immediate), ("NOP", Implied), ("*SBC", Immediate), ("CPX", Absolute), ("SBC", Absolute), ("INC", Absolute), ("*ISB", Absolute), // e8
+	("BEQ", Relative), ("SBC", IndirectY), ("*KIL", Implied), ("*ISB", IndirectY), ("*NOP", ZeroPageX), ("SBC", ZeroPageX), ("INC", ZeroPageX), ("*ISB", ZeroPageX), // f0
+	("SED", Implied), ("SBC", AbsoluteY), ("*NOP", Implied), ("*ISB", AbsoluteY), ("*NOP", AbsoluteX), ("SBC", AbsoluteX), ("INC", AbsoluteX), ("*ISB", AbsoluteX)  // f8
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Opcode {
+	pub mnemonic: &'static str,
+	pub mode: AddressingMode,
+	pub official: bool
+}
+
+pub fn get_opcode(opcode: u8) -> Opcode {
+	let (name, mode) = OPCODES[opcode as usize];
+	match name.strip_prefix('*') {
+		Some(mnemonic) => Opcode {
+			mnemonic,
+			mode,
+			official: false
+		},
+		None => Opcode {
+			mnemonic: name,
+			mode,
+			official: true
+		}
+	}
+}
+
+// names given to addresses, by a symbol file for instance
+pub trait Labels {
+	fn get_label(&self, address: u16) -> Option<&str>;
+}
+
+impl Labels for HashMap<u16, String> {
+	fn get_label(&self, address: u16) -> Option<&str> {
+		self.get(&address).map(String::as_str)
+	}
+}
+
+pub struct NoLabels;
+
+impl Labels for NoLabels {
+	fn get_label(&self, _: u16) -> Option<&str> {
+		None
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Instruction {
+	pub address: u16,
+	pub opcode: Opcode,
+	// the opcode followed by its operand
+	pub bytes: Vec<u8>
+}
+
+impl Instruction {
+	// returns None if the bytes end before the instruction does
+	pub fn decode(address: u16, bytes: &[u8]) -> Option<Self> {
+		let opcode = get_opcode(*bytes.first()?);
+		let size = opcode.mode.get_size() as usize;
+		if bytes.len() < size {
+			return None;
+		}
+		Some(Self {
+			address,
+			opcode,
+			bytes: bytes[..size].to_vec()
+		})
+	}
+
+	pub fn get_size(&self) -> u16 {
+		self.bytes.len() as _
+	}
+
+	pub fn get_next_address(&self) -> u16 {
+		self.address.wrapping_add(self.get_size())
+	}
+
+	// the byte or the little endian word following the opcode
+	pub fn get_operand(&self) -> u16 {
+		match self.bytes.len() {
+			2 => self.bytes[1] as _,
+			3 => ((self.bytes[2] as u16) << 8) | self.bytes[1] as u16,
+			_ => 0
+		}
+	}
+
+	// the address the operand refers to, before indexing, or the destination of a branch
+	pub fn get_target(&self) -> Option<u16> {
+		match self.opcode.mode {
+			Implied | Accumulator | Immediate => None,
+			Relative => Some(self.get_next_address().wrapping_add(self.get_operand() as i8 as u16)),
+			_ => Some(self.get_operand())
+		}
+	}
+
+	pub fn format_bytes(&self) -> String {
+		self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ")
+	}
+
+	pub fn format_operand(&self, labels: &dyn Labels) -> String {
+		let operand = self.get_operand();
+		let target = match self.get_target().and_then(|address| labels.get_label(address)) {
+			Some(label) => label.to_string(),
+			None => match self.opcode.mode {
+				ZeroPage | ZeroPageX | ZeroPageY | IndirectX | IndirectY => format!("${:02X}", operand),
+				Relative => format!("${:04X}", self.get_target().unwrap()),
+				_ => format!("${:04X}", operand)
+			}
+		};
+		match self.opcode.mode {
+			Implied => String::new(),
+			Accumulator => "A".to_string(),
+			Immediate => format!("#${:02X}", operand),
+			ZeroPage | Absolute | Relative => target,
+			ZeroPageX | AbsoluteX => format!("{},X", target),
+			ZeroPageY | AbsoluteY => format!("{},Y", target),
+			Indirect => format!("({})", target),
+			IndirectX => format!("({},X)", target),
+			IndirectY => format!("({}),Y", target)
+		}
+	}
+
+	// the mnemonic and the operand, like "LDA $0200,X"
+	pub fn format(&self, labels: &dyn Labels) -> String {
+		let operand = self.format_operand(labels);
+		if operand.is_empty() {
+			self.opcode.mnemonic.to_string()
+		} else {
+			format!("{} {}", self.opcode.mnemonic, operand)
+		}
+	}
+}
+
+impl fmt::Display for Instruction {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		write!(formatter, "{}", self.format(&NoLabels))
+	}
+}
+
+// disassembles the bytes as if they were at the address; an instruction cut by the end of the bytes is left out
+pub fn disassemble(bytes: &[u8], address: u16) -> Vec<Instruction> {
+	let mut instructions = Vec::new();
+	let mut offset = 0;
+	while let Some(instruction) = Instruction::decode(address.wrapping_add(offset as u16), &bytes[offset..]) {
+		offset += instruction.bytes.len();
+		instructions.push(instruction);
+	}
+	instructions
+}
+
+// disassembles a number of instructions from an address space, the CPU's one for instance
+pub fn disassemble_memory<F: FnMut(u16) -> u8>(mut read: F, address: u16, count: usize) -> Vec<Instruction> {
+	let mut instructions = Vec::with_capacity(count);
+	let mut address = address;
+	for _ in 0..count {
+		let opcode = read(address);
+		let size = get_opcode(opcode).mode.get_size();
+		let bytes: Vec<u8> = (0..size).map(|offset| read(address.wrapping_add(offset))).collect();
+		let instruction = Instruction::decode(address, &bytes).unwrap();
+		address = instruction.get_next_address();
+		instructions.push(instruction);
+	}
+	instructions
+}
@@ -1,12 +1,38 @@
+use std::{
+	io,
+	path::Path
+};
+#[cfg(feature = "debugger")]
+use std::mem;
+
 use mappers::*;
 use cpu::*;
 use ppu::*;
 use apu::*;
 use joypad::*;
 use screen::*;
+use region::*;
+use symbols::*;
+use cdl::*;
+use profiler::*;
+use events::*;
+
+#[cfg(any(feature = "debugger", feature = "scripting"))]
+use accesses::AccessLog;
 
 pub const RAM_SIZE: usize = 0x800;
 
+// a snapshot of the console between two instructions, kept in memory
+pub struct SaveState {
+	ram: [u8; RAM_SIZE],
+	mapper: Option<Box<dyn Mapper>>,
+	cpu: Cpu,
+	ppu: Ppu,
+	apu: Apu,
+	joypad: Joypad,
+	ppu_cycle_remainder: u8
+}
+
 pub struct Emulator {
 	pub ram: [u8; RAM_SIZE],
 	pub mapper: Option<Box<dyn Mapper>>,
@@ -14,7 +40,23 @@ pub struct Emulator {
 	pub ppu: Ppu,
 	pub apu: Apu,
 	pub joypad: Joypad,
-	pub screen: Screen
+	pub screen: Screen,
+	pub region: Region,
+	pub symbols: SymbolTable,
+	pub code_data_log: Option<CodeDataLog>,
+	pub profiler: Option<Profiler>,
+	// the register accesses, for the event viewer
+	pub event_log: Option<EventLog>,
+	// accesses to the CPU bus, for the watchpoints of the debugger
+	#[cfg(feature = "debugger")]
+	pub memory_accesses: AccessLog,
+	// accesses to the CPU bus, for the memory callbacks of scripts
+	#[cfg(feature = "scripting")]
+	pub script_accesses: AccessLog,
+	synchronized_cycles: u8,
+	ppu_cycle_remainder: u8,
+	prg_rom_size: usize,
+	chr_rom_size: usize
 }
 
 impl Emulator {
@@ -26,7 +68,20 @@ impl Emulator {
 			ppu: Ppu::new(),
 			apu: Apu::new(),
 			joypad: Joypad::new(),
-			screen: Screen::new()
+			screen: Screen::new(),
+			region: Region::Ntsc,
+			symbols: SymbolTable::new(),
+			code_data_log: None,
+			profiler: None,
+			event_log: None,
+			#[cfg(feature = "debugger")]
+			memory_accesses: AccessLog::default(),
+			#[cfg(feature = "scripting")]
+			script_accesses: AccessLog::default(),
+			synchronized_cycles: 0,
+			ppu_cycle_remainder: 0,
+			prg_rom_size: 0,
+			chr_rom_size: 0
 		}
 	}
 
@@ -50,18 +105,143 @@ impl Emulator {
 		let chr_rom_end = chr_rom_start + chr_rom_size * 1024;
 		let chr_rom = &contents[chr_rom_start..chr_rom_end];
 		self.ppu.load_chr_rom(chr_rom);
+		self.prg_rom_size = prg_rom.len();
+		self.chr_rom_size = chr_rom.len();
 		
 		let mapper_number = (contents[7] & 0xf0) | (contents[6] >> 4);
 		info!("Cartridge mapper: {}", mapper_number);
 		self.mapper = Some(create_mapper(mapper_number, prg_rom));
 
-		self.cpu.init_pc();
+		let region = Region::detect(filename, &contents);
+		info!("Region: {:?}", region);
+		self.set_region(region);
+
+		self.symbols = SymbolTable::load_for_rom(filename);
+
+		Cpu::init_pc(self);
+	}
+
+	pub fn set_region(&mut self, region: Region) {
+		self.region = region;
+		self.ppu.set_region(region);
+		self.apu.set_region(region);
+	}
+
+	// the reset button, the RAM, the mapper and the joypad keeping their state
+	pub fn reset(&mut self) {
+		Cpu::reset(self);
+		self.ppu.reset();
+		self.synchronized_cycles = 0;
+	}
+
+	pub fn save_state(&self) -> SaveState {
+		SaveState {
+			ram: self.ram,
+			mapper: self.mapper.as_ref().map(|mapper| mapper.clone_box()),
+			cpu: self.cpu.save_state(),
+			ppu: self.ppu.clone(),
+			apu: self.apu.clone(),
+			joypad: self.joypad.clone(),
+			ppu_cycle_remainder: self.ppu_cycle_remainder
+		}
+	}
+
+	// the logs, the profiler and the watchpoints of the debugger go on from the loaded state
+	pub fn load_state(&mut self, state: &SaveState) {
+		self.ram = state.ram;
+		self.mapper = state.mapper.as_ref().map(|mapper| mapper.clone_box());
+		self.cpu.load_state(&state.cpu);
+		#[cfg(feature = "debugger")]
+		let memory_accesses = mem::take(&mut self.ppu.memory_accesses);
+		self.ppu = state.ppu.clone();
+		#[cfg(feature = "debugger")]
+		{
+			self.ppu.memory_accesses = memory_accesses;
+		}
+		self.apu = state.apu.clone();
+		self.joypad = state.joypad.clone();
+		self.synchronized_cycles = 0;
+		self.ppu_cycle_remainder = state.ppu_cycle_remainder;
+	}
+
+	// tells apart the PRG ROM banks mapped at the same CPU address
+	pub fn get_prg_rom_offset(&self, address: u16) -> Option<usize> {
+		self.mapper.as_ref().and_then(|mapper| mapper.get_prg_rom_offset(address))
+	}
+
+	// labels of the CPU address space with the banks mapped now
+	pub fn get_labels(&self) -> MappedLabels<'_, impl Fn(u16) -> Option<usize> + '_> {
+		self.symbols.with_mapping(move |address| self.get_prg_rom_offset(address))
+	}
+
+	pub fn get_symbol_address(&self, name: &str) -> Option<u16> {
+		self.symbols.get_address(name, |address| self.get_prg_rom_offset(address))
+	}
+
+	// logs the PRG and CHR ROM bytes accessed until stop_code_data_log is called, continuing the log in the file if there is one
+	pub fn start_code_data_log<P: AsRef<Path>>(&mut self, filename: P) -> io::Result<()> {
+		self.stop_code_data_log()?;
+		self.code_data_log = Some(CodeDataLog::open(filename, self.prg_rom_size, self.chr_rom_size)?);
+		Ok(())
+	}
+
+	// saves the log
+	pub fn stop_code_data_log(&mut self) -> io::Result<()> {
+		match self.code_data_log.take() {
+			Some(code_data_log) => code_data_log.save(),
+			None => Ok(())
+		}
+	}
+
+	// profiles the CPU until stop_profiler is called, replacing any profile in progress
+	pub fn start_profiler<P: AsRef<Path>>(&mut self, filename: P) -> io::Result<()> {
+		self.stop_profiler()?;
+		self.profiler = Some(Profiler::new(filename));
+		Ok(())
+	}
+
+	// saves the report, with the labels of the symbol files
+	pub fn stop_profiler(&mut self) -> io::Result<()> {
+		match self.profiler.take() {
+			Some(profiler) => profiler.save(&self.symbols),
+			None => Ok(())
+		}
+	}
+
+	// keeps the events already logged if the log is enabled again
+	pub fn set_event_logging(&mut self, enabled: bool) {
+		if !enabled {
+			self.event_log = None;
+		} else if self.event_log.is_none() {
+			self.event_log = Some(EventLog::new());
+		}
+	}
+
+	// runs until the PPU has a new frame to draw
+	pub fn run_frame(&mut self) {
+		while !self.screen.is_draw_requested() {
+			self.step();
+		}
 	}
 
 	pub fn step(&mut self) {
-		let cycles = 3 * self.cpu.execute_next_instruction();
-		for _ in 0..cycles {
-			self.ppu.do_cycle(&mut self.cpu, &mut self.screen);
+		let cycles = Cpu::execute_next_instruction(self);
+		self.synchronize_ppu(cycles - 1);
+		self.cpu.poll_interrupts();
+		self.synchronize_ppu(cycles);
+		self.synchronized_cycles = 0;
+	}
+
+	// runs the PPU until it has caught up with the given cycle of the current CPU instruction
+	pub fn synchronize_ppu(&mut self, cpu_cycle: u8) {
+		let (ppu_cycles, cpu_cycles) = self.region.get_ppu_cycles_per_cpu_cycle();
+		while self.synchronized_cycles < cpu_cycle {
+			self.ppu_cycle_remainder += ppu_cycles;
+			while self.ppu_cycle_remainder >= cpu_cycles {
+				self.ppu.do_cycle(&mut self.cpu, &mut self.screen, self.code_data_log.as_mut());
+				self.ppu_cycle_remainder -= cpu_cycles;
+			}
+			self.synchronized_cycles += 1;
 		}
 	}
 }
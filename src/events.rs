@@ -0,0 +1,102 @@
+use std::{fmt, mem};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EventKind {
+	Read,
+	Write
+}
+
+// an access to a PPU, APU or mapper register, with the position of the PPU and the instruction doing it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Event {
+	pub kind: EventKind,
+	pub address: u16,
+	pub value: u8,
+	pub pc: u16,
+	pub scanline: u16,
+	pub dot: u16
+}
+
+impl fmt::Display for Event {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		write!(formatter, "{:>3},{:>3} PC:{:04X} ", self.scanline, self.dot, self.pc)?;
+		match self.kind {
+			EventKind::Read => write!(formatter, "read {:02X} from {:04X}", self.value, self.address)?,
+			EventKind::Write => write!(formatter, "write {:02X} to {:04X}", self.value, self.address)?
+		}
+		match get_register_name(self.address) {
+			Some(name) => write!(formatter, " {}", name),
+			None => Ok(())
+		}
+	}
+}
+
+// the registers at $2000-$2007, $4000-$4017 and the mapper ones, written at $8000-$FFFF
+pub fn is_register(kind: EventKind, address: u16) -> bool {
+	match address {
+		0x2000 ..= 0x2007 | 0x4000 ..= 0x4017 => true,
+		0x8000 ..= 0xffff => kind == EventKind::Write,
+		_ => false
+	}
+}
+
+pub fn get_register_name(address: u16) -> Option<&'static str> {
+	let name = match address {
+		0x2000 => "PPUCTRL",
+		0x2001 => "PPUMASK",
+		0x2002 => "PPUSTATUS",
+		0x2003 => "OAMADDR",
+		0x2004 => "OAMDATA",
+		0x2005 => "PPUSCROLL",
+		0x2006 => "PPUADDR",
+		0x2007 => "PPUDATA",
+		0x4000 ..= 0x4003 => "SQ1",
+		0x4004 ..= 0x4007 => "SQ2",
+		0x4008 ..= 0x400b => "TRI",
+		0x400c ..= 0x400f => "NOISE",
+		0x4010 ..= 0x4013 => "DMC",
+		0x4014 => "OAMDMA",
+		0x4015 => "SND_CHN",
+		0x4016 => "JOY1",
+		0x4017 => "JOY2",
+		0x8000 ..= 0xffff => "mapper",
+		_ => return None
+	};
+	Some(name)
+}
+
+// the register accesses of the current frame and the one before, a frame starting with VBlank
+#[derive(Default)]
+pub struct EventLog {
+	frame: u64,
+	events: Vec<Event>,
+	previous_frame: u64,
+	previous_events: Vec<Event>
+}
+
+impl EventLog {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// the frame counter of the PPU tells when a new frame starts
+	pub fn record(&mut self, frame: u64, event: Event) {
+		if frame != self.frame {
+			self.previous_frame = self.frame;
+			self.previous_events = mem::take(&mut self.events);
+			self.frame = frame;
+		}
+		self.events.push(event);
+	}
+
+	// the events of the frame before the current one of the PPU, none if it had none
+	pub fn get_last_frame(&self, frame: u64) -> &[Event] {
+		if self.frame + 1 == frame {
+			&self.events
+		} else if self.frame == frame && self.previous_frame + 1 == frame {
+			&self.previous_events
+		} else {
+			&[]
+		}
+	}
+}
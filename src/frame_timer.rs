@@ -0,0 +1,56 @@
+use std::time::{Instant, Duration};
+
+// keeps the emulation at the console frame rate and measures the actual one
+pub struct FrameTimer {
+	frame_rate: f64,
+	frame_counter: u16,
+	frame_counting_instant: Instant,
+	last_frame_instant: Instant,
+	last_frame_extra_sleep_time: Duration
+}
+
+impl FrameTimer {
+	pub fn new(frame_rate: f64) -> Self {
+		Self {
+			frame_rate,
+			frame_counter: 0,
+			frame_counting_instant: Instant::now(),
+			last_frame_instant: Instant::now(),
+			last_frame_extra_sleep_time: Duration::new(0, 0)
+		}
+	}
+
+	// sleeps until the next frame is due
+	pub fn regulate_frame_rate(&mut self) {
+		if cfg!(not(feature = "fullspeed")) {
+			let frame_duration = Duration::from_secs_f64(1.0 / self.frame_rate);
+			let last_frame_duration = self.last_frame_instant.elapsed();
+			if last_frame_duration < frame_duration {
+				let mut sleep_time = frame_duration - last_frame_duration;
+				if sleep_time > self.last_frame_extra_sleep_time {
+					sleep_time -= self.last_frame_extra_sleep_time;
+					std::thread::sleep(sleep_time);
+					self.last_frame_extra_sleep_time = self.last_frame_instant.elapsed() - last_frame_duration - sleep_time;
+				} else {
+					self.last_frame_extra_sleep_time -= sleep_time;
+				}
+			}
+			self.last_frame_instant = Instant::now();
+		}
+	}
+
+	// returns the frames per second and the speed in percent once every second
+	pub fn count_frame(&mut self) -> Option<(f64, f64)> {
+		self.frame_counter += 1;
+		let elapsed = self.frame_counting_instant.elapsed();
+		if elapsed >= Duration::from_secs(1) {
+			self.frame_counting_instant = Instant::now();
+			let fps = (self.frame_counter as f64 / elapsed.as_secs_f64()).round();
+			self.frame_counter = 0;
+			let speed = (100.0 * fps / self.frame_rate).round();
+			Some((fps, speed))
+		} else {
+			None
+		}
+	}
+}
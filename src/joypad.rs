@@ -1,3 +1,4 @@
+#[derive(Clone)]
 pub struct Joypad {
     register: u8,
     strobe: bool,
@@ -101,7 +102,18 @@ impl Joypad {
         }
     }
 
-    #[cfg(any(feature = "trace", test))]
+    // buttons in the order of the register: A, B, Select, Start, Up, Down, Left and Right from bit 0 to 7
+    pub fn set_buttons(&mut self, buttons: u8) {
+        self.a_button_down = (buttons & 0x01) != 0;
+        self.b_button_down = (buttons & 0x02) != 0;
+        self.select_button_down = (buttons & 0x04) != 0;
+        self.start_button_down = (buttons & 0x08) != 0;
+        self.up_button_down = (buttons & 0x10) != 0;
+        self.down_button_down = (buttons & 0x20) != 0;
+        self.left_button_down = (buttons & 0x40) != 0;
+        self.right_button_down = (buttons & 0x80) != 0;
+    }
+
     pub fn read_debug(&self) -> u8 {
         if self.strobe {
             self.a_button_down as u8
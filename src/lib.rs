@@ -0,0 +1,32 @@
+#[macro_use]
+extern crate log;
+#[cfg(feature = "scripting")]
+extern crate rhai;
+
+pub mod emulator;
+pub mod cpu;
+pub mod ppu;
+pub mod apu;
+pub mod joypad;
+pub mod mappers;
+pub mod screen;
+pub mod region;
+pub mod checksum;
+pub mod palette;
+pub mod ntsc;
+pub mod png;
+pub mod screenshot;
+pub mod avi;
+pub mod movie;
+pub mod disasm;
+pub mod symbols;
+pub mod cdl;
+pub mod profiler;
+pub mod events;
+
+#[cfg(any(feature = "debugger", feature = "scripting"))]
+pub mod accesses;
+#[cfg(feature = "debugger")]
+pub mod debugger;
+#[cfg(feature = "scripting")]
+pub mod scripting;
@@ -1,57 +1,562 @@
 extern crate winit;
 extern crate wgpu;
 extern crate futures;
+#[cfg(feature = "software-renderer")]
+extern crate raw_window_handle;
+#[cfg(all(feature = "software-renderer", any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd")))]
+extern crate x11_dl;
+#[cfg(all(feature = "software-renderer", target_os = "windows"))]
+extern crate winapi;
+extern crate mu;
 
 #[macro_use]
 extern crate log;
 
-mod emulator;
 mod renderer;
-mod cpu;
-mod ppu;
-mod apu;
-mod joypad;
-mod mappers;
-mod screen;
+mod options;
+mod frame_timer;
+mod viewers;
 
-use std::time::{Instant, Duration};
+use std::{
+	borrow::Cow,
+	path::{Path, PathBuf}
+};
 
 use winit::{
 	event::*,
 	event_loop::{ControlFlow, EventLoop},
-	window::WindowBuilder
+	window::{WindowBuilder, Fullscreen}
+};
+
+use mu::{
+	emulator::*,
+	cpu::*,
+	screen::*,
+	palette::*,
+	ntsc::*,
+	png::*,
+	screenshot::*,
+	avi::*,
+	disasm::*,
+	symbols::*
 };
 
-use emulator::*;
+#[cfg(feature = "debugger")]
+use mu::debugger::*;
+#[cfg(feature = "scripting")]
+use mu::scripting::*;
+
 use renderer::*;
-use screen::*;
+use options::*;
+use frame_timer::*;
+use viewers::*;
+
+const EMULATOR_NAME: &str = "MU";
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+// palette and NTSC filter, which both renderers change with hotkeys
+struct VideoSettings {
+	palettes: Vec<Palette>,
+	palette_index: usize,
+	ntsc_filter_enabled: bool,
+	ntsc_parameters: NtscParameters,
+	filter_parameters: FilterParameters
+}
+
+impl VideoSettings {
+	fn new(options: &Options) -> Self {
+		// builtin, generated and loaded palettes
+		let mut palettes = vec![Palette::new(), Palette::generate(&options.ntsc_parameters)];
+		// start with the loaded palette if there is one
+		let mut palette_index = 0;
+		if let Some(ref filename) = options.palette {
+			match Palette::load(filename) {
+				Ok(palette) => {
+					palettes.push(palette);
+					palette_index = palettes.len() - 1;
+				},
+				Err(error) => error!("Couldn't load the palette {}: {}", filename, error)
+			}
+		}
+		Self {
+			palettes,
+			palette_index,
+			ntsc_filter_enabled: options.ntsc_filter,
+			ntsc_parameters: options.ntsc_parameters,
+			filter_parameters: options.filter_parameters
+		}
+	}
+
+	fn get_palette(&self) -> &Palette {
+		&self.palettes[self.palette_index]
+	}
+
+	fn apply(&self, screen: &mut Screen) {
+		screen.set_palette(self.palettes[self.palette_index].clone());
+		let ntsc_filter = if self.ntsc_filter_enabled {
+			Some(NtscFilter::new(&self.ntsc_parameters, &self.filter_parameters))
+		} else {
+			None
+		};
+		screen.set_ntsc_filter(ntsc_filter);
+	}
+
+	fn cycle_palette(&mut self, screen: &mut Screen) {
+		self.palette_index = (self.palette_index + 1) % self.palettes.len();
+		self.apply(screen);
+	}
+
+	fn toggle_ntsc_filter(&mut self, screen: &mut Screen) {
+		self.ntsc_filter_enabled = !self.ntsc_filter_enabled;
+		self.apply(screen);
+	}
+}
+
+// F12 saves the frame as the emulator outputs it, or scaled to the window without the shader preset
+struct Screenshots {
+	directory: String,
+	rom_filename: String,
+	scaled: bool,
+	display_settings: DisplaySettings
+}
+
+impl Screenshots {
+	fn new(options: &Options) -> Self {
+		Self {
+			directory: options.screenshot_directory.clone(),
+			rom_filename: options.filename.clone(),
+			scaled: options.scaled_screenshots,
+			display_settings: options.display_settings
+		}
+	}
+
+	fn take(&self, screen: &Screen, window_width: usize, window_height: usize) {
+		let result = if self.scaled {
+			let image = self.display_settings.scale_frame(screen.get_frame_buffer(), screen.get_frame_width(), FRAME_HEIGHT, window_width, window_height);
+			get_timestamped_filename(&self.directory, &self.rom_filename, "png").and_then(|filename| {
+				save_png(&filename.to_string_lossy(), window_width, window_height, &image).map(|_| filename)
+			})
+		} else {
+			save_screenshot(screen, &self.directory, &self.rom_filename)
+		};
+		match result {
+			Ok(filename) => info!("Saved screenshot {}", filename.display()),
+			Err(error) => error!("Couldn't save the screenshot: {}", error)
+		}
+	}
+}
+
+// F9 starts and stops recording every emulated frame, whatever the speed of the host, without audio
+struct VideoRecording {
+	directory: String,
+	rom_filename: String,
+	frame_rate: f64,
+	recorder: Option<AviRecorder>
+}
+
+impl VideoRecording {
+	fn new(options: &Options, screen: &Screen, frame_rate: f64) -> Self {
+		let mut recording = Self {
+			directory: options.recording_directory.clone(),
+			rom_filename: options.filename.clone(),
+			frame_rate,
+			recorder: None
+		};
+		if let Some(ref filename) = options.recording {
+			recording.start(filename, screen);
+		}
+		recording
+	}
+
+	fn start(&mut self, filename: &str, screen: &Screen) {
+		match AviRecorder::new(filename, screen.get_frame_width(), FRAME_HEIGHT, self.frame_rate) {
+			Ok(recorder) => {
+				info!("Recording to {}", filename);
+				self.recorder = Some(recorder);
+			},
+			Err(error) => error!("Couldn't start the recording: {}", error)
+		}
+	}
+
+	fn toggle(&mut self, screen: &Screen) {
+		if self.recorder.is_some() {
+			self.stop();
+		} else {
+			match get_timestamped_filename(&self.directory, &self.rom_filename, "avi") {
+				Ok(filename) => self.start(&filename.to_string_lossy(), screen),
+				Err(error) => error!("Couldn't start the recording: {}", error)
+			}
+		}
+	}
+
+	fn record_frame(&mut self, screen: &Screen) {
+		if let Some(ref mut recorder) = self.recorder {
+			if let Err(error) = recorder.record_frame(screen.get_frame_buffer(), screen.get_frame_width(), FRAME_HEIGHT) {
+				error!("{}", error);
+				self.stop();
+			}
+		}
+	}
+
+	fn stop(&mut self) {
+		if let Some(recorder) = self.recorder.take() {
+			let frame_count = recorder.get_frame_count();
+			match recorder.finish() {
+				Ok(()) => info!("Recorded {} frames", frame_count),
+				Err(error) => error!("Couldn't finish the recording: {}", error)
+			}
+		}
+	}
+}
+
+const DEFAULT_TRACE_FILENAME: &str = "trace.log";
+
+// F8 starts and stops the trace log of the CPU, to the file given by --trace or trace.log
+struct Tracing {
+	filename: String,
+	options: TraceOptions
+}
+
+impl Tracing {
+	fn new(options: &Options, cpu: &mut Cpu) -> Self {
+		let tracing = Self {
+			filename: options.trace.clone().unwrap_or_else(|| DEFAULT_TRACE_FILENAME.to_string()),
+			options: options.trace_options
+		};
+		if options.trace.is_some() {
+			tracing.start(cpu);
+		}
+		tracing
+	}
+
+	fn start(&self, cpu: &mut Cpu) {
+		match cpu.start_trace(&self.filename, self.options) {
+			Ok(()) => info!("Tracing to {}", self.filename),
+			Err(error) => error!("Couldn't start the trace: {}", error)
+		}
+	}
+
+	fn toggle(&self, cpu: &mut Cpu) {
+		if cpu.is_tracing() {
+			self.stop(cpu);
+		} else {
+			self.start(cpu);
+		}
+	}
+
+	fn stop(&self, cpu: &mut Cpu) {
+		if cpu.is_tracing() {
+			match cpu.stop_trace() {
+				Ok(()) => info!("Saved the trace to {}", self.filename),
+				Err(error) => error!("Couldn't write the trace: {}", error)
+			}
+		}
+	}
+}
+
+// F7 starts and stops the code/data log, to the file given by --cdl or the name of the ROM with a .cdl extension
+struct CodeDataLogging {
+	filename: PathBuf
+}
+
+impl CodeDataLogging {
+	fn new(options: &Options, emulator: &mut Emulator) -> Self {
+		let logging = Self {
+			filename: options.code_data_log.as_ref().map(PathBuf::from).unwrap_or_else(|| Path::new(&options.filename).with_extension("cdl"))
+		};
+		if options.code_data_log.is_some() {
+			logging.start(emulator);
+		}
+		logging
+	}
+
+	fn start(&self, emulator: &mut Emulator) {
+		match emulator.start_code_data_log(&self.filename) {
+			Ok(()) => info!("Logging code and data to {}", self.filename.display()),
+			Err(error) => error!("Couldn't start the code/data log: {}", error)
+		}
+	}
+
+	fn toggle(&self, emulator: &mut Emulator) {
+		if emulator.code_data_log.is_some() {
+			self.stop(emulator);
+		} else {
+			self.start(emulator);
+		}
+	}
+
+	fn stop(&self, emulator: &mut Emulator) {
+		let description = match emulator.code_data_log {
+			Some(ref code_data_log) => code_data_log.describe(),
+			None => return
+		};
+		match emulator.stop_code_data_log() {
+			Ok(()) => info!("Saved the code/data log to {}, {}", self.filename.display(), description),
+			Err(error) => error!("Couldn't save the code/data log: {}", error)
+		}
+	}
+}
+
+// F6 starts and stops the profiler, writing the report to the file given by --profile or the name of the ROM with a .profile.txt extension
+struct Profiling {
+	filename: PathBuf
+}
+
+impl Profiling {
+	fn new(options: &Options, emulator: &mut Emulator) -> Self {
+		let profiling = Self {
+			filename: options.profile.as_ref().map(PathBuf::from).unwrap_or_else(|| Path::new(&options.filename).with_extension("profile.txt"))
+		};
+		if options.profile.is_some() {
+			profiling.start(emulator);
+		}
+		profiling
+	}
+
+	fn start(&self, emulator: &mut Emulator) {
+		match emulator.start_profiler(&self.filename) {
+			Ok(()) => info!("Profiling to {}", self.filename.display()),
+			Err(error) => error!("Couldn't start the profiler: {}", error)
+		}
+	}
+
+	fn toggle(&self, emulator: &mut Emulator) {
+		if emulator.profiler.is_some() {
+			self.stop(emulator);
+		} else {
+			self.start(emulator);
+		}
+	}
+
+	fn stop(&self, emulator: &mut Emulator) {
+		let description = match emulator.profiler {
+			Some(ref profiler) => profiler.describe(),
+			None => return
+		};
+		match emulator.stop_profiler() {
+			Ok(()) => info!("Saved the profile to {}, {}", self.filename.display(), description),
+			Err(error) => error!("Couldn't save the profile: {}", error)
+		}
+	}
+}
+
+// runs the frames through the debugger and the script when they are enabled
+struct FrameRunner {
+	#[cfg(feature = "debugger")]
+	debugger: Debugger,
+	#[cfg(feature = "scripting")]
+	script: Option<Script>
+}
+
+impl FrameRunner {
+	#[cfg_attr(not(feature = "scripting"), allow(unused_variables))]
+	fn new(options: &Options, emulator: &mut Emulator) -> Self {
+		Self {
+			#[cfg(feature = "debugger")]
+			debugger: create_debugger(options),
+			#[cfg(feature = "scripting")]
+			script: options.script.as_ref().map(|filename| Script::load(filename, emulator).unwrap_or_else(|error| {
+				error!("Couldn't load the script {}: {}", filename, error);
+				std::process::exit(2);
+			}))
+		}
+	}
+
+	// runs until the PPU has a new frame to draw, or less while the debugger pauses the emulation
+	fn run_frame(&mut self, emulator: &mut Emulator) {
+		#[cfg(feature = "scripting")]
+		{
+			if let Some(ref mut script) = self.script {
+				#[cfg(not(feature = "debugger"))]
+				script.run_frame(emulator);
+				#[cfg(feature = "debugger")]
+				{
+					self.debugger.run_frame_with(emulator, &mut |emulator| script.step(emulator));
+					if emulator.screen.is_draw_requested() {
+						script.end_frame(emulator);
+					}
+				}
+				return;
+			}
+		}
+		#[cfg(not(feature = "debugger"))]
+		emulator.run_frame();
+		#[cfg(feature = "debugger")]
+		self.debugger.run_frame(emulator);
+	}
+
+	// the frame with what the script draws over it
+	fn get_frame_buffer<'a>(&self, screen: &'a Screen) -> Cow<'a, [u8]> {
+		#[cfg(feature = "scripting")]
+		{
+			if let Some(frame_buffer) = self.script.as_ref().and_then(|script| script.draw_overlay(screen.get_frame_buffer(), screen.get_frame_width())) {
+				return Cow::Owned(frame_buffer);
+			}
+		}
+		Cow::Borrowed(screen.get_frame_buffer())
+	}
+}
 
 fn main() {
 	env_logger::Builder::new().filter_level(log::LevelFilter::Info).init();
 
-	let filename = std::env::args().nth(1).unwrap();
+	if std::env::args().nth(1).as_deref() == Some("disasm") {
+		run_disassembler(&DisassemblerOptions::parse());
+		return;
+	}
+
+	let options = Options::parse();
 	let mut emulator = Emulator::new();
-	emulator.load_file(&filename);
-	
+	emulator.load_file(&options.filename);
+	if let Some(region) = options.region {
+		emulator.set_region(region);
+	}
+	for filename in &options.symbol_files {
+		if let Err(error) = emulator.symbols.load(filename) {
+			error!("Couldn't load the symbols of {}: {}", filename, error);
+		}
+	}
+
+	let video_settings = VideoSettings::new(&options);
+	video_settings.apply(&mut emulator.screen);
+
+	run_renderer(emulator, video_settings, &options);
+}
+
+// prints the listing of a 16KB PRG ROM bank, the last one being at $C000 and the others at $8000 unless an origin is given
+fn run_disassembler(options: &DisassemblerOptions) {
+	let contents = std::fs::read(&options.filename).unwrap();
+	if contents.len() < 16 || &contents[..4] != b"NES\x1a" {
+		panic!("Wrong file format");
+	}
+
+	let bank_count = contents[4] as usize;
+	if options.bank >= bank_count {
+		panic!("No PRG ROM bank {}, the ROM has {}", options.bank, bank_count);
+	}
+	// the 512 bytes of trainer are between the header and the PRG ROM
+	let prg_rom_start = if (contents[6] & 0x04) != 0 { 16 + 512 } else { 16 };
+	let bank_start = prg_rom_start + options.bank * PRG_BANK_SIZE;
+	let bank = contents.get(bank_start..bank_start + PRG_BANK_SIZE)
+		.unwrap_or_else(|| panic!("The PRG ROM bank {} is truncated, the file has {} bytes", options.bank, contents.len()));
+	let origin = options.origin.unwrap_or(if options.bank == bank_count - 1 {
+		0xc000
+	} else {
+		0x8000
+	});
+
+	let mut symbols = SymbolTable::load_for_rom(&options.filename);
+	for filename in &options.symbol_files {
+		symbols.load(filename).unwrap_or_else(|error| panic!("Couldn't load the symbols of {}: {}", filename, error));
+	}
+	// the addresses outside of the bank are in the last one when it is fixed at $C000, as with UxROM
+	let last_bank_start = (bank_count - 1) * PRG_BANK_SIZE;
+	let labels = symbols.with_mapping(|address| {
+		let offset = address.wrapping_sub(origin) as usize;
+		if offset < PRG_BANK_SIZE {
+			Some(options.bank * PRG_BANK_SIZE + offset)
+		} else if address >= 0xc000 && origin == 0x8000 {
+			Some(last_bank_start + (address - 0xc000) as usize)
+		} else {
+			None
+		}
+	});
+
+	println!("; PRG ROM bank {} of {} at ${:04X}", options.bank, bank_count, origin);
+	let instructions = disassemble(bank, origin);
+	for instruction in &instructions {
+		if let Some(label) = labels.get_label(instruction.address) {
+			println!("{}:", label);
+		}
+		println!("{:04X}  {:<8}  {}", instruction.address, instruction.format_bytes(), instruction.format(&labels));
+	}
+	// the bytes of an instruction cut by the end of the bank
+	let size: usize = instructions.iter().map(|instruction| instruction.bytes.len()).sum();
+	for (offset, byte) in bank.iter().enumerate().skip(size) {
+		println!("{:04X}  {:02X}        .byte ${:02X}", origin.wrapping_add(offset as u16), byte, byte);
+	}
+}
+
+#[cfg(feature = "debugger")]
+fn create_debugger(options: &Options) -> Debugger {
+	let mut debugger = Debugger::new();
+	if let Some(port) = options.gdb_port {
+		if let Err(error) = debugger.listen_gdb(port) {
+			error!("Couldn't listen for a GDB client on port {}: {}", port, error);
+		}
+	}
+	debugger
+}
+
+fn get_title(fps: f64, speed: f64) -> String {
+	format!("{} - FPS: {} - SPEED: {}%", EMULATOR_NAME, fps, speed)
+}
+
+fn run_renderer(mut emulator: Emulator, mut video_settings: VideoSettings, options: &Options) -> ! {
 	let event_loop = EventLoop::new();
 
-	const EMULATOR_NAME: &str = "MU";
 	let window = WindowBuilder::new().with_title(EMULATOR_NAME).build(&event_loop).unwrap();
-	
-	let mut renderer = Renderer::new(&window, FRAME_WIDTH as _, FRAME_HEIGHT as _);
 
-	let mut frame_counter = 0u16;
-	let mut frame_counting_instant = Instant::now();
-	let mut last_frame_instant = Instant::now();
-	let mut last_frame_extra_sleep_time = Duration::new(0, 0);
+	let mut renderer = Renderer::new(&window, emulator.screen.get_frame_width() as _, FRAME_HEIGHT as _, options.software_rendering).unwrap_or_else(|error| {
+		error!("{}", error);
+		std::process::exit(1);
+	});
+
+	// the S key cycles through the builtin shader presets and the loaded one
+	let mut presets: Vec<Preset> = BUILTIN_PRESETS.iter().map(|name| Preset::builtin(name).unwrap()).collect();
+	let mut preset_index = 0;
+	if let Some(ref name) = options.shader_preset {
+		match Preset::load(name) {
+			Ok(preset) => preset_index = presets.iter().position(|builtin_preset| builtin_preset.name == preset.name).unwrap_or_else(|| {
+				presets.push(preset);
+				presets.len() - 1
+			}),
+			Err(error) => error!("Couldn't load the shader preset {}: {}", name, error)
+		}
+	}
+	renderer.set_preset(presets[preset_index].clone());
+	renderer.set_display_settings(options.display_settings);
+
+	// F11 toggles fullscreen
+	let toggle_fullscreen = |window: &winit::window::Window| if window.fullscreen().is_some() {
+		window.set_fullscreen(None);
+	} else {
+		window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+	};
+	if options.fullscreen {
+		toggle_fullscreen(&window);
+	}
+
+	let screenshots = Screenshots::new(options);
+	let mut recording = VideoRecording::new(options, &emulator.screen, emulator.region.get_frame_rate());
+	let tracing = Tracing::new(options, &mut emulator.cpu);
+	let code_data_logging = CodeDataLogging::new(options, &mut emulator);
+	let profiling = Profiling::new(options, &mut emulator);
+	let mut frame_timer = FrameTimer::new(emulator.region.get_frame_rate());
 
-	event_loop.run(move |event, _, control_flow| {
+	// F1 to F5 toggle the debug viewers
+	let mut viewers = Viewers::new();
+
+	let mut frame_runner = FrameRunner::new(options, &mut emulator);
+
+	event_loop.run(move |event, event_loop, control_flow| {
 		match event {
+			Event::WindowEvent {
+				window_id,
+				ref event
+			} if window_id != window.id() => viewers.handle_window_event(window_id, event, &renderer),
 			Event::WindowEvent {
 				ref event,
 				..
 			} => match event {
-				WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+				WindowEvent::CloseRequested => {
+					recording.stop();
+					tracing.stop(&mut emulator.cpu);
+					code_data_logging.stop(&mut emulator);
+					profiling.stop(&mut emulator);
+					*control_flow = ControlFlow::Exit;
+				},
 				WindowEvent::KeyboardInput {
 					ref input,
 					..
@@ -69,6 +574,33 @@ fn main() {
 						Some(VirtualKeyCode::Down) => emulator.joypad.press_down_button(),
 						Some(VirtualKeyCode::Left) => emulator.joypad.press_left_button(),
 						Some(VirtualKeyCode::Right) => emulator.joypad.press_right_button(),
+						Some(VirtualKeyCode::P) => video_settings.cycle_palette(&mut emulator.screen),
+						Some(VirtualKeyCode::N) => {
+							video_settings.toggle_ntsc_filter(&mut emulator.screen);
+							renderer.set_frame_size(emulator.screen.get_frame_width() as _, FRAME_HEIGHT as _);
+						},
+						Some(VirtualKeyCode::S) => {
+							preset_index = (preset_index + 1) % presets.len();
+							info!("Shader preset: {}", presets[preset_index].name);
+							renderer.set_preset(presets[preset_index].clone());
+						},
+						Some(VirtualKeyCode::F1) => viewers.toggle(ViewerKind::Nametables, emulator.region, event_loop, &renderer),
+						Some(VirtualKeyCode::F2) => viewers.toggle(ViewerKind::PatternTables, emulator.region, event_loop, &renderer),
+						Some(VirtualKeyCode::F3) => viewers.toggle(ViewerKind::Palette, emulator.region, event_loop, &renderer),
+						Some(VirtualKeyCode::F4) => viewers.toggle(ViewerKind::Oam, emulator.region, event_loop, &renderer),
+						Some(VirtualKeyCode::F5) => {
+							viewers.toggle(ViewerKind::Events, emulator.region, event_loop, &renderer);
+							emulator.set_event_logging(viewers.is_open(ViewerKind::Events));
+						},
+						Some(VirtualKeyCode::F6) => profiling.toggle(&mut emulator),
+						Some(VirtualKeyCode::F7) => code_data_logging.toggle(&mut emulator),
+						Some(VirtualKeyCode::F8) => tracing.toggle(&mut emulator.cpu),
+						Some(VirtualKeyCode::F9) => recording.toggle(&emulator.screen),
+						Some(VirtualKeyCode::F11) => toggle_fullscreen(&window),
+						Some(VirtualKeyCode::F12) => {
+							let size = window.inner_size();
+							screenshots.take(&emulator.screen, size.width as _, size.height as _);
+						},
 						_ => {}
 					},
 					KeyboardInput {
@@ -95,41 +627,20 @@ fn main() {
 				_ => {}
 			},
 			Event::MainEventsCleared => {
-				// draw frame
-				while !emulator.screen.is_draw_requested() {
-					emulator.step();
-				}
-				renderer.draw(emulator.screen.get_frame_buffer());
-				emulator.screen.finish_draw();
-
-				// regulate frame rate
-				if cfg!(not(feature = "fullspeed")) {
-					const FRAME_DURATION: Duration = Duration::from_nanos(16_666_667);
-					let last_frame_duration = last_frame_instant.elapsed();
-					if last_frame_duration < FRAME_DURATION {
-						let mut sleep_time = FRAME_DURATION - last_frame_duration;
-						if sleep_time > last_frame_extra_sleep_time {
-							sleep_time -= last_frame_extra_sleep_time;
-							std::thread::sleep(sleep_time);
-							last_frame_extra_sleep_time = last_frame_instant.elapsed() - last_frame_duration - sleep_time;
-						} else {
-							last_frame_extra_sleep_time -= sleep_time;
-						}
-					}
-					last_frame_instant = Instant::now();
+				// draw frame, or the last one while the debugger pauses the emulation
+				frame_runner.run_frame(&mut emulator);
+				renderer.draw(&frame_runner.get_frame_buffer(&emulator.screen));
+				if emulator.screen.is_draw_requested() {
+					recording.record_frame(&emulator.screen);
+					viewers.update(&emulator, video_settings.get_palette(), &renderer);
+					// the event viewer may have been closed
+					emulator.set_event_logging(viewers.is_open(ViewerKind::Events));
+					emulator.screen.finish_draw();
 				}
 
-				// compute and display frame rate
-				frame_counter += 1;
-				let elapsed = frame_counting_instant.elapsed();
-				if elapsed >= Duration::from_secs(1) {
-					frame_counting_instant = Instant::now();
-					let fps = (frame_counter as f64 / elapsed.as_secs_f64()).round();
-					frame_counter = 0;
-					const FRAME_RATE: f64 = 60.0;
-					let speed = (100.0 * fps / FRAME_RATE).round();
-					let title = format!("{} - FPS: {} - SPEED: {}%", EMULATOR_NAME, fps, speed);
-					window.set_title(&title);
+				frame_timer.regulate_frame_rate();
+				if let Some((fps, speed)) = frame_timer.count_frame() {
+					window.set_title(&get_title(fps, speed));
 				}
 			},
 			_ => {}
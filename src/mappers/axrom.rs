@@ -5,6 +5,7 @@ const PRG_ROM_BANK_SIZE: usize = 0x8000;
 const PRG_ROM_START: u16 = 0x8000;
 const PRG_ROM_END: u16 = 0xffff;
 
+#[derive(Clone)]
 pub(super) struct Axrom {
     prg_rom: Vec<u8>,
     prg_rom_bank: u8
@@ -22,7 +23,7 @@ impl Axrom {
 impl Mapper for Axrom {
     fn read(&self, address: u16) -> u8 {
         match address {
-            PRG_ROM_START ..= PRG_ROM_END => self.prg_rom[(address - PRG_ROM_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank as usize], // switchable
+            PRG_ROM_START ..= PRG_ROM_END => self.prg_rom[self.get_prg_rom_offset(address).unwrap()],
             _ => unimplemented!()
         }
     }
@@ -33,4 +34,15 @@ impl Mapper for Axrom {
             _ => unimplemented!()
         }
     }
+
+    fn get_prg_rom_offset(&self, address: u16) -> Option<usize> {
+        match address {
+            PRG_ROM_START ..= PRG_ROM_END => Some((address - PRG_ROM_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank as usize), // switchable
+            _ => None
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
 }
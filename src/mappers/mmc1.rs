@@ -16,6 +16,7 @@ const PRG_ROM_BANK_0_END: u16 = 0xbfff;
 const PRG_ROM_BANK_1_START: u16 = 0xc000;
 const PRG_ROM_BANK_1_END: u16 = 0xffff;
 
+#[derive(Clone)]
 pub(super) struct Mmc1 {
     prg_ram: [u8; PRG_RAM_SIZE],
     prg_rom: Vec<u8>,
@@ -54,18 +55,7 @@ impl Mapper for Mmc1 {
             } else {
                 0
             },
-            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_0_END => match self.prg_rom_bank_mode {
-                0 | 1 => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE_32KB * (self.prg_rom_bank & 0b1110) as usize], // switchable (32 KB bank)
-                2 => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize], // fixed to first bank (16 KB bank)
-                3 => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE_16KB * self.prg_rom_bank as usize], // switchable (16 KB bank)
-                _ => unreachable!()
-            },
-            PRG_ROM_BANK_1_START ..= PRG_ROM_BANK_1_END => match self.prg_rom_bank_mode {
-                0 | 1 => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE_32KB * (self.prg_rom_bank & 0b1110) as usize], // switchable (32 KB bank)
-                2 => self.prg_rom[(address - PRG_ROM_BANK_1_START) as usize + PRG_ROM_BANK_SIZE_16KB * self.prg_rom_bank as usize], // switchable (16 KB bank)
-                3 => self.prg_rom[(address - PRG_ROM_BANK_1_START) as usize + self.prg_rom.len() - PRG_ROM_BANK_SIZE_16KB], // fixed to last bank (16 KB bank)
-                _ => unreachable!()
-            },
+            PRG_ROM_START ..= PRG_ROM_END => self.prg_rom[self.get_prg_rom_offset(address).unwrap()],
             _ => unimplemented!()
         }
     }
@@ -103,4 +93,26 @@ impl Mapper for Mmc1 {
             _ => unimplemented!()
         }
     }
+
+    fn get_prg_rom_offset(&self, address: u16) -> Option<usize> {
+        match address {
+            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_0_END => match self.prg_rom_bank_mode {
+                0 | 1 => Some((address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE_32KB * (self.prg_rom_bank & 0b1110) as usize), // switchable (32 KB bank)
+                2 => Some((address - PRG_ROM_BANK_0_START) as usize), // fixed to first bank (16 KB bank)
+                3 => Some((address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE_16KB * self.prg_rom_bank as usize), // switchable (16 KB bank)
+                _ => unreachable!()
+            },
+            PRG_ROM_BANK_1_START ..= PRG_ROM_BANK_1_END => match self.prg_rom_bank_mode {
+                0 | 1 => Some((address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE_32KB * (self.prg_rom_bank & 0b1110) as usize), // switchable (32 KB bank)
+                2 => Some((address - PRG_ROM_BANK_1_START) as usize + PRG_ROM_BANK_SIZE_16KB * self.prg_rom_bank as usize), // switchable (16 KB bank)
+                3 => Some((address - PRG_ROM_BANK_1_START) as usize + self.prg_rom.len() - PRG_ROM_BANK_SIZE_16KB), // fixed to last bank (16 KB bank)
+                _ => unreachable!()
+            },
+            _ => None
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
 }
@@ -18,6 +18,7 @@ const PRG_ROM_BANK_2_END: u16 = 0xdfff;
 const PRG_ROM_BANK_3_START: u16 = 0xe000;
 const PRG_ROM_BANK_3_END: u16 = 0xffff;
 
+#[derive(Clone)]
 pub(super) struct Mmc3 {
     prg_ram: [u8; PRG_RAM_SIZE],
     prg_rom: Vec<u8>,
@@ -54,18 +55,7 @@ impl Mapper for Mmc3 {
             } else {
                 0
             },
-            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_0_END => match self.prg_rom_bank_mode {
-                0 => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank_0 as usize], // switchable
-                1 => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize + self.prg_rom.len() - 2 * PRG_ROM_BANK_SIZE], // fixed to second-last bank
-                _ => unreachable!()
-            },
-            PRG_ROM_BANK_1_START ..= PRG_ROM_BANK_1_END => self.prg_rom[(address - PRG_ROM_BANK_1_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank_1 as usize], // switchable
-            PRG_ROM_BANK_2_START ..= PRG_ROM_BANK_2_END => match self.prg_rom_bank_mode {
-                0 => self.prg_rom[(address - PRG_ROM_BANK_2_START) as usize + self.prg_rom.len() - 2 * PRG_ROM_BANK_SIZE], // fixed to second-last bank
-                1 => self.prg_rom[(address - PRG_ROM_BANK_2_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank_0 as usize], // switchable
-                _ => unreachable!()
-            },
-            PRG_ROM_BANK_3_START ..= PRG_ROM_BANK_3_END => self.prg_rom[(address - PRG_ROM_BANK_3_START) as usize + self.prg_rom.len() - PRG_ROM_BANK_SIZE], // fixed to last bank
+            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_3_END => self.prg_rom[self.get_prg_rom_offset(address).unwrap()],
             _ => unimplemented!()
         }
     }
@@ -110,4 +100,26 @@ impl Mapper for Mmc3 {
             _ => unimplemented!()
         }
     }
+
+    fn get_prg_rom_offset(&self, address: u16) -> Option<usize> {
+        match address {
+            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_0_END => match self.prg_rom_bank_mode {
+                0 => Some((address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank_0 as usize), // switchable
+                1 => Some((address - PRG_ROM_BANK_0_START) as usize + self.prg_rom.len() - 2 * PRG_ROM_BANK_SIZE), // fixed to second-last bank
+                _ => unreachable!()
+            },
+            PRG_ROM_BANK_1_START ..= PRG_ROM_BANK_1_END => Some((address - PRG_ROM_BANK_1_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank_1 as usize), // switchable
+            PRG_ROM_BANK_2_START ..= PRG_ROM_BANK_2_END => match self.prg_rom_bank_mode {
+                0 => Some((address - PRG_ROM_BANK_2_START) as usize + self.prg_rom.len() - 2 * PRG_ROM_BANK_SIZE), // fixed to second-last bank
+                1 => Some((address - PRG_ROM_BANK_2_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank_0 as usize), // switchable
+                _ => unreachable!()
+            },
+            PRG_ROM_BANK_3_START ..= PRG_ROM_BANK_3_END => Some((address - PRG_ROM_BANK_3_START) as usize + self.prg_rom.len() - PRG_ROM_BANK_SIZE), // fixed to last bank
+            _ => None
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
 }
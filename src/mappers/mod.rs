@@ -20,6 +20,14 @@ pub trait Mapper {
     fn write(&mut self, u16, u8) {
         unimplemented!();
     }
+
+    // offset in the PRG ROM of the byte mapped at a CPU address, which tells the switchable banks apart
+    fn get_prg_rom_offset(&self, _address: u16) -> Option<usize> {
+        None
+    }
+
+    // copy of the banks and registers, for save states
+    fn clone_box(&self) -> Box<dyn Mapper>;
 }
 
 pub fn create_mapper(number: u8, prg_rom: &[u8]) -> Box<dyn Mapper> {
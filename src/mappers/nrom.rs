@@ -7,6 +7,7 @@ const PRG_RAM_END: u16 = 0x7fff;
 const PRG_ROM_START: u16 = 0x8000;
 const PRG_ROM_END: u16 = 0xffff;
 
+#[derive(Clone)]
 pub(super) struct Nrom {
     prg_ram: [u8; PRG_RAM_SIZE],
     prg_rom: Vec<u8>
@@ -25,7 +26,7 @@ impl Mapper for Nrom {
     fn read(&self, address: u16) -> u8 {
         match address {
             PRG_RAM_START ..= PRG_RAM_END => self.prg_ram[(address - PRG_RAM_START) as usize],
-            PRG_ROM_START ..= PRG_ROM_END => self.prg_rom[(address - PRG_ROM_START) as usize % self.prg_rom.len()],
+            PRG_ROM_START ..= PRG_ROM_END => self.prg_rom[self.get_prg_rom_offset(address).unwrap()],
             _ => unimplemented!()
         }
     }
@@ -37,4 +38,15 @@ impl Mapper for Nrom {
             _ => unimplemented!()
         }
     }
+
+    fn get_prg_rom_offset(&self, address: u16) -> Option<usize> {
+        match address {
+            PRG_ROM_START ..= PRG_ROM_END => Some((address - PRG_ROM_START) as usize % self.prg_rom.len()),
+            _ => None
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
 }
@@ -11,6 +11,7 @@ const PRG_ROM_BANK_0_END: u16 = 0xbfff;
 const PRG_ROM_BANK_1_START: u16 = 0xc000;
 const PRG_ROM_BANK_1_END: u16 = 0xffff;
 
+#[derive(Clone)]
 pub(super) struct Uxrom {
     prg_rom: Vec<u8>,
     prg_rom_bank: u8
@@ -29,8 +30,7 @@ impl Mapper for Uxrom {
     fn read(&self, address: u16) -> u8 {
         match address {
             0x6000 ..= 0x7fff => 0,
-            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_0_END => self.prg_rom[(address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank as usize], // switchable
-            PRG_ROM_BANK_1_START ..= PRG_ROM_BANK_1_END => self.prg_rom[(address - PRG_ROM_BANK_1_START) as usize + self.prg_rom.len() - PRG_ROM_BANK_SIZE], // fixed to last bank
+            PRG_ROM_START ..= PRG_ROM_END => self.prg_rom[self.get_prg_rom_offset(address).unwrap()],
             _ => unimplemented!()
         }
     }
@@ -41,4 +41,16 @@ impl Mapper for Uxrom {
             _ => unimplemented!()
         }
     }
+
+    fn get_prg_rom_offset(&self, address: u16) -> Option<usize> {
+        match address {
+            PRG_ROM_BANK_0_START ..= PRG_ROM_BANK_0_END => Some((address - PRG_ROM_BANK_0_START) as usize + PRG_ROM_BANK_SIZE * self.prg_rom_bank as usize), // switchable
+            PRG_ROM_BANK_1_START ..= PRG_ROM_BANK_1_END => Some((address - PRG_ROM_BANK_1_START) as usize + self.prg_rom.len() - PRG_ROM_BANK_SIZE), // fixed to last bank
+            _ => None
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
 }
@@ -0,0 +1,48 @@
+use std::{
+	fs,
+	io::{Error, ErrorKind, Result}
+};
+
+use joypad::*;
+
+// FCEUX text movie (.fm2), only the first gamepad is used
+pub struct Movie {
+	// one byte per frame with the buttons in the order of the joypad register
+	frames: Vec<u8>
+}
+
+impl Movie {
+	pub fn load(filename: &str) -> Result<Self> {
+		let contents = fs::read_to_string(filename)?;
+		let mut frames = Vec::new();
+		for line in contents.lines() {
+			if line.starts_with("binary 1") {
+				return Err(Error::new(ErrorKind::InvalidData, "Binary movies aren't supported"));
+			}
+			// input lines look like "|commands|RLDUTSBA|port 1|port 2|", the other lines are the header
+			if !line.starts_with('|') {
+				continue;
+			}
+			let port0 = line.split('|').nth(2).ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Wrong input line: {}", line)))?;
+			let mut buttons = 0;
+			for (index, button) in port0.chars().take(8).enumerate() {
+				if button != '.' && button != ' ' {
+					buttons |= 0x80 >> index;
+				}
+			}
+			frames.push(buttons);
+		}
+		Ok(Self {
+			frames
+		})
+	}
+
+	pub fn get_frame_count(&self) -> usize {
+		self.frames.len()
+	}
+
+	// the buttons are released after the end of the movie
+	pub fn apply(&self, frame: usize, joypad: &mut Joypad) {
+		joypad.set_buttons(self.frames.get(frame).cloned().unwrap_or(0));
+	}
+}
@@ -0,0 +1,179 @@
+use std::f32::consts::PI;
+
+use palette::*;
+use screen::*;
+
+// the PPU outputs 8 signal samples per pixel, 12 samples make up one color subcarrier period
+const SAMPLES_PER_PIXEL: usize = 8;
+const LINE_SAMPLES: usize = FRAME_WIDTH * SAMPLES_PER_PIXEL;
+
+// samples added on both sides of a line so that the filters don't read out of bounds, a whole number of periods
+const PADDING: usize = 2 * SIGNAL_PHASES;
+const PADDED_LINE_SAMPLES: usize = LINE_SAMPLES + 2 * PADDING;
+
+// width of the narrow luma filter which lets part of the subcarrier through
+const ARTIFACT_WINDOW: usize = 10;
+const SHARPNESS_WINDOW: usize = 8;
+
+// 7 output pixels for every 3 input pixels, like blargg's nes_ntsc
+pub const NTSC_FRAME_WIDTH: usize = ((FRAME_WIDTH - 1) / 3 + 1) * 7;
+
+const PIXEL_SIZE: usize = 4;
+
+#[derive(Copy, Clone)]
+pub struct FilterParameters {
+	pub sharpness: f32, // -1 is blurry, 1 is sharp
+	pub artifacts: f32, // amount of subcarrier leaking into luma, 0 is none, 1 is a real television
+	pub fringing: f32 // amount of luma edges decoded as color, 0 is none, 1 is a real television
+}
+
+impl Default for FilterParameters {
+	fn default() -> Self {
+		Self {
+			sharpness: 0.0,
+			artifacts: 1.0,
+			fringing: 1.0
+		}
+	}
+}
+
+pub struct NtscFilter {
+	ntsc_parameters: NtscParameters,
+	filter_parameters: FilterParameters,
+	signal_levels: Vec<[f32; SIGNAL_PHASES]>,
+	luma_levels: Vec<f32>,
+	cosines: [f32; SIGNAL_PHASES],
+	sines: [f32; SIGNAL_PHASES],
+	burst_phase: usize,
+	signal: Vec<f32>,
+	reference_luma: Vec<f32>,
+	luma: Vec<f32>,
+	i: Vec<f32>,
+	q: Vec<f32>,
+	// scratch buffers of filter_line
+	chroma: Vec<f32>,
+	i_modulated: Vec<f32>,
+	q_modulated: Vec<f32>,
+	signal_sums: Vec<f32>,
+	luma_sums: Vec<f32>,
+	i_sums: Vec<f32>,
+	q_sums: Vec<f32>
+}
+
+impl NtscFilter {
+	pub fn new(ntsc_parameters: &NtscParameters, filter_parameters: &FilterParameters) -> Self {
+		let signal_levels: Vec<[f32; SIGNAL_PHASES]> = (0..PALETTE_SIZE as u16).map(|index| {
+			let mut levels = [0.0; SIGNAL_PHASES];
+			for (phase, level) in levels.iter_mut().enumerate() {
+				*level = get_signal_level(index, phase);
+			}
+			levels
+		}).collect();
+		let luma_levels = signal_levels.iter().map(|levels| levels.iter().sum::<f32>() / SIGNAL_PHASES as f32).collect();
+		let hue = ntsc_parameters.hue * PI / 180.0;
+		let mut cosines = [0.0; SIGNAL_PHASES];
+		let mut sines = [0.0; SIGNAL_PHASES];
+		for phase in 0..SIGNAL_PHASES {
+			let angle = PI * phase as f32 / 6.0 + hue;
+			cosines[phase] = angle.cos();
+			sines[phase] = angle.sin();
+		}
+		Self {
+			ntsc_parameters: *ntsc_parameters,
+			filter_parameters: *filter_parameters,
+			signal_levels,
+			luma_levels,
+			cosines,
+			sines,
+			burst_phase: 0,
+			signal: vec![0.0; PADDED_LINE_SAMPLES],
+			reference_luma: vec![0.0; PADDED_LINE_SAMPLES],
+			luma: vec![0.0; PADDED_LINE_SAMPLES],
+			i: vec![0.0; PADDED_LINE_SAMPLES],
+			q: vec![0.0; PADDED_LINE_SAMPLES],
+			chroma: vec![0.0; PADDED_LINE_SAMPLES],
+			i_modulated: vec![0.0; PADDED_LINE_SAMPLES],
+			q_modulated: vec![0.0; PADDED_LINE_SAMPLES],
+			signal_sums: vec![0.0; PADDED_LINE_SAMPLES + 1],
+			luma_sums: vec![0.0; PADDED_LINE_SAMPLES + 1],
+			i_sums: vec![0.0; PADDED_LINE_SAMPLES + 1],
+			q_sums: vec![0.0; PADDED_LINE_SAMPLES + 1]
+		}
+	}
+
+	// turns a frame of 9-bit palette indices into a NTSC_FRAME_WIDTH wide RGBA frame
+	pub fn apply(&mut self, pixel_indices: &[u16], frame_buffer: &mut [u8]) {
+		for (row, (indices, output)) in pixel_indices.chunks(FRAME_WIDTH).zip(frame_buffer.chunks_mut(NTSC_FRAME_WIDTH * PIXEL_SIZE)).enumerate() {
+			// each scanline starts a third of a subcarrier period later than the previous one
+			let line_phase = 4 * ((self.burst_phase + row) % 3);
+			self.filter_line(indices, line_phase);
+			for (column, pixel) in output.chunks_mut(PIXEL_SIZE).enumerate() {
+				let sample = PADDING + (2 * column + 1) * LINE_SAMPLES / (2 * NTSC_FRAME_WIDTH);
+				let y = self.luma[sample] * self.ntsc_parameters.contrast + self.ntsc_parameters.brightness;
+				let i = self.i[sample] * self.ntsc_parameters.saturation;
+				let q = self.q[sample] * self.ntsc_parameters.saturation;
+				let [red, green, blue] = yiq_to_rgb(y, i, q, self.ntsc_parameters.gamma);
+				pixel.copy_from_slice(&[red, green, blue, 0xff]);
+			}
+		}
+		// the phase of the first scanline changes every frame, making the artifacts crawl
+		self.burst_phase = (self.burst_phase + 1) % 3;
+	}
+
+	fn filter_line(&mut self, indices: &[u16], line_phase: usize) {
+		// generate the composite signal, the padding repeats the border pixels
+		for sample in 0..PADDED_LINE_SAMPLES {
+			let pixel = (sample.max(PADDING) - PADDING).min(LINE_SAMPLES - 1) / SAMPLES_PER_PIXEL;
+			let index = indices[pixel] as usize;
+			self.signal[sample] = self.signal_levels[index][(line_phase + sample) % SIGNAL_PHASES];
+			self.reference_luma[sample] = self.luma_levels[index];
+		}
+
+		// a low pass filter over one period removes the subcarrier, a narrower one lets some of it through
+		get_prefix_sums(&self.signal, &mut self.signal_sums);
+		let fringing = self.filter_parameters.fringing;
+		let artifacts = self.filter_parameters.artifacts;
+		// the chroma of the padding stays 0
+		for (sample, chroma) in self.chroma.iter_mut().enumerate().take(PADDED_LINE_SAMPLES - SIGNAL_PHASES).skip(SIGNAL_PHASES) {
+			let luma = get_average(&self.signal_sums, sample, SIGNAL_PHASES);
+			let narrow_luma = get_average(&self.signal_sums, sample, ARTIFACT_WINDOW);
+			self.luma[sample] = luma + artifacts * (narrow_luma - luma);
+			// luma edges that aren't fully removed show up as color fringes
+			let reference_luma = self.reference_luma[sample] + fringing * (luma - self.reference_luma[sample]);
+			*chroma = self.signal[sample] - reference_luma;
+		}
+
+		// demodulate the color
+		let phase_of = |sample: usize| (line_phase + sample) % SIGNAL_PHASES;
+		for (sample, chroma) in self.chroma.iter().enumerate() {
+			self.i_modulated[sample] = chroma * self.cosines[phase_of(sample)];
+			self.q_modulated[sample] = chroma * self.sines[phase_of(sample)];
+		}
+		get_prefix_sums(&self.i_modulated, &mut self.i_sums);
+		get_prefix_sums(&self.q_modulated, &mut self.q_sums);
+		get_prefix_sums(&self.luma, &mut self.luma_sums);
+		let sharpness = self.filter_parameters.sharpness;
+		for sample in PADDING..PADDING + LINE_SAMPLES {
+			self.i[sample] = get_average(&self.i_sums, sample, SIGNAL_PHASES);
+			self.q[sample] = get_average(&self.q_sums, sample, SIGNAL_PHASES);
+			let blurred_luma = get_average(&self.luma_sums, sample, SHARPNESS_WINDOW);
+			self.luma[sample] += sharpness * (self.luma[sample] - blurred_luma);
+		}
+	}
+}
+
+// sums has one more element than values, starting with 0
+fn get_prefix_sums(values: &[f32], sums: &mut [f32]) {
+	let mut sum = 0.0;
+	sums[0] = sum;
+	for (value, next_sum) in values.iter().zip(sums[1..].iter_mut()) {
+		sum += value;
+		*next_sum = sum;
+	}
+}
+
+// box filter of the given width centered on a sample
+fn get_average(sums: &[f32], sample: usize, width: usize) -> f32 {
+	let start = sample - width / 2;
+	(sums[start + width] - sums[start]) / width as f32
+}
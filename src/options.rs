@@ -0,0 +1,167 @@
+use mu::{
+	cpu::{TraceFormat, TraceOptions},
+	region::*,
+	palette::*,
+	ntsc::*
+};
+
+use renderer::*;
+
+pub struct Options {
+	pub filename: String,
+	pub region: Option<Region>,
+	pub palette: Option<String>,
+	pub ntsc_parameters: NtscParameters,
+	pub ntsc_filter: bool,
+	pub filter_parameters: FilterParameters,
+	pub shader_preset: Option<String>,
+	pub display_settings: DisplaySettings,
+	pub fullscreen: bool,
+	pub software_rendering: bool,
+	pub screenshot_directory: String,
+	pub scaled_screenshots: bool,
+	pub recording: Option<String>,
+	pub recording_directory: String,
+	pub trace: Option<String>,
+	pub trace_options: TraceOptions,
+	// loaded with the ones named after the ROM
+	pub symbol_files: Vec<String>,
+	pub code_data_log: Option<String>,
+	pub profile: Option<String>,
+	#[cfg(feature = "debugger")]
+	pub gdb_port: Option<u16>,
+	#[cfg(feature = "scripting")]
+	pub script: Option<String>
+}
+
+impl Options {
+	pub fn parse() -> Self {
+		let mut filename = None;
+		let mut region = None;
+		let mut palette = None;
+		let mut ntsc_parameters = NtscParameters::default();
+		let mut ntsc_filter = false;
+		let mut filter_parameters = FilterParameters::default();
+		let mut shader_preset = None;
+		let mut display_settings = DisplaySettings::default();
+		let mut fullscreen = false;
+		let mut software_rendering = false;
+		let mut screenshot_directory = "screenshots".to_string();
+		let mut scaled_screenshots = false;
+		let mut recording = None;
+		let mut recording_directory = "recordings".to_string();
+		let mut trace = None;
+		let mut trace_options = TraceOptions::default();
+		let mut symbol_files = Vec::new();
+		let mut code_data_log = None;
+		let mut profile = None;
+		#[cfg(feature = "debugger")]
+		let mut gdb_port = None;
+		#[cfg(feature = "scripting")]
+		let mut script = None;
+		let mut arguments = std::env::args().skip(1);
+		while let Some(argument) = arguments.next() {
+			match argument.as_str() {
+				"--region" => region = Some(get_value(&mut arguments, &argument).parse().unwrap()),
+				"--palette" => palette = Some(get_value(&mut arguments, &argument)),
+				"--hue" => ntsc_parameters.hue = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--saturation" => ntsc_parameters.saturation = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--contrast" => ntsc_parameters.contrast = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--brightness" => ntsc_parameters.brightness = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--gamma" => ntsc_parameters.gamma = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--ntsc" => ntsc_filter = true,
+				"--sharpness" => filter_parameters.sharpness = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--artifacts" => filter_parameters.artifacts = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--fringing" => filter_parameters.fringing = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--shader" => shader_preset = Some(get_value(&mut arguments, &argument)),
+				"--integer-scaling" => display_settings.integer_scaling = true,
+				"--aspect-ratio" => display_settings.aspect_ratio = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--overscan" => display_settings.overscan = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--fullscreen" => fullscreen = true,
+				"--software-renderer" => software_rendering = true,
+				"--screenshot-directory" => screenshot_directory = get_value(&mut arguments, &argument),
+				"--scaled-screenshots" => scaled_screenshots = true,
+				"--record" => recording = Some(get_value(&mut arguments, &argument)),
+				"--recording-directory" => recording_directory = get_value(&mut arguments, &argument),
+				"--trace" => trace = Some(get_value(&mut arguments, &argument)),
+				"--trace-format" => trace_options.format = get_value(&mut arguments, &argument).parse::<TraceFormat>().unwrap(),
+				"--trace-ring" => trace_options.ring_buffer_size = Some(get_value(&mut arguments, &argument).parse().unwrap()),
+				"--trace-ppu" => trace_options.ppu_position = true,
+				"--trace-cycles" => trace_options.cpu_cycles = true,
+				"--symbols" => symbol_files.push(get_value(&mut arguments, &argument)),
+				"--cdl" => code_data_log = Some(get_value(&mut arguments, &argument)),
+				"--profile" => profile = Some(get_value(&mut arguments, &argument)),
+				#[cfg(feature = "debugger")]
+				"--gdb" => gdb_port = Some(get_value(&mut arguments, &argument).parse().unwrap()),
+				#[cfg(feature = "scripting")]
+				"--script" => script = Some(get_value(&mut arguments, &argument)),
+				_ => filename = Some(argument)
+			}
+		}
+		Self {
+			filename: filename.expect("No ROM file given"),
+			region,
+			palette,
+			ntsc_parameters,
+			ntsc_filter,
+			filter_parameters,
+			shader_preset,
+			display_settings,
+			fullscreen,
+			software_rendering,
+			screenshot_directory,
+			scaled_screenshots,
+			recording,
+			recording_directory,
+			trace,
+			trace_options,
+			symbol_files,
+			code_data_log,
+			profile,
+			#[cfg(feature = "debugger")]
+			gdb_port,
+			#[cfg(feature = "scripting")]
+			script
+		}
+	}
+}
+
+// mu disasm <rom> [--bank <index>] [--origin <address>] [--symbols <file>]...
+pub struct DisassemblerOptions {
+	pub filename: String,
+	pub bank: usize,
+	pub origin: Option<u16>,
+	pub symbol_files: Vec<String>
+}
+
+impl DisassemblerOptions {
+	pub fn parse() -> Self {
+		let mut filename = None;
+		let mut bank = 0;
+		let mut origin = None;
+		let mut symbol_files = Vec::new();
+		let mut arguments = std::env::args().skip(2);
+		while let Some(argument) = arguments.next() {
+			match argument.as_str() {
+				"--bank" => bank = get_value(&mut arguments, &argument).parse().unwrap(),
+				"--origin" => {
+					let value = get_value(&mut arguments, &argument);
+					let digits = value.trim_start_matches('$').trim_start_matches("0x");
+					origin = Some(u16::from_str_radix(digits, 16).unwrap_or_else(|_| panic!("Not a hexadecimal address: {}", value)));
+				},
+				"--symbols" => symbol_files.push(get_value(&mut arguments, &argument)),
+				_ => filename = Some(argument)
+			}
+		}
+		Self {
+			filename: filename.expect("No ROM file given"),
+			bank,
+			origin,
+			symbol_files
+		}
+	}
+}
+
+fn get_value(arguments: &mut dyn Iterator<Item = String>, option: &str) -> String {
+	arguments.next().unwrap_or_else(|| panic!("Missing value for {}", option))
+}
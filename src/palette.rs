@@ -0,0 +1,183 @@
+use std::{
+	f32::consts::PI,
+	fs,
+	io::{Error, ErrorKind, Result}
+};
+
+pub const COLOR_COUNT: usize = 0x40;
+pub const EMPHASIS_COUNT: usize = 8;
+pub const PALETTE_SIZE: usize = COLOR_COUNT * EMPHASIS_COUNT;
+
+const CHANNEL_COUNT: usize = 3;
+
+// how much a color channel is dimmed by each emphasis bit that doesn't select it
+const EMPHASIS_ATTENUATION: f32 = 0.816;
+
+const COLORS: [[u8; CHANNEL_COUNT]; COLOR_COUNT] = [[0x54, 0x54, 0x54], [0x00, 0x1e, 0x74], [0x08, 0x10, 0x90], [0x30, 0x00, 0x88],
+                                                     [0x44, 0x00, 0x64], [0x5c, 0x00, 0x30], [0x54, 0x04, 0x00], [0x3c, 0x18, 0x00],
+                                                     [0x20, 0x2a, 0x00], [0x08, 0x3a, 0x00], [0x00, 0x40, 0x00], [0x00, 0x3c, 0x00],
+                                                     [0x00, 0x32, 0x3c], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+                                                     [0x98, 0x96, 0x98], [0x08, 0x4c, 0xc4], [0x30, 0x32, 0xec], [0x5c, 0x1e, 0xe4],
+                                                     [0x88, 0x14, 0xb0], [0xa0, 0x14, 0x64], [0x98, 0x22, 0x20], [0x78, 0x3c, 0x00],
+                                                     [0x54, 0x5a, 0x00], [0x28, 0x72, 0x00], [0x08, 0x7c, 0x00], [0x00, 0x76, 0x28],
+                                                     [0x00, 0x66, 0x78], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+                                                     [0xec, 0xee, 0xec], [0x4c, 0x9a, 0xec], [0x78, 0x7c, 0xec], [0xb0, 0x62, 0xec],
+                                                     [0xe4, 0x54, 0xec], [0xec, 0x58, 0xb4], [0xec, 0x6a, 0x64], [0xd4, 0x88, 0x20],
+                                                     [0xa0, 0xaa, 0x00], [0x74, 0xc4, 0x00], [0x4c, 0xd0, 0x20], [0x38, 0xcc, 0x6c],
+                                                     [0x38, 0xb4, 0xcc], [0x3c, 0x3c, 0x3c], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+                                                     [0xec, 0xee, 0xec], [0xa8, 0xcc, 0xec], [0xbc, 0xbc, 0xec], [0xd4, 0xb2, 0xec],
+                                                     [0xec, 0xae, 0xec], [0xec, 0xae, 0xd4], [0xec, 0xb4, 0xb0], [0xe4, 0xc4, 0x90],
+                                                     [0xcc, 0xd2, 0x78], [0xb4, 0xde, 0x78], [0xa8, 0xe2, 0x90], [0x98, 0xe2, 0xb4],
+                                                     [0xa0, 0xd6, 0xe4], [0xa0, 0xa2, 0xa0], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00]];
+
+// composite signal voltages relative to sync, for the low and high half of the color wave of each level
+const SIGNAL_LEVELS: [[f32; 4]; 2] = [[0.350, 0.518, 0.962, 1.550], [1.094, 1.506, 1.962, 1.962]];
+const SIGNAL_BLACK: f32 = 0.518;
+const SIGNAL_WHITE: f32 = 1.962;
+const SIGNAL_ATTENUATION: f32 = 0.746;
+
+// samples per color subcarrier period
+pub const SIGNAL_PHASES: usize = 12;
+
+#[derive(Copy, Clone)]
+pub struct NtscParameters {
+	pub hue: f32, // in degrees
+	pub saturation: f32,
+	pub contrast: f32,
+	pub brightness: f32,
+	pub gamma: f32
+}
+
+impl Default for NtscParameters {
+	fn default() -> Self {
+		Self {
+			hue: 0.0,
+			saturation: 1.0,
+			contrast: 1.0,
+			brightness: 0.0,
+			gamma: 2.2
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct Palette {
+	colors: Vec<[u8; CHANNEL_COUNT]>
+}
+
+impl Default for Palette {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Palette {
+	pub fn new() -> Self {
+		Self::from_colors(&COLORS)
+	}
+
+	// loads 64 colors (192 bytes) or 64 colors for each of the 8 emphasis combinations (1536 bytes)
+	pub fn load(filename: &str) -> Result<Self> {
+		let contents = fs::read(filename)?;
+		let colors: Vec<[u8; CHANNEL_COUNT]> = contents.chunks_exact(CHANNEL_COUNT).map(|color| [color[0], color[1], color[2]]).collect();
+		match contents.len() {
+			0xc0 => Ok(Self::from_colors(&colors)),
+			0x600 => Ok(Self {
+				colors
+			}),
+			size => Err(Error::new(ErrorKind::InvalidData, format!("Wrong palette size: {} bytes", size)))
+		}
+	}
+
+	// decodes the composite signal of each color like an ideal NTSC television would
+	pub fn generate(parameters: &NtscParameters) -> Self {
+		let hue = parameters.hue * PI / 180.0;
+		let mut colors = Vec::with_capacity(PALETTE_SIZE);
+		for index in 0..PALETTE_SIZE as u16 {
+			let mut y = 0.0;
+			let mut i = 0.0;
+			let mut q = 0.0;
+			for phase in 0..SIGNAL_PHASES {
+				let level = get_signal_level(index, phase) / SIGNAL_PHASES as f32;
+				let angle = PI * phase as f32 / 6.0 + hue;
+				y += level;
+				i += level * angle.cos();
+				q += level * angle.sin();
+			}
+			y = y * parameters.contrast + parameters.brightness;
+			i *= parameters.saturation;
+			q *= parameters.saturation;
+			colors.push(yiq_to_rgb(y, i, q, parameters.gamma));
+		}
+		Self {
+			colors
+		}
+	}
+
+	fn from_colors(colors: &[[u8; CHANNEL_COUNT]]) -> Self {
+		let mut emphasized_colors = Vec::with_capacity(PALETTE_SIZE);
+		for emphasis in 0..EMPHASIS_COUNT {
+			for color in colors {
+				let mut color = *color;
+				// bit 0 emphasizes red, bit 1 green and bit 2 blue
+				for (channel, value) in color.iter_mut().enumerate() {
+					for bit in 0..3 {
+						if bit != channel && (emphasis >> bit) & 1 != 0 {
+							*value = (*value as f32 * EMPHASIS_ATTENUATION) as u8;
+						}
+					}
+				}
+				emphasized_colors.push(color);
+			}
+		}
+		Self {
+			colors: emphasized_colors
+		}
+	}
+
+	pub fn get_rgba(&self, index: u16) -> [u8; 4] {
+		let [red, green, blue] = self.colors[index as usize];
+		[red, green, blue, 0xff]
+	}
+}
+
+// normalized composite level (0 for black, 1 for white) of a 9-bit palette index at a given subcarrier phase
+pub fn get_signal_level(index: u16, phase: usize) -> f32 {
+	let color = (index & 0x0f) as usize;
+	let level = if color > 13 {
+		1 // colors $xE and $xF are forced to level 1
+	} else {
+		((index >> 4) & 0b11) as usize
+	};
+	let emphasis = index >> 6;
+	// the offset lines the color wave up with the burst so that hue 0 looks right
+	let in_color_phase = |color: usize| (color + phase + 8) % SIGNAL_PHASES < 6;
+
+	let low = SIGNAL_LEVELS[0][level];
+	let high = SIGNAL_LEVELS[1][level];
+	let mut signal = match color {
+		0 => high,
+		13 ..= 15 => low,
+		_ => if in_color_phase(color) {
+			high
+		} else {
+			low
+		}
+	};
+	if ((emphasis & 1) != 0 && in_color_phase(0)) || ((emphasis & 2) != 0 && in_color_phase(4)) || ((emphasis & 4) != 0 && in_color_phase(8)) {
+		signal *= SIGNAL_ATTENUATION;
+	}
+	(signal - SIGNAL_BLACK) / (SIGNAL_WHITE - SIGNAL_BLACK)
+}
+
+pub fn yiq_to_rgb(y: f32, i: f32, q: f32, gamma: f32) -> [u8; CHANNEL_COUNT] {
+	let correct = |value: f32| {
+		let value = value.clamp(0.0, 1.0).powf(2.2 / gamma);
+		(value * 255.0).round() as u8
+	};
+	[
+		correct(y + 0.956 * i + 0.621 * q),
+		correct(y - 0.272 * i - 0.647 * q),
+		correct(y - 1.106 * i + 1.703 * q)
+	]
+}
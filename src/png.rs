@@ -0,0 +1,85 @@
+use std::{
+	fs,
+	io::Result
+};
+
+use checksum::*;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const BIT_DEPTH: u8 = 8;
+const COLOR_TYPE_RGB: u8 = 2;
+const CHANNEL_COUNT: usize = 3;
+const PIXEL_SIZE: usize = 4;
+
+// largest block of a stored (uncompressed) deflate stream
+const MAX_STORED_BLOCK_SIZE: usize = 0xffff;
+
+// encodes a RGBA frame buffer as a RGB PNG, uncompressed to keep the encoder small
+pub fn encode_png(width: usize, height: usize, frame_buffer: &[u8]) -> Vec<u8> {
+	// each row starts with its filter type, 0 for none
+	let mut image_data = Vec::with_capacity(height * (1 + width * CHANNEL_COUNT));
+	for row in frame_buffer.chunks(width * PIXEL_SIZE).take(height) {
+		image_data.push(0);
+		for pixel in row.chunks(PIXEL_SIZE) {
+			image_data.extend_from_slice(&pixel[..CHANNEL_COUNT]);
+		}
+	}
+
+	let mut header = Vec::new();
+	header.extend_from_slice(&(width as u32).to_be_bytes());
+	header.extend_from_slice(&(height as u32).to_be_bytes());
+	header.extend_from_slice(&[BIT_DEPTH, COLOR_TYPE_RGB, 0, 0, 0]); // no compression, filter and interlace options
+
+	let mut png = SIGNATURE.to_vec();
+	write_chunk(&mut png, b"IHDR", &header);
+	write_chunk(&mut png, b"IDAT", &deflate_stored(&image_data));
+	write_chunk(&mut png, b"IEND", &[]);
+	png
+}
+
+pub fn save_png(filename: &str, width: usize, height: usize, frame_buffer: &[u8]) -> Result<()> {
+	fs::write(filename, encode_png(width, height, frame_buffer))
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+	png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+	let start = png.len();
+	png.extend_from_slice(chunk_type);
+	png.extend_from_slice(data);
+	// the CRC covers the type and the data but not the length
+	let crc = crc32(&png[start..]);
+	png.extend_from_slice(&crc.to_be_bytes());
+}
+
+// zlib stream made of stored deflate blocks
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+	let mut stream = vec![0x78, 0x01]; // deflate with a 32KB window, no preset dictionary
+	// an empty stream still needs one final block
+	let blocks: Vec<&[u8]> = if data.is_empty() {
+		vec![data]
+	} else {
+		data.chunks(MAX_STORED_BLOCK_SIZE).collect()
+	};
+	for (number, block) in blocks.iter().enumerate() {
+		let is_final = number == blocks.len() - 1;
+		stream.push(is_final as u8); // block type 0 (stored) in the next 2 bits
+		let length = block.len() as u16;
+		stream.extend_from_slice(&length.to_le_bytes());
+		stream.extend_from_slice(&(!length).to_le_bytes());
+		stream.extend_from_slice(block);
+	}
+	stream.extend_from_slice(&adler32(data).to_be_bytes());
+	stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+	const MODULO: u32 = 65521;
+	let mut a = 1;
+	let mut b = 0;
+	for byte in data {
+		a = (a + *byte as u32) % MODULO;
+		b = (b + a) % MODULO;
+	}
+	(b << 16) | a
+}
@@ -33,6 +33,7 @@ const SPRITE_PALETTE_2_END: u16 = 0x3f1b;
 const SPRITE_PALETTE_3_START: u16 = 0x3f1d;
 const SPRITE_PALETTE_3_END: u16 = 0x3f1f;
 
+#[derive(Clone)]
 pub(super) struct Memory {
 	table: [u8; TABLE_SIZE as _],
 	background_palette: [u8; BACKGROUND_PALETTE_SIZE as _],
@@ -4,21 +4,24 @@ mod memory;
 #[cfg(test)]
 mod tests;
 
-#[cfg(feature = "nametable-viewer")]
-mod nametable_viewer;
-
 use screen::*;
 use cpu::*;
+use region::*;
+use cdl::{self, CodeDataLog};
 use self::memory::*;
 
 #[cfg(feature = "benchmark")]
 use emulator::*;
 
-#[cfg(feature = "nametable-viewer")]
-use self::nametable_viewer::*;
+#[cfg(feature = "debugger")]
+use accesses::AccessLog;
+
+pub const OAM_SIZE: usize = 256;
 
-const OAM_SIZE: usize = 256;
+// PPU cycles between a rising edge of the NMI line and the CPU noticing it
+const NMI_DELAY: u8 = 2;
 
+#[derive(Clone)]
 pub struct Ppu {
 	ppuctrl: u8,
 	ppumask: u8,
@@ -31,11 +34,17 @@ pub struct Ppu {
 	flipflop: bool,
 	cycle_counter: u16,
 	scanline_counter: u16,
+	// VBlanks since the power on
+	frame_counter: u64,
 	oam: [u8; OAM_SIZE],
 	memory: Memory,
-	
-	#[cfg(feature = "nametable-viewer")]
-	nametable_viewer: NametableViewer
+	nmi_line: bool,
+	nmi_delay: u8,
+	vblank_suppressed: bool,
+	region: Region,
+	// accesses to the PPU bus through PPUDATA, for the watchpoints of the debugger
+	#[cfg(feature = "debugger")]
+	pub memory_accesses: AccessLog
 }
 
 impl Ppu {
@@ -52,11 +61,15 @@ impl Ppu {
 			flipflop: false,
 			cycle_counter: 0,
 			scanline_counter: 0,
+			frame_counter: 0,
 			oam: [0; OAM_SIZE],
 			memory: Memory::new(),
-
-			#[cfg(feature = "nametable-viewer")]
-			nametable_viewer: NametableViewer::new()
+			nmi_line: false,
+			nmi_delay: 0,
+			vblank_suppressed: false,
+			region: Region::Ntsc,
+			#[cfg(feature = "debugger")]
+			memory_accesses: AccessLog::default()
 		}
 	}
 
@@ -64,11 +77,93 @@ impl Ppu {
 		self.memory.load_chr_rom(chr_rom);
 	}
 
-	pub fn do_cycle(&mut self, cpu: &mut Cpu, screen: &mut Screen) {
+	pub fn set_region(&mut self, region: Region) {
+		self.region = region;
+	}
+
+	// the reset button clears PPUCTRL, PPUMASK, the scroll and the write toggle, the memories and the position being kept
+	pub fn reset(&mut self) {
+		self.ppuctrl = 0;
+		self.ppumask = 0;
+		self.scroll_x = 0;
+		self.scroll_y = 0;
+		self.ppudata_buffer = 0;
+		self.flipflop = false;
+		self.nmi_delay = 0;
+		self.update_nmi_line();
+	}
+
+	// side effect free reads of the PPU address space, for the debug viewers
+	pub fn read_memory_debug(&self, address: u16) -> u8 {
+		self.memory.read(address)
+	}
+
+	pub fn get_oam(&self) -> &[u8; OAM_SIZE] {
+		&self.oam
+	}
+
+	pub fn get_ppuctrl(&self) -> u8 {
+		self.ppuctrl
+	}
+
+	pub fn get_ppuaddr(&self) -> u16 {
+		self.ppuaddr
+	}
+
+	// top left corner of the screen in the 512x480 map of the 4 nametables
+	pub fn get_scroll_position(&self) -> (u16, u16) {
+		let x = self.scroll_x as u16 + 256 * (self.ppuctrl & 1) as u16;
+		let y = self.scroll_y as u16 + 240 * ((self.ppuctrl >> 1) & 1) as u16;
+		(x, y)
+	}
+
+	// scanline and dot the PPU is at
+	pub fn get_position(&self) -> (u16, u16) {
+		(self.scanline_counter, self.cycle_counter)
+	}
+
+	pub fn get_frame_counter(&self) -> u64 {
+		self.frame_counter
+	}
+
+	// from the VBlank scanline to the pre-render one, when the game can update the nametables
+	pub fn is_in_vblank(&self) -> bool {
+		self.scanline_counter >= self.region.get_vblank_scanline() && self.scanline_counter < self.region.get_scanline_count() - 1
+	}
+
+	// the NMI line is the AND of the VBlank flag and the NMI enable bit, the CPU reacts to its rising edge
+	fn update_nmi_line(&mut self) {
+		let nmi_line = (self.ppuctrl & self.ppustatus & 0x80) != 0;
+		if nmi_line && !self.nmi_line {
+			self.nmi_delay = NMI_DELAY;
+		}
+		self.nmi_line = nmi_line;
+	}
+
+	// combines a palette entry with the greyscale and color emphasis bits of PPUMASK
+	fn get_pixel_index(&self, color: u8) -> u16 {
+		let color = if (self.ppumask & 0x01) != 0 {
+			color & 0x30
+		} else {
+			color & 0x3f
+		};
+		(((self.ppumask & 0xe0) as u16) << 1) | color as u16
+	}
+
+	// the pattern table bytes drawn are logged as rendered CHR ROM
+	pub fn do_cycle(&mut self, cpu: &mut Cpu, screen: &mut Screen, mut code_data_log: Option<&mut CodeDataLog>) {
+		if self.nmi_delay > 0 {
+			self.nmi_delay -= 1;
+			// the NMI is lost if the line went low again in the meantime
+			if self.nmi_delay == 0 && self.nmi_line {
+				cpu.request_interrupt(Interrupt::Nmi);
+			}
+		}
+
 		self.cycle_counter += 1;
 		if self.cycle_counter == 341 {
 			self.cycle_counter = 0;
-			self.scanline_counter = (self.scanline_counter + 1) % 262;
+			self.scanline_counter = (self.scanline_counter + 1) % self.region.get_scanline_count();
 			match self.scanline_counter {
 				// visible scanlines
 				0 ..= 239 => {
@@ -91,11 +186,20 @@ impl Ppu {
 							let palette_number = ((attribute >> (4 * ((tile_row / 2) % 2))) >> (2 * ((tile_column / 2) % 2))) & 0b11;
 							let tile_number_address = nametable_address + tile_row * 32 + tile_column;
 							let tile_number = self.memory.read(tile_number_address);
-							let low_byte = self.memory.read(pattern_address + (tile_number as u16) * 16 + pixel_row);
-							let high_byte = self.memory.read(pattern_address + (tile_number as u16) * 16 + pixel_row + 8);
+							let tile_address = pattern_address + (tile_number as u16) * 16 + pixel_row;
+							let low_byte = self.memory.read(tile_address);
+							let high_byte = self.memory.read(tile_address + 8);
+							if let Some(ref mut code_data_log) = code_data_log {
+								code_data_log.log_chr_rom(tile_address, cdl::RENDERED);
+								code_data_log.log_chr_rom(tile_address + 8, cdl::RENDERED);
+							}
 							let low_bit = (low_byte >> (7 - pixel_column)) & 1;
 							let high_bit = (high_byte >> (7 - pixel_column)) & 1;
-							let color_number = (high_bit << 1) | low_bit;
+							let color_number = if column < 8 && (self.ppumask & 0x02) == 0 {
+								0 // background hidden in leftmost 8 pixels
+							} else {
+								(high_bit << 1) | low_bit
+							};
 							let color_address = if color_number == 0 { // is pixel opaque?
 								0 // backdrop color
 							} else {
@@ -103,15 +207,21 @@ impl Ppu {
 								4 * palette_number as u16 + color_number as u16
 							} + 0x3f00;
 							let color = self.memory.read(color_address);
-							screen.set_pixel(self.scanline_counter as _, column as _, color as _);
+							screen.set_pixel(self.scanline_counter as _, column as _, self.get_pixel_index(color));
+						}
+					} else {
+						let color = self.memory.read(0x3f00); // backdrop color
+						for column in 0..FRAME_WIDTH {
+							screen.set_pixel(self.scanline_counter as _, column, self.get_pixel_index(color));
 						}
 					}
 					// render sprites
 					if (self.ppumask & 0x10) != 0 {
-						let sprite_size = (self.ppuctrl >> 6) & 1;
-						if sprite_size == 1 {
-							unimplemented!("8x16 sprites");
-						}
+						let sprite_height = if (self.ppuctrl & 0x20) == 0 {
+							8
+						} else {
+							16
+						};
 						for number in (0..64).rev() {
 							let sprite_y = self.oam[number * 4];
 							let tile_number = self.oam[number * 4 + 1];
@@ -120,21 +230,33 @@ impl Ppu {
 							let horizontal_flip = (attributes & 0x40) != 0;
 							let vertical_flip = (attributes & 0x80) != 0;
 							let sprite_x = self.oam[number * 4 + 3];
-							let pattern_address = 0x1000 * ((self.ppuctrl >> 3) & 1) as u16;
-							for pixel_row in 0..8 {
+							let (pattern_address, tile_number) = if sprite_height == 8 {
+								(0x1000 * ((self.ppuctrl >> 3) & 1) as u16, tile_number as u16)
+							} else {
+								// 8x16 sprites select their pattern table with bit 0 of the tile number
+								(0x1000 * (tile_number & 1) as u16, (tile_number & 0xfe) as u16)
+							};
+							for pixel_row in 0..sprite_height {
+								// sprites are drawn one scanline below their OAM Y coordinate
 								let row = (if vertical_flip {
-									7 - pixel_row
+									sprite_height - 1 - pixel_row
 								} else {
 									pixel_row
-								}) + sprite_y as u16;
+								}) + sprite_y as u16 + 1;
 								
 								// ugly:
 								if row != self.scanline_counter {
 									continue;
 								}
 								
-								let low_byte = self.memory.read(pattern_address + (tile_number as u16) * 16 + pixel_row);
-								let high_byte = self.memory.read(pattern_address + (tile_number as u16) * 16 + pixel_row + 8);
+								// the bottom half of a 8x16 sprite is the next tile
+								let tile_address = pattern_address + (tile_number + pixel_row / 8) * 16 + pixel_row % 8;
+								let low_byte = self.memory.read(tile_address);
+								let high_byte = self.memory.read(tile_address + 8);
+								if let Some(ref mut code_data_log) = code_data_log {
+									code_data_log.log_chr_rom(tile_address, cdl::RENDERED);
+									code_data_log.log_chr_rom(tile_address + 8, cdl::RENDERED);
+								}
 								for pixel_column in 0..8 {
 									let column = (if horizontal_flip {
 										7 - pixel_column
@@ -142,44 +264,45 @@ impl Ppu {
 										pixel_column
 									}) + sprite_x as u16;
 									if column >= FRAME_WIDTH as u16 {
-										// sprite in leftmost 8 pixels
-										if (sprite_x < 8) && ((self.ppuctrl & 0x04) == 0) {
-											break; // hide
-										} else {
-											continue; // show
-										}
+										continue; // off screen
+									}
+									if column < 8 && (self.ppumask & 0x04) == 0 {
+										continue; // sprites hidden in leftmost 8 pixels
 									}
 									let low_bit = (low_byte >> (7 - pixel_column)) & 1;
 									let high_bit = (high_byte >> (7 - pixel_column)) & 1;
 									let color_number = (high_bit << 1) | low_bit;
 									if color_number != 0 { // opaque
-										if number == 0 && background_opacity[column as usize] {
+										if number == 0 && background_opacity[column as usize] && column != 255 {
 											self.ppustatus |= 0x40; // sprite 0 hit
 										}
 										let color = self.memory.read(0x3f00 + 4 * palette_number as u16 + color_number as u16);
-										screen.set_pixel(row as _, column as _, color as _);
+										screen.set_pixel(row as _, column as _, self.get_pixel_index(color));
 									}
 								}
 							}
 						}
 					}
 				},
+				_ => {}
+			}
+		}
+		if self.cycle_counter == 1 {
+			if self.scanline_counter == self.region.get_vblank_scanline() {
 				// VBlank start
-				241 => {
+				if !self.vblank_suppressed {
 					self.ppustatus |= 0x80;
-					if (self.ppuctrl & 0x80) != 0 {
-						cpu.request_interrupt(Interrupt::Nmi);
-					}
-
-					#[cfg(not(test))]
-					screen.request_draw();
+					self.update_nmi_line();
+				}
+				self.vblank_suppressed = false;
+				self.frame_counter += 1;
 
-					#[cfg(feature = "nametable-viewer")]
-					NametableViewer::update(self);
-				},
+				#[cfg(not(test))]
+				screen.request_draw();
+			} else if self.scanline_counter == self.region.get_scanline_count() - 1 {
 				// VBlank end
-				261 => self.ppustatus &= 0x1f,
-				_ => {}
+				self.ppustatus &= 0x1f;
+				self.update_nmi_line();
 			}
 		}
 	}
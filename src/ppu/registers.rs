@@ -1,12 +1,12 @@
 use emulator::*;
 use ppu::*;
 
+#[cfg(feature = "debugger")]
+use accesses::{Bus, AccessKind};
+
 pub fn write_ppuctrl(ppu: &mut Ppu, value: u8) {
     ppu.ppuctrl = value;
-    if (ppu.ppuctrl & ppu.ppustatus & 0x80) != 0 {
-        // TODO: trigger NMI
-        warn!("NMI not triggered");
-    }
+    ppu.update_nmi_line();
 }
 
 pub fn write_ppumask(ppu: &mut Ppu, value: u8) {
@@ -14,13 +14,17 @@ pub fn write_ppumask(ppu: &mut Ppu, value: u8) {
 }
 
 pub fn read_ppustatus(ppu: &mut Ppu) -> u8 {
+    // reading one cycle before VBlank starts prevents the flag from being set for this frame
+    if ppu.scanline_counter == ppu.region.get_vblank_scanline() && ppu.cycle_counter == 0 {
+        ppu.vblank_suppressed = true;
+    }
     let value = ppu.ppustatus;
     ppu.ppustatus &= 0x7f;
     ppu.flipflop = false;
+    ppu.update_nmi_line();
     value
 }
 
-#[cfg(any(feature = "trace", test))]
 pub fn read_ppustatus_debug(ppu: &Ppu) -> u8 {
     ppu.ppustatus
 }
@@ -33,7 +37,6 @@ pub fn read_oamdata(ppu: &mut Ppu) -> u8 {
     ppu.oam[ppu.oamaddr as usize]
 }
 
-#[cfg(any(feature = "trace", test))]
 pub fn read_oamdata_debug(ppu: &Ppu) -> u8 {
     ppu.oam[ppu.oamaddr as usize]
 }
@@ -64,6 +67,8 @@ pub fn write_ppuaddr(ppu: &mut Ppu, value: u8) {
 pub fn read_ppudata(ppu: &mut Ppu) -> u8 {
     let old_value = ppu.ppudata_buffer;
     ppu.ppudata_buffer = ppu.memory.read(ppu.ppuaddr);
+    #[cfg(feature = "debugger")]
+    ppu.memory_accesses.record(Bus::Ppu, AccessKind::Read, ppu.ppuaddr, ppu.ppudata_buffer);
     increment_ppuaddr(ppu);
     if ppu.ppuaddr <= 0x3eff {
         old_value
@@ -72,7 +77,6 @@ pub fn read_ppudata(ppu: &mut Ppu) -> u8 {
     }
 }
 
-#[cfg(any(feature = "trace", test))]
 pub fn read_ppudata_debug(ppu: &Ppu) -> u8 {
     if ppu.ppuaddr <= 0x3eff {
         ppu.ppudata_buffer
@@ -82,6 +86,8 @@ pub fn read_ppudata_debug(ppu: &Ppu) -> u8 {
 }
 
 pub fn write_ppudata(ppu: &mut Ppu, value: u8) {
+    #[cfg(feature = "debugger")]
+    ppu.memory_accesses.record(Bus::Ppu, AccessKind::Write, ppu.ppuaddr, value);
     ppu.memory.write(ppu.ppuaddr, value);
     increment_ppuaddr(ppu);
 }
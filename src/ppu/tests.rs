@@ -1,33 +1,11 @@
-use super::*;
-use emulator::*;
 use cpu::tests::*;
 
-fn run_test(filename: &str) {
-	let mut emulator = Emulator::new();
-	emulator.load_file(filename);
-	Cpu::init_pc(&mut emulator);
-	while read8_debug(&emulator, 0x6000) != 0x80 {
-		let cycles = 3 * Cpu::execute_next_instruction(&mut emulator);
-		for _ in 0..cycles {
-			emulator.ppu.do_cycle(&mut emulator.cpu, &mut emulator.screen);
-		}
-	}
-	while read8_debug(&emulator, 0x6000) == 0x80 {
-		let cycles = 3 * Cpu::execute_next_instruction(&mut emulator);
-		for _ in 0..cycles {
-			emulator.ppu.do_cycle(&mut emulator.cpu, &mut emulator.screen);
-		}
-	}
-	assert_eq!(read8_debug(&emulator, 0x6000), 0);
-}
-
 #[test]
 fn vbl_basics() {
 	run_test("tests/ppu/ppu_vbl_nmi/01-vbl_basics.nes");
 }
 
 #[test]
-#[ignore]
 fn vbl_set_time() {
 	run_test("tests/ppu/ppu_vbl_nmi/02-vbl_set_time.nes");
 }
@@ -38,31 +16,26 @@ fn vbl_clear_time() {
 }
 
 #[test]
-#[ignore]
 fn nmi_control() {
 	run_test("tests/ppu/ppu_vbl_nmi/04-nmi_control.nes");
 }
 
 #[test]
-#[ignore]
 fn nmi_timing() {
 	run_test("tests/ppu/ppu_vbl_nmi/05-nmi_timing.nes");
 }
 
 #[test]
-#[ignore]
 fn suppression() {
 	run_test("tests/ppu/ppu_vbl_nmi/06-suppression.nes");
 }
 
 #[test]
-#[ignore]
 fn nmi_on_timing() {
 	run_test("tests/ppu/ppu_vbl_nmi/07-nmi_on_timing.nes");
 }
 
 #[test]
-#[ignore]
 fn nmi_off_timing() {
 	run_test("tests/ppu/ppu_vbl_nmi/08-nmi_off_timing.nes");
 }
@@ -80,37 +53,31 @@ fn even_odd_timing() {
 }
 
 #[test]
-#[ignore]
 fn basics() {
 	run_test("tests/ppu/ppu_sprite_hit/01-basics.nes");
 }
 
 #[test]
-#[ignore]
 fn alignment() {
 	run_test("tests/ppu/ppu_sprite_hit/02-alignment.nes");
 }
 
 #[test]
-#[ignore]
-fn corner() {
-	run_test("tests/ppu/ppu_sprite_hit/03-corner.nes");
+fn corners() {
+	run_test("tests/ppu/ppu_sprite_hit/03-corners.nes");
 }
 
 #[test]
-#[ignore]
 fn flip() {
 	run_test("tests/ppu/ppu_sprite_hit/04-flip.nes");
 }
 
 #[test]
-#[ignore]
 fn left_clip() {
 	run_test("tests/ppu/ppu_sprite_hit/05-left_clip.nes");
 }
 
 #[test]
-#[ignore]
 fn right_edge() {
 	run_test("tests/ppu/ppu_sprite_hit/06-right_edge.nes");
 }
@@ -122,7 +89,6 @@ fn screen_bottom() {
 }
 
 #[test]
-#[ignore]
 fn double_height() {
 	run_test("tests/ppu/ppu_sprite_hit/08-double_height.nes");
 }
@@ -0,0 +1,256 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io::Result,
+	path::{Path, PathBuf}
+};
+
+use symbols::*;
+
+// more nested calls than the stack can hold come from code leaving subroutines without returning
+const MAX_CALL_DEPTH: usize = 128;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum CallKind {
+	Subroutine,
+	Nmi,
+	Irq
+}
+
+// a JSR target or an interrupt handler, the PRG ROM offset telling apart the banks mapped at the same address
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct Routine {
+	address: u16,
+	prg_rom_offset: Option<usize>
+}
+
+#[derive(Default)]
+struct RoutineStats {
+	calls: u64,
+	// cycles of the routine and the ones it calls
+	inclusive_cycles: u64,
+	// cycles of the routine alone
+	exclusive_cycles: u64
+}
+
+struct Call {
+	routine: Routine,
+	kind: CallKind,
+	// the stack pointer once the return address is pushed, the call returns when it goes above
+	stack_pointer: u8,
+	start_cycles: u64,
+	// the cycles of the interrupts serviced during the call, which aren't counted in its inclusive cycles
+	interrupt_cycles: u64,
+	frame: usize
+}
+
+// a frame starts when the PPU enters VBlank
+#[derive(Default)]
+struct FrameStats {
+	cycles: u64,
+	nmi_cycles: u64,
+	irq_cycles: u64,
+	// scanline and dot at the end of the first NMI handler of the frame
+	nmi_end: Option<(u16, u16)>,
+	// the handler returned after the end of VBlank
+	nmi_overrun: bool
+}
+
+// attributes the CPU cycles to subroutines and interrupt handlers, frame by frame
+pub struct Profiler {
+	filename: PathBuf,
+	routines: HashMap<Routine, RoutineStats>,
+	calls: Vec<Call>,
+	// cycles since the start of the profile
+	cycles: u64,
+	// cycles outside of any subroutine, like the main loop
+	top_level_cycles: u64,
+	frames: Vec<FrameStats>,
+	ppu_frame: Option<u64>
+}
+
+impl Profiler {
+	// the report is written to the file when the profile is saved
+	pub fn new<P: AsRef<Path>>(filename: P) -> Self {
+		Self {
+			filename: filename.as_ref().to_path_buf(),
+			routines: HashMap::new(),
+			calls: Vec::new(),
+			cycles: 0,
+			top_level_cycles: 0,
+			frames: Vec::new(),
+			ppu_frame: None
+		}
+	}
+
+	// the PPU frame counter, checked before each instruction
+	pub fn set_frame(&mut self, ppu_frame: u64) {
+		if self.ppu_frame != Some(ppu_frame) {
+			self.ppu_frame = Some(ppu_frame);
+			self.frames.push(FrameStats::default());
+		}
+	}
+
+	// a JSR, a BRK or an interrupt, with the stack pointer after the return address and the flags are pushed
+	pub fn enter(&mut self, address: u16, prg_rom_offset: Option<usize>, kind: CallKind, stack_pointer: u8) {
+		if self.calls.len() == MAX_CALL_DEPTH {
+			self.calls.remove(0);
+		}
+		let routine = Routine {
+			address,
+			prg_rom_offset
+		};
+		self.routines.entry(routine).or_default().calls += 1;
+		self.calls.push(Call {
+			routine,
+			kind,
+			stack_pointer,
+			start_cycles: self.cycles,
+			interrupt_cycles: 0,
+			frame: self.frames.len().saturating_sub(1)
+		});
+	}
+
+	// the cycles of an instruction, spent in the innermost routine
+	pub fn add_cycles(&mut self, cycles: u64) {
+		self.cycles += cycles;
+		match self.calls.last() {
+			Some(call) => self.routines.get_mut(&call.routine).unwrap().exclusive_cycles += cycles,
+			None => self.top_level_cycles += cycles
+		}
+		let interrupt = self.calls.iter().rev().map(|call| call.kind).find(|&kind| kind != CallKind::Subroutine);
+		if let Some(frame) = self.frames.last_mut() {
+			frame.cycles += cycles;
+			match interrupt {
+				Some(CallKind::Nmi) => frame.nmi_cycles += cycles,
+				Some(CallKind::Irq) => frame.irq_cycles += cycles,
+				_ => {}
+			}
+		}
+	}
+
+	// ends the calls the stack pointer went above, by a RTS, a RTI or code dropping return addresses, and returns the frame of the NMI handler ended if there is one
+	pub fn leave(&mut self, stack_pointer: u8) -> Option<usize> {
+		let mut nmi_frame = None;
+		while self.calls.last().is_some_and(|call| call.stack_pointer < stack_pointer) {
+			let call = self.calls.pop().unwrap();
+			let cycles = self.cycles - call.start_cycles;
+			// a recursive call is already counted by the outer one
+			if !self.calls.iter().any(|outer_call| outer_call.routine == call.routine) {
+				self.routines.get_mut(&call.routine).unwrap().inclusive_cycles += cycles - call.interrupt_cycles;
+			}
+			if call.kind != CallKind::Subroutine {
+				for outer_call in self.calls.iter_mut() {
+					outer_call.interrupt_cycles += cycles;
+				}
+			}
+			if call.kind == CallKind::Nmi {
+				nmi_frame = Some(call.frame);
+			}
+		}
+		nmi_frame
+	}
+
+	// the end of the NMI handler of a frame, at a PPU position after the end of VBlank if it is an overrun
+	pub fn end_nmi(&mut self, frame: usize, position: (u16, u16), overrun: bool) {
+		if let Some(frame) = self.frames.get_mut(frame) {
+			if frame.nmi_end.is_none() {
+				frame.nmi_end = Some(position);
+				frame.nmi_overrun = overrun;
+			}
+		}
+	}
+
+	// the routines still running count until now, without the interrupt they may be in
+	fn get_inclusive_cycles(&self, routine: &Routine, stats: &RoutineStats) -> u64 {
+		let running_cycles = match self.calls.iter().position(|call| call.routine == *routine) {
+			Some(index) => {
+				let call = &self.calls[index];
+				let interrupt = self.calls[index + 1..].iter().find(|inner_call| inner_call.kind != CallKind::Subroutine);
+				self.cycles - call.start_cycles - call.interrupt_cycles - interrupt.map_or(0, |interrupt| self.cycles - interrupt.start_cycles)
+			},
+			None => 0
+		};
+		stats.inclusive_cycles + running_cycles
+	}
+
+	// the routines sorted by inclusive cycles
+	fn get_sorted_routines(&self) -> Vec<(&Routine, &RoutineStats, u64)> {
+		let mut routines: Vec<_> = self.routines.iter().map(|(routine, stats)| (routine, stats, self.get_inclusive_cycles(routine, stats))).collect();
+		routines.sort_by_key(|&(routine, _, inclusive_cycles)| (std::cmp::Reverse(inclusive_cycles), routine.address));
+		routines
+	}
+
+	// "$C123", its label if it has one, the PRG ROM offset telling apart the banks otherwise
+	fn get_routine_name(routine: &Routine, symbols: &SymbolTable) -> String {
+		match (symbols.get_label(routine.address, routine.prg_rom_offset), routine.prg_rom_offset) {
+			(Some(label), _) => label.to_string(),
+			(None, Some(offset)) => format!("${:04X} (PRG ROM ${:05X})", routine.address, offset),
+			(None, None) => format!("${:04X}", routine.address)
+		}
+	}
+
+	// a JSON report if the extension is .json, a text one otherwise
+	pub fn save(&self, symbols: &SymbolTable) -> Result<()> {
+		let report = match self.filename.extension().and_then(|extension| extension.to_str()) {
+			Some("json") => self.format_json(symbols),
+			_ => self.format_text(symbols)
+		};
+		fs::write(&self.filename, report)
+	}
+
+	pub fn get_filename(&self) -> &Path {
+		&self.filename
+	}
+
+	pub fn describe(&self) -> String {
+		let frame_count = self.frames.len().max(1) as u64;
+		let nmi_cycles = self.frames.iter().map(|frame| frame.nmi_cycles).sum::<u64>();
+		let overrun_count = self.frames.iter().filter(|frame| frame.nmi_overrun).count();
+		format!("{} cycles in {} frames, {} NMI cycles per frame, {} NMI overruns", self.cycles, self.frames.len(), nmi_cycles / frame_count, overrun_count)
+	}
+
+	pub fn format_text(&self, symbols: &SymbolTable) -> String {
+		let percentage = |cycles: u64| 100.0 * cycles as f64 / self.cycles.max(1) as f64;
+		let mut report = format!("{}\n\n", self.describe());
+		report += &format!("{:<32} {:>8} {:>12} {:>7} {:>12} {:>7}\n", "Routine", "Calls", "Inclusive", "%", "Exclusive", "%");
+		report += &format!("{:<32} {:>8} {:>12} {:>7} {:>12} {:>6.2}%\n", "(top level)", "", "", "", self.top_level_cycles, percentage(self.top_level_cycles));
+		for (routine, stats, inclusive_cycles) in self.get_sorted_routines() {
+			report += &format!("{:<32} {:>8} {:>12} {:>6.2}% {:>12} {:>6.2}%\n", Self::get_routine_name(routine, symbols), stats.calls, inclusive_cycles, percentage(inclusive_cycles), stats.exclusive_cycles, percentage(stats.exclusive_cycles));
+		}
+		report += &format!("\n{:>6} {:>8} {:>8} {:>8} {:>12}\n", "Frame", "Cycles", "NMI", "IRQ", "NMI end");
+		for (index, frame) in self.frames.iter().enumerate() {
+			let nmi_end = match frame.nmi_end {
+				Some((scanline, dot)) => format!("{:>3},{:>3}{}", scanline, dot, if frame.nmi_overrun { " overrun" } else { "" }),
+				None => "-".to_string()
+			};
+			report += &format!("{:>6} {:>8} {:>8} {:>8} {:>12}\n", index, frame.cycles, frame.nmi_cycles, frame.irq_cycles, nmi_end);
+		}
+		report
+	}
+
+	pub fn format_json(&self, symbols: &SymbolTable) -> String {
+		let routines: Vec<_> = self.get_sorted_routines().iter().map(|&(routine, stats, inclusive_cycles)| {
+			let prg_rom_offset = routine.prg_rom_offset.map_or("null".to_string(), |offset| offset.to_string());
+			format!("    {{\"name\": {}, \"address\": {}, \"prg_rom_offset\": {}, \"calls\": {}, \"inclusive_cycles\": {}, \"exclusive_cycles\": {}}}", quote_json(&Self::get_routine_name(routine, symbols)), routine.address, prg_rom_offset, stats.calls, inclusive_cycles, stats.exclusive_cycles)
+		}).collect();
+		let frames: Vec<_> = self.frames.iter().map(|frame| {
+			let (nmi_end_scanline, nmi_end_dot) = frame.nmi_end.map_or(("null".to_string(), "null".to_string()), |(scanline, dot)| (scanline.to_string(), dot.to_string()));
+			format!("    {{\"cycles\": {}, \"nmi_cycles\": {}, \"irq_cycles\": {}, \"nmi_end_scanline\": {}, \"nmi_end_dot\": {}, \"nmi_overrun\": {}}}", frame.cycles, frame.nmi_cycles, frame.irq_cycles, nmi_end_scanline, nmi_end_dot, frame.nmi_overrun)
+		}).collect();
+		format!("{{\n  \"cycles\": {},\n  \"top_level_cycles\": {},\n  \"routines\": [\n{}\n  ],\n  \"frames\": [\n{}\n  ]\n}}\n", self.cycles, self.top_level_cycles, routines.join(",\n"), frames.join(",\n"))
+	}
+}
+
+fn quote_json(string: &str) -> String {
+	let mut quoted = String::from("\"");
+	for character in string.chars() {
+		match character {
+			'"' => quoted += "\\\"",
+			'\\' => quoted += "\\\\",
+			character if (character as u32) < 0x20 => quoted += &format!("\\u{:04x}", character as u32),
+			character => quoted.push(character)
+		}
+	}
+	quoted + "\""
+}
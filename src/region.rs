@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Region {
+	Ntsc,
+	Pal,
+	Dendy
+}
+
+impl Region {
+	// from the NES 2.0 header, else the tags of the filename, else the PAL bit of the iNES 1.0 header
+	pub fn detect(filename: &str, contents: &[u8]) -> Self {
+		// NES 2.0 header
+		if (contents[7] & 0x0c) == 0x08 {
+			return match contents[12] & 0b11 {
+				1 => Region::Pal,
+				3 => Region::Dendy,
+				_ => Region::Ntsc // NTSC or multi-region
+			};
+		}
+
+		// GoodNES and No-Intro style tags
+		const PAL_TAGS: [&str; 4] = ["(E)", "(Europe)", "(PAL)", "(A)"];
+		if PAL_TAGS.iter().any(|tag| filename.contains(tag)) {
+			return Region::Pal;
+		}
+
+		// rarely set in iNES 1.0 headers
+		if (contents[9] & 1) != 0 {
+			return Region::Pal;
+		}
+
+		Region::Ntsc
+	}
+
+	pub fn get_scanline_count(&self) -> u16 {
+		match self {
+			Region::Ntsc => 262,
+			Region::Pal | Region::Dendy => 312
+		}
+	}
+
+	pub fn get_vblank_scanline(&self) -> u16 {
+		match self {
+			Region::Ntsc | Region::Pal => 241,
+			Region::Dendy => 291 // 50 extra post-render scanlines
+		}
+	}
+
+	// as a fraction, PAL runs 3.2 PPU cycles per CPU cycle
+	pub fn get_ppu_cycles_per_cpu_cycle(&self) -> (u8, u8) {
+		match self {
+			Region::Ntsc | Region::Dendy => (3, 1),
+			Region::Pal => (16, 5)
+		}
+	}
+
+	pub fn get_frame_rate(&self) -> f64 {
+		match self {
+			Region::Ntsc => 60.0988,
+			Region::Pal | Region::Dendy => 50.007
+		}
+	}
+}
+
+impl FromStr for Region {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"ntsc" => Ok(Region::Ntsc),
+			"pal" => Ok(Region::Pal),
+			"dendy" => Ok(Region::Dendy),
+			_ => Err(format!("Unknown region {}", s))
+		}
+	}
+}
@@ -0,0 +1,132 @@
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+use winit::window::Window;
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+use x11_dl::xlib;
+
+#[cfg(target_os = "windows")]
+use winapi::{
+    shared::windef::HWND,
+    um::{wingdi, winuser}
+};
+
+const UNSUPPORTED_WINDOW_SYSTEM: &str = "The software renderer doesn't support this window system";
+
+// copies 0RGB pixels to a window with the drawing functions of the window system, winit owns the window and the connection
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+pub struct Blitter {
+    xlib: xlib::Xlib,
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    visual: *mut xlib::Visual,
+    depth: i32,
+    gc: xlib::GC
+}
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+impl Blitter {
+    pub fn new(window: &Window) -> Result<Self, String> {
+        let handle = match window.raw_window_handle() {
+            RawWindowHandle::Xlib(handle) => handle,
+            _ => return Err(UNSUPPORTED_WINDOW_SYSTEM.to_string())
+        };
+        unsafe {
+            let xlib = xlib::Xlib::open().map_err(|error| format!("Couldn't load Xlib: {}", error))?;
+            let display = handle.display as *mut xlib::Display;
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            if (xlib.XGetWindowAttributes)(display, handle.window, &mut attributes) == 0 {
+                return Err("Couldn't get the attributes of the window".to_string());
+            }
+            // the pixels are copied as they are, so the window must store them in the same order
+            let visual = &*attributes.visual;
+            if (attributes.depth != 24 && attributes.depth != 32) || visual.red_mask != 0xff0000 || visual.green_mask != 0x00ff00 || visual.blue_mask != 0x0000ff {
+                return Err(format!("The software renderer doesn't support the {}-bit visual of the window", attributes.depth));
+            }
+            let gc = (xlib.XCreateGC)(display, handle.window, 0, std::ptr::null_mut());
+            Ok(Self {
+                xlib,
+                display,
+                window: handle.window,
+                visual: attributes.visual,
+                depth: attributes.depth,
+                gc
+            })
+        }
+    }
+
+    // the pixels fill the window, row by row from the top
+    pub fn blit(&mut self, pixels: &mut [u32], width: usize, height: usize) {
+        unsafe {
+            let image = (self.xlib.XCreateImage)(self.display, self.visual, self.depth as u32, xlib::ZPixmap, 0, pixels.as_mut_ptr() as *mut _, width as u32, height as u32, 32, 0);
+            if image.is_null() {
+                error!("Couldn't draw the window: XCreateImage failed");
+                return;
+            }
+            (self.xlib.XPutImage)(self.display, self.window, self.gc, image, 0, 0, 0, 0, width as u32, height as u32);
+            // the pixels belong to the surface, not to the image
+            (*image).data = std::ptr::null_mut();
+            (self.xlib.XDestroyImage)(image);
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+impl Drop for Blitter {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xlib.XFreeGC)(self.display, self.gc);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub struct Blitter {
+    window: HWND
+}
+
+#[cfg(target_os = "windows")]
+impl Blitter {
+    pub fn new(window: &Window) -> Result<Self, String> {
+        match window.raw_window_handle() {
+            RawWindowHandle::Windows(handle) => Ok(Self {
+                window: handle.hwnd as HWND
+            }),
+            _ => Err(UNSUPPORTED_WINDOW_SYSTEM.to_string())
+        }
+    }
+
+    pub fn blit(&mut self, pixels: &mut [u32], width: usize, height: usize) {
+        unsafe {
+            let mut info: wingdi::BITMAPINFO = std::mem::zeroed();
+            info.bmiHeader.biSize = std::mem::size_of::<wingdi::BITMAPINFOHEADER>() as u32;
+            info.bmiHeader.biWidth = width as i32;
+            // a negative height for the rows to go from the top
+            info.bmiHeader.biHeight = -(height as i32);
+            info.bmiHeader.biPlanes = 1;
+            info.bmiHeader.biBitCount = 32;
+            info.bmiHeader.biCompression = wingdi::BI_RGB;
+            let device_context = winuser::GetDC(self.window);
+            if device_context.is_null() {
+                error!("Couldn't draw the window: GetDC failed");
+                return;
+            }
+            wingdi::SetDIBitsToDevice(device_context, 0, 0, width as u32, height as u32, 0, 0, 0, height as u32, pixels.as_ptr() as *const _, &info, wingdi::DIB_RGB_COLORS);
+            winuser::ReleaseDC(self.window, device_context);
+        }
+    }
+}
+
+// no drawing functions for the other window systems yet, the Wayland windows of Linux and BSD are refused in Blitter::new as well
+#[cfg(not(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd", target_os = "windows")))]
+pub struct Blitter;
+
+#[cfg(not(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd", target_os = "windows")))]
+impl Blitter {
+    pub fn new(_window: &Window) -> Result<Self, String> {
+        Err(UNSUPPORTED_WINDOW_SYSTEM.to_string())
+    }
+
+    pub fn blit(&mut self, _pixels: &mut [u32], _width: usize, _height: usize) {}
+}
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use mu::screen::*;
+
+const PIXEL_SIZE: usize = 4;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum AspectRatio {
+    Stretch, // fills the whole window
+    Square, // 1:1 pixels
+    Ntsc // 8:7 pixels, like a NTSC television
+}
+
+impl FromStr for AspectRatio {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "stretch" => Ok(AspectRatio::Stretch),
+            "1:1" => Ok(AspectRatio::Square),
+            "8:7" => Ok(AspectRatio::Ntsc),
+            _ => Err(format!("Unknown aspect ratio: {}", name))
+        }
+    }
+}
+
+impl AspectRatio {
+    fn get_pixel_aspect_ratio(&self) -> f32 {
+        match self {
+            AspectRatio::Ntsc => 8.0 / 7.0,
+            _ => 1.0
+        }
+    }
+}
+
+// lines and columns hidden on each side of the frame, in NES pixels
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Overscan {
+    pub top: u32,
+    pub bottom: u32,
+    pub left: u32,
+    pub right: u32
+}
+
+impl FromStr for Overscan {
+    type Err = String;
+
+    // "<top>,<bottom>,<left>,<right>" or a single value for all sides
+    fn from_str(values: &str) -> Result<Self, Self::Err> {
+        let values = values.split(',').map(|value| value.trim().parse().map_err(|_| format!("Wrong overscan: {}", value))).collect::<Result<Vec<u32>, _>>()?;
+        let overscan = match values[..] {
+            [all] => Self {
+                top: all,
+                bottom: all,
+                left: all,
+                right: all
+            },
+            [top, bottom, left, right] => Self {
+                top,
+                bottom,
+                left,
+                right
+            },
+            _ => return Err("Overscan needs 1 or 4 values".to_string())
+        };
+        if overscan.top + overscan.bottom >= FRAME_HEIGHT as u32 || overscan.left + overscan.right >= FRAME_WIDTH as u32 {
+            return Err("Overscan hides the whole frame".to_string());
+        }
+        Ok(overscan)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct DisplaySettings {
+    pub integer_scaling: bool,
+    pub aspect_ratio: AspectRatio,
+    pub overscan: Overscan
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            integer_scaling: false,
+            aspect_ratio: AspectRatio::Stretch,
+            overscan: Overscan::default()
+        }
+    }
+}
+
+impl DisplaySettings {
+    // offset and size of the visible part of the frame, in texture coordinates
+    pub(super) fn get_texture_rectangle(&self) -> [f32; 4] {
+        let overscan = &self.overscan;
+        [
+            overscan.left as f32 / FRAME_WIDTH as f32,
+            overscan.top as f32 / FRAME_HEIGHT as f32,
+            (FRAME_WIDTH as u32 - overscan.left - overscan.right) as f32 / FRAME_WIDTH as f32,
+            (FRAME_HEIGHT as u32 - overscan.top - overscan.bottom) as f32 / FRAME_HEIGHT as f32
+        ]
+    }
+
+    // position and size of the image in the window, centered with black bars around it
+    pub(super) fn get_viewport(&self, window_width: u32, window_height: u32) -> [f32; 4] {
+        let (window_width, window_height) = (window_width as f32, window_height as f32);
+        let visible_width = (FRAME_WIDTH as u32 - self.overscan.left - self.overscan.right) as f32;
+        let visible_height = (FRAME_HEIGHT as u32 - self.overscan.top - self.overscan.bottom) as f32;
+        let (width, height) = if self.aspect_ratio == AspectRatio::Stretch && !self.integer_scaling {
+            (window_width, window_height)
+        } else {
+            let display_width = visible_width * self.aspect_ratio.get_pixel_aspect_ratio();
+            let mut scale = (window_width / display_width).min(window_height / visible_height);
+            // the vertical scale is an integer, the horizontal one only is for 1:1 pixels
+            if self.integer_scaling && scale >= 1.0 {
+                scale = scale.floor();
+            }
+            ((display_width * scale).round(), visible_height * scale)
+        };
+        [((window_width - width) / 2.0).floor(), ((window_height - height) / 2.0).floor(), width, height]
+    }
+
+    // nearest neighbour scaling of a RGBA frame to a RGBA image of the window size, for the scaled screenshots
+    pub fn scale_frame(&self, frame_buffer: &[u8], frame_width: usize, frame_height: usize, window_width: usize, window_height: usize) -> Vec<u8> {
+        let mut image = vec![0; window_width * window_height * PIXEL_SIZE];
+        let [x, y, width, height] = self.get_viewport(window_width as _, window_height as _);
+        let [texture_x, texture_y, texture_width, texture_height] = self.get_texture_rectangle();
+        let (x, y) = (x.max(0.0) as usize, y.max(0.0) as usize);
+        for row in 0..(height as usize).min(window_height - y) {
+            let source_row = ((texture_y + (row as f32 + 0.5) / height * texture_height) * frame_height as f32) as usize;
+            for column in 0..(width as usize).min(window_width - x) {
+                let source_column = ((texture_x + (column as f32 + 0.5) / width * texture_width) * frame_width as f32) as usize;
+                let source = (source_row * frame_width + source_column) * PIXEL_SIZE;
+                let destination = ((y + row) * window_width + x + column) * PIXEL_SIZE;
+                image[destination..destination + PIXEL_SIZE].copy_from_slice(&frame_buffer[source..source + PIXEL_SIZE]);
+            }
+        }
+        image
+    }
+}
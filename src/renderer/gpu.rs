@@ -0,0 +1,438 @@
+use wgpu::*;
+use wgpu::util::{DeviceExt, BufferInitDescriptor};
+use futures::executor::block_on;
+
+use winit::{
+    window::Window,
+    dpi::PhysicalSize
+};
+
+use super::*;
+
+// format of the textures between two passes
+const TARGET_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+struct ShaderPass {
+    render_pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    // the last pass draws to the window instead
+    target: Option<(Texture, TextureView)>
+}
+
+// extra window showing an image with the device of the main window, for the debug viewers
+pub struct GpuViewerSurface {
+    surface: Surface,
+    swap_chain_descriptor: SwapChainDescriptor,
+    swap_chain: SwapChain,
+    texture_size: Extent3d,
+    texture: Texture,
+    render_pipeline: RenderPipeline,
+    bind_group: BindGroup
+}
+
+pub struct GpuRenderer {
+    instance: Instance,
+    surface: Surface,
+    device: Device,
+    queue: Queue,
+    swap_chain_descriptor: SwapChainDescriptor,
+    swap_chain: SwapChain,
+    bind_group_layout: BindGroupLayout,
+    pipeline_layout: PipelineLayout,
+    texture_size: Extent3d,
+    texture: Texture,
+    preset: Preset,
+    display_settings: DisplaySettings,
+    viewport: [f32; 4],
+    passes: Vec<ShaderPass>
+}
+
+impl GpuRenderer {
+    // fails without a GPU adapter able to draw to the window
+    pub fn new(window: &Window, frame_width: u32, frame_height: u32) -> Result<Self, String> {
+        let window_size = window.inner_size();
+        let instance = Instance::new(BackendBit::VULKAN);
+        let surface = unsafe { instance.create_surface(window) };
+        let adapter_options = RequestAdapterOptions {
+            power_preference: PowerPreference::Default,
+            compatible_surface: Some(&surface)
+        };
+        let adapter = block_on(instance.request_adapter(&adapter_options)).ok_or_else(|| "No GPU adapter for the window".to_string())?;
+        let device_descriptor = DeviceDescriptor {
+            features: Features::empty(),
+            limits: Limits::default(),
+            shader_validation: true
+        };
+        let (device, queue) = block_on(adapter.request_device(&device_descriptor, None)).map_err(|error| format!("Couldn't open the GPU device: {}", error))?;
+        let swap_chain_descriptor = SwapChainDescriptor {
+            usage: TextureUsage::OUTPUT_ATTACHMENT,
+            format: TextureFormat::Bgra8UnormSrgb,
+            width: window_size.width,
+            height: window_size.height,
+            present_mode: PresentMode::Mailbox
+        };
+        let swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+        let bind_group_layout_entry_0 = BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStage::FRAGMENT,
+            ty: BindingType::SampledTexture {
+                multisampled: false,
+                dimension: TextureViewDimension::D2,
+                component_type: TextureComponentType::Uint
+            },
+            count: None
+        };
+        let bind_group_layout_entry_1 = BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStage::FRAGMENT,
+            ty: BindingType::Sampler {
+                comparison: false
+            },
+            count: None
+        };
+        // source and output sizes of the pass, and the part of the source to draw
+        let bind_group_layout_entry_2 = BindGroupLayoutEntry {
+            binding: 2,
+            visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT,
+            ty: BindingType::UniformBuffer {
+                dynamic: false,
+                min_binding_size: None
+            },
+            count: None
+        };
+        let bind_group_layout_descriptor = BindGroupLayoutDescriptor {
+            entries: &[bind_group_layout_entry_0, bind_group_layout_entry_1, bind_group_layout_entry_2],
+            label: None
+        };
+        let bind_group_layout = device.create_bind_group_layout(&bind_group_layout_descriptor);
+        let pipeline_layout_descriptor = PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[]
+        };
+        let pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descriptor);
+        let (texture_size, texture) = create_texture(&device, frame_width, frame_height, TextureFormat::Rgba8UnormSrgb, TextureUsage::SAMPLED | TextureUsage::COPY_DST);
+        let mut renderer = Self {
+            instance,
+            surface,
+            device,
+            queue,
+            swap_chain_descriptor,
+            swap_chain,
+            bind_group_layout,
+            pipeline_layout,
+            texture_size,
+            texture,
+            preset: Preset::builtin("none").unwrap(),
+            display_settings: DisplaySettings::default(),
+            viewport: [0.0, 0.0, window_size.width as f32, window_size.height as f32],
+            passes: Vec::new()
+        };
+        renderer.create_passes();
+        Ok(renderer)
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.swap_chain_descriptor.width = size.width;
+        self.swap_chain_descriptor.height = size.height;
+        self.swap_chain = self.device.create_swap_chain(&self.surface, &self.swap_chain_descriptor);
+        self.create_passes();
+    }
+
+    // the frame size changes when the NTSC filter is toggled
+    pub fn set_frame_size(&mut self, frame_width: u32, frame_height: u32) {
+        let (texture_size, texture) = create_texture(&self.device, frame_width, frame_height, TextureFormat::Rgba8UnormSrgb, TextureUsage::SAMPLED | TextureUsage::COPY_DST);
+        self.texture_size = texture_size;
+        self.texture = texture;
+        self.create_passes();
+    }
+
+    pub fn set_preset(&mut self, preset: Preset) {
+        self.preset = preset;
+        self.create_passes();
+    }
+
+    pub fn set_display_settings(&mut self, display_settings: DisplaySettings) {
+        self.display_settings = display_settings;
+        self.create_passes();
+    }
+
+    // the intermediate textures depend on the frame and window sizes, so the passes are recreated when one of them changes
+    fn create_passes(&mut self) {
+        self.viewport = self.display_settings.get_viewport(self.swap_chain_descriptor.width, self.swap_chain_descriptor.height);
+        let viewport_size = (self.viewport[2], self.viewport[3]);
+        let mut source_size = (self.texture_size.width as f32, self.texture_size.height as f32);
+        let mut source_view = self.texture.create_view(&TextureViewDescriptor::default());
+        let vertex_shader_module = self.device.create_shader_module(include_spirv!("../shaders/shader.vert.spv"));
+        self.passes.clear();
+        for (number, pass) in self.preset.passes.iter().enumerate() {
+            let is_last = number == self.preset.passes.len() - 1;
+            // only the first pass crops the overscan
+            let texture_rectangle = if number == 0 {
+                self.display_settings.get_texture_rectangle()
+            } else {
+                [0.0, 0.0, 1.0, 1.0]
+            };
+            let output_size = match pass.scale {
+                Scale::Source(scale) if !is_last => {
+                    let width = source_size.0 * texture_rectangle[2] * scale;
+                    let height = source_size.1 * texture_rectangle[3] * scale;
+                    (width.round().max(1.0), height.round().max(1.0))
+                },
+                _ => viewport_size
+            };
+            let filter_mode = if pass.linear_filtering {
+                FilterMode::Linear
+            } else {
+                FilterMode::Nearest
+            };
+            let sizes = [source_size.0, source_size.1, output_size.0, output_size.1,
+                         texture_rectangle[0], texture_rectangle[1], texture_rectangle[2], texture_rectangle[3]];
+            let bind_group = self.create_bind_group(&source_view, filter_mode, sizes);
+            let fragment_shader_module = self.device.create_shader_module(match pass.shader {
+                Shader::Passthrough => include_spirv!("../shaders/passthrough.frag.spv"),
+                Shader::Scanlines => include_spirv!("../shaders/scanlines.frag.spv"),
+                Shader::ApertureMask => include_spirv!("../shaders/aperture_mask.frag.spv"),
+                Shader::SharpBilinear => include_spirv!("../shaders/sharp_bilinear.frag.spv")
+            });
+            let format = if is_last {
+                self.swap_chain_descriptor.format
+            } else {
+                TARGET_FORMAT
+            };
+            let render_pipeline = create_render_pipeline(&self.device, &self.pipeline_layout, &vertex_shader_module, &fragment_shader_module, format);
+            let target = if is_last {
+                None
+            } else {
+                let (_, texture) = create_texture(&self.device, output_size.0 as _, output_size.1 as _, TARGET_FORMAT, TextureUsage::SAMPLED | TextureUsage::OUTPUT_ATTACHMENT);
+                let view = texture.create_view(&TextureViewDescriptor::default());
+                // the output of this pass is the input of the next one
+                source_view = texture.create_view(&TextureViewDescriptor::default());
+                source_size = output_size;
+                Some((texture, view))
+            };
+            self.passes.push(ShaderPass {
+                render_pipeline,
+                bind_group,
+                target
+            });
+        }
+    }
+
+    fn create_bind_group(&self, source_view: &TextureView, filter_mode: FilterMode, sizes: [f32; 8]) -> BindGroup {
+        let sampler_descriptor = SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        };
+        let sampler = self.device.create_sampler(&sampler_descriptor);
+        let uniform_buffer_descriptor = BufferInitDescriptor {
+            label: None,
+            contents: &sizes.iter().flat_map(|size| size.to_ne_bytes().to_vec()).collect::<Vec<u8>>(),
+            usage: BufferUsage::UNIFORM
+        };
+        let uniform_buffer = self.device.create_buffer_init(&uniform_buffer_descriptor);
+        let bind_group_entry_0 = BindGroupEntry {
+            binding: 0,
+            resource: BindingResource::TextureView(source_view)
+        };
+        let bind_group_entry_1 = BindGroupEntry {
+            binding: 1,
+            resource: BindingResource::Sampler(&sampler)
+        };
+        let bind_group_entry_2 = BindGroupEntry {
+            binding: 2,
+            resource: BindingResource::Buffer(uniform_buffer.slice(..))
+        };
+        let bind_group_descriptor = BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[bind_group_entry_0, bind_group_entry_1, bind_group_entry_2],
+            label: None
+        };
+        self.device.create_bind_group(&bind_group_descriptor)
+    }
+
+    pub fn draw(&mut self, frame_buffer: &[u8]) {
+        let texture_copy_view = TextureCopyView {
+            texture: &self.texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO
+        };
+        let texture_data_layout = TextureDataLayout {
+            offset: 0,
+            bytes_per_row: 4 * self.texture_size.width,
+            rows_per_image: self.texture_size.height
+        };
+        self.queue.write_texture(texture_copy_view, frame_buffer, texture_data_layout, self.texture_size);
+
+        let frame = self.swap_chain.get_current_frame().unwrap().output;
+        let encoder_descriptor = CommandEncoderDescriptor {
+            label: None
+        };
+        let mut encoder = self.device.create_command_encoder(&encoder_descriptor);
+        for pass in &self.passes {
+            let (attachment, viewport) = match pass.target {
+                Some((_, ref view)) => (view, None),
+                None => (&frame.view, Some(self.viewport))
+            };
+            let render_pass_color_attachment_descriptor = RenderPassColorAttachmentDescriptor {
+                attachment,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: true
+                }
+            };
+            let render_pass_descriptor = RenderPassDescriptor {
+                color_attachments: &[render_pass_color_attachment_descriptor],
+                depth_stencil_attachment: None
+            };
+            let mut render_pass = encoder.begin_render_pass(&render_pass_descriptor);
+            if let Some([x, y, width, height]) = viewport {
+                render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+            }
+            render_pass.set_pipeline(&pass.render_pipeline);
+            render_pass.set_bind_group(0, &pass.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    // the image is stretched to the window with nearest neighbour filtering
+    pub fn create_viewer_surface(&self, window: &Window, image_width: u32, image_height: u32) -> GpuViewerSurface {
+        let window_size = window.inner_size();
+        let surface = unsafe { self.instance.create_surface(window) };
+        let swap_chain_descriptor = SwapChainDescriptor {
+            width: window_size.width,
+            height: window_size.height,
+            ..self.swap_chain_descriptor.clone()
+        };
+        let swap_chain = self.device.create_swap_chain(&surface, &swap_chain_descriptor);
+        let (texture_size, texture) = create_texture(&self.device, image_width, image_height, TextureFormat::Rgba8UnormSrgb, TextureUsage::SAMPLED | TextureUsage::COPY_DST);
+        let vertex_shader_module = self.device.create_shader_module(include_spirv!("../shaders/shader.vert.spv"));
+        let fragment_shader_module = self.device.create_shader_module(include_spirv!("../shaders/passthrough.frag.spv"));
+        let render_pipeline = create_render_pipeline(&self.device, &self.pipeline_layout, &vertex_shader_module, &fragment_shader_module, swap_chain_descriptor.format);
+        let bind_group = self.create_viewer_bind_group(&texture, texture_size, &swap_chain_descriptor);
+        GpuViewerSurface {
+            surface,
+            swap_chain_descriptor,
+            swap_chain,
+            texture_size,
+            texture,
+            render_pipeline,
+            bind_group
+        }
+    }
+
+    pub fn resize_viewer_surface(&self, viewer_surface: &mut GpuViewerSurface, size: PhysicalSize<u32>) {
+        viewer_surface.swap_chain_descriptor.width = size.width;
+        viewer_surface.swap_chain_descriptor.height = size.height;
+        viewer_surface.swap_chain = self.device.create_swap_chain(&viewer_surface.surface, &viewer_surface.swap_chain_descriptor);
+        viewer_surface.bind_group = self.create_viewer_bind_group(&viewer_surface.texture, viewer_surface.texture_size, &viewer_surface.swap_chain_descriptor);
+    }
+
+    fn create_viewer_bind_group(&self, texture: &Texture, texture_size: Extent3d, swap_chain_descriptor: &SwapChainDescriptor) -> BindGroup {
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let sizes = [texture_size.width as f32, texture_size.height as f32,
+                     swap_chain_descriptor.width as f32, swap_chain_descriptor.height as f32,
+                     0.0, 0.0, 1.0, 1.0];
+        self.create_bind_group(&view, FilterMode::Nearest, sizes)
+    }
+
+    pub fn draw_viewer_surface(&self, viewer_surface: &mut GpuViewerSurface, image: &[u8]) {
+        let texture_copy_view = TextureCopyView {
+            texture: &viewer_surface.texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO
+        };
+        let texture_data_layout = TextureDataLayout {
+            offset: 0,
+            bytes_per_row: 4 * viewer_surface.texture_size.width,
+            rows_per_image: viewer_surface.texture_size.height
+        };
+        self.queue.write_texture(texture_copy_view, image, texture_data_layout, viewer_surface.texture_size);
+
+        let frame = viewer_surface.swap_chain.get_current_frame().unwrap().output;
+        let encoder_descriptor = CommandEncoderDescriptor {
+            label: None
+        };
+        let mut encoder = self.device.create_command_encoder(&encoder_descriptor);
+        {
+            let render_pass_color_attachment_descriptor = RenderPassColorAttachmentDescriptor {
+                attachment: &frame.view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: true
+                }
+            };
+            let render_pass_descriptor = RenderPassDescriptor {
+                color_attachments: &[render_pass_color_attachment_descriptor],
+                depth_stencil_attachment: None
+            };
+            let mut render_pass = encoder.begin_render_pass(&render_pass_descriptor);
+            render_pass.set_pipeline(&viewer_surface.render_pipeline);
+            render_pass.set_bind_group(0, &viewer_surface.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+fn create_texture(device: &Device, width: u32, height: u32, format: TextureFormat, usage: TextureUsage) -> (Extent3d, Texture) {
+    let texture_size = Extent3d {
+        width,
+        height,
+        depth: 1
+    };
+    let texture_descriptor = TextureDescriptor {
+        size: texture_size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage,
+        label: None
+    };
+    (texture_size, device.create_texture(&texture_descriptor))
+}
+
+fn create_render_pipeline(device: &Device, pipeline_layout: &PipelineLayout, vertex_shader_module: &ShaderModule, fragment_shader_module: &ShaderModule, format: TextureFormat) -> RenderPipeline {
+    let vertex_stage_descriptor = ProgrammableStageDescriptor {
+        module: vertex_shader_module,
+        entry_point: "main"
+    };
+    let fragment_stage_descriptor = ProgrammableStageDescriptor {
+        module: fragment_shader_module,
+        entry_point: "main"
+    };
+    let color_state_descriptor = ColorStateDescriptor {
+        format,
+        color_blend: BlendDescriptor::REPLACE,
+        alpha_blend: BlendDescriptor::REPLACE,
+        write_mask: ColorWrite::ALL
+    };
+    let vertex_state_descriptor = VertexStateDescriptor {
+        index_format: IndexFormat::Uint16,
+        vertex_buffers: &[]
+    };
+    let render_pipeline_descriptor = RenderPipelineDescriptor {
+        label: None,
+        layout: Some(pipeline_layout),
+        vertex_stage: vertex_stage_descriptor,
+        fragment_stage: Some(fragment_stage_descriptor),
+        rasterization_state: None,
+        color_states: &[color_state_descriptor],
+        primitive_topology: PrimitiveTopology::TriangleList,
+        depth_stencil_state: None,
+        vertex_state: vertex_state_descriptor,
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false
+    };
+    device.create_render_pipeline(&render_pipeline_descriptor)
+}
@@ -0,0 +1,126 @@
+mod preset;
+mod display;
+mod gpu;
+#[cfg(feature = "software-renderer")]
+mod software;
+#[cfg(feature = "software-renderer")]
+mod blitter;
+
+pub use self::preset::*;
+pub use self::display::*;
+pub use self::gpu::*;
+#[cfg(feature = "software-renderer")]
+pub use self::software::*;
+
+use winit::{
+    window::Window,
+    dpi::PhysicalSize
+};
+
+// draws the frames to the main window and the images of the debug viewers, on the GPU or on the CPU
+pub enum Renderer {
+    Gpu(GpuRenderer),
+    #[cfg(feature = "software-renderer")]
+    Software(SoftwareRenderer)
+}
+
+// a debug viewer window, drawn by the same kind of renderer as the main window
+pub enum ViewerSurface {
+    Gpu(GpuViewerSurface),
+    #[cfg(feature = "software-renderer")]
+    Software(SoftwareSurface)
+}
+
+impl Renderer {
+    // the software renderer is used when it is forced or when there is no GPU adapter to draw to the window
+    #[cfg(feature = "software-renderer")]
+    pub fn new(window: &Window, frame_width: u32, frame_height: u32, software: bool) -> Result<Self, String> {
+        if !software {
+            match GpuRenderer::new(window, frame_width, frame_height) {
+                Ok(renderer) => return Ok(Renderer::Gpu(renderer)),
+                Err(error) => warn!("{}, falling back to the software renderer", error)
+            }
+        }
+        info!("Using the software renderer");
+        SoftwareRenderer::new(window, frame_width, frame_height).map(Renderer::Software)
+    }
+
+    #[cfg(not(feature = "software-renderer"))]
+    pub fn new(window: &Window, frame_width: u32, frame_height: u32, software: bool) -> Result<Self, String> {
+        if software {
+            return Err("MU was built without the software-renderer feature".to_string());
+        }
+        GpuRenderer::new(window, frame_width, frame_height).map(Renderer::Gpu)
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        match self {
+            Renderer::Gpu(renderer) => renderer.resize(size),
+            #[cfg(feature = "software-renderer")]
+            Renderer::Software(renderer) => renderer.resize(size)
+        }
+    }
+
+    // the frame size changes when the NTSC filter is toggled
+    pub fn set_frame_size(&mut self, frame_width: u32, frame_height: u32) {
+        match self {
+            Renderer::Gpu(renderer) => renderer.set_frame_size(frame_width, frame_height),
+            #[cfg(feature = "software-renderer")]
+            Renderer::Software(renderer) => renderer.set_frame_size(frame_width, frame_height)
+        }
+    }
+
+    pub fn set_preset(&mut self, preset: Preset) {
+        match self {
+            Renderer::Gpu(renderer) => renderer.set_preset(preset),
+            #[cfg(feature = "software-renderer")]
+            Renderer::Software(renderer) => renderer.set_preset(preset)
+        }
+    }
+
+    pub fn set_display_settings(&mut self, display_settings: DisplaySettings) {
+        match self {
+            Renderer::Gpu(renderer) => renderer.set_display_settings(display_settings),
+            #[cfg(feature = "software-renderer")]
+            Renderer::Software(renderer) => renderer.set_display_settings(display_settings)
+        }
+    }
+
+    pub fn draw(&mut self, frame_buffer: &[u8]) {
+        match self {
+            Renderer::Gpu(renderer) => renderer.draw(frame_buffer),
+            #[cfg(feature = "software-renderer")]
+            Renderer::Software(renderer) => renderer.draw(frame_buffer)
+        }
+    }
+
+    pub fn create_viewer_surface(&self, window: &Window, image_width: u32, image_height: u32) -> Result<ViewerSurface, String> {
+        match self {
+            Renderer::Gpu(renderer) => Ok(ViewerSurface::Gpu(renderer.create_viewer_surface(window, image_width, image_height))),
+            #[cfg(feature = "software-renderer")]
+            Renderer::Software(_) => SoftwareSurface::new(window).map(ViewerSurface::Software)
+        }
+    }
+
+    pub fn resize_viewer_surface(&self, viewer_surface: &mut ViewerSurface, size: PhysicalSize<u32>) {
+        match (self, viewer_surface) {
+            (Renderer::Gpu(renderer), ViewerSurface::Gpu(viewer_surface)) => renderer.resize_viewer_surface(viewer_surface, size),
+            #[cfg(feature = "software-renderer")]
+            (_, ViewerSurface::Software(viewer_surface)) => viewer_surface.resize(size),
+            #[cfg(feature = "software-renderer")]
+            _ => unreachable!()
+        }
+    }
+
+    // the GPU renderer knows the size of the image from its texture
+    #[cfg_attr(not(feature = "software-renderer"), allow(unused_variables))]
+    pub fn draw_viewer_surface(&self, viewer_surface: &mut ViewerSurface, image: &[u8], image_width: usize, image_height: usize) {
+        match (self, viewer_surface) {
+            (Renderer::Gpu(renderer), ViewerSurface::Gpu(viewer_surface)) => renderer.draw_viewer_surface(viewer_surface, image),
+            #[cfg(feature = "software-renderer")]
+            (_, ViewerSurface::Software(viewer_surface)) => viewer_surface.draw_image(image, image_width, image_height),
+            #[cfg(feature = "software-renderer")]
+            _ => unreachable!()
+        }
+    }
+}
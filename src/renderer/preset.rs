@@ -0,0 +1,105 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    str::FromStr
+};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Shader {
+    Passthrough,
+    Scanlines,
+    ApertureMask,
+    SharpBilinear
+}
+
+impl FromStr for Shader {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        match name {
+            "passthrough" => Ok(Shader::Passthrough),
+            "scanlines" => Ok(Shader::Scanlines),
+            "aperture-mask" => Ok(Shader::ApertureMask),
+            "sharp-bilinear" => Ok(Shader::SharpBilinear),
+            _ => Err(format!("Unknown shader: {}", name))
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Scale {
+    Source(f32), // multiple of the size of the pass input
+    Viewport
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Pass {
+    pub shader: Shader,
+    pub scale: Scale,
+    pub linear_filtering: bool
+}
+
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub name: String,
+    pub passes: Vec<Pass>
+}
+
+pub const BUILTIN_PRESETS: [&str; 4] = ["none", "scanlines", "crt", "sharp-bilinear"];
+
+const BUILTIN_PRESET_SOURCES: [&str; 4] = [
+    "passthrough viewport nearest",
+    "scanlines viewport nearest",
+    "scanlines 4 nearest\naperture-mask viewport linear",
+    "sharp-bilinear viewport linear"
+];
+
+impl Preset {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let index = BUILTIN_PRESETS.iter().position(|preset| *preset == name)?;
+        Some(Self::parse(name, BUILTIN_PRESET_SOURCES[index]).unwrap())
+    }
+
+    // a builtin preset name or the path of a preset file
+    pub fn load(name: &str) -> Result<Self> {
+        if let Some(preset) = Self::builtin(name) {
+            return Ok(preset);
+        }
+        let source = fs::read_to_string(name)?;
+        Self::parse(name, &source).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+
+    // one pass per line: <shader> [<scale>|viewport] [nearest|linear], the last pass always draws to the window
+    fn parse(name: &str, source: &str) -> std::result::Result<Self, String> {
+        let mut passes = Vec::new();
+        for line in source.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let shader = words.next().unwrap().parse()?;
+            let scale = match words.next() {
+                None | Some("viewport") => Scale::Viewport,
+                Some(scale) => Scale::Source(scale.parse().map_err(|_| format!("Wrong scale: {}", scale))?)
+            };
+            let linear_filtering = match words.next() {
+                None | Some("nearest") => false,
+                Some("linear") => true,
+                Some(filter) => return Err(format!("Wrong filter: {}", filter))
+            };
+            passes.push(Pass {
+                shader,
+                scale,
+                linear_filtering
+            });
+        }
+        if passes.is_empty() {
+            return Err("Empty preset".to_string());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            passes
+        })
+    }
+}
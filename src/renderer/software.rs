@@ -0,0 +1,321 @@
+use std::f32::consts::PI;
+
+use winit::{
+    window::Window,
+    dpi::PhysicalSize
+};
+
+use super::*;
+use super::blitter::*;
+
+const PIXEL_SIZE: usize = 4;
+
+// same as the fragment shaders
+const SCANLINE_DARKNESS: f32 = 0.4;
+const MASK_DARKNESS: f32 = 0.3;
+const BRIGHTNESS_BOOST: f32 = 1.2;
+
+// enough linear values to encode every 8-bit sRGB color back to itself
+const ENCODING_TABLE_SIZE: usize = 0x10000;
+
+type Color = [f32; 3];
+
+// a window drawn on the CPU, for the main window and the debug viewers of the software renderer
+pub struct SoftwareSurface {
+    // the function table of Xlib is large
+    blitter: Box<Blitter>,
+    buffer: Vec<u32>,
+    size: PhysicalSize<u32>
+}
+
+impl SoftwareSurface {
+    pub fn new(window: &Window) -> Result<Self, String> {
+        Ok(Self {
+            blitter: Box::new(Blitter::new(window)?),
+            buffer: Vec::new(),
+            size: window.inner_size()
+        })
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.size = size;
+    }
+
+    // 0RGB pixels, nothing is drawn while the window is minimized
+    fn draw<F: FnOnce(&mut [u32], usize, usize)>(&mut self, draw: F) {
+        let (width, height) = (self.size.width as usize, self.size.height as usize);
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.buffer.resize(width * height, 0);
+        draw(&mut self.buffer, width, height);
+        self.blitter.blit(&mut self.buffer, width, height);
+    }
+
+    // the image is stretched to the window with nearest neighbour filtering, like the viewer surfaces of the GPU renderer
+    pub fn draw_image(&mut self, image: &[u8], image_width: usize, image_height: usize) {
+        self.draw(|buffer, width, height| {
+            for (row, line) in buffer.chunks_mut(width).enumerate() {
+                let source_row = row * image_height / height;
+                for (column, pixel) in line.iter_mut().enumerate() {
+                    let source = (source_row * image_width + column * image_width / width) * PIXEL_SIZE;
+                    *pixel = (image[source] as u32) << 16 | (image[source + 1] as u32) << 8 | image[source + 2] as u32;
+                }
+            }
+        });
+    }
+}
+
+// RGB image in linear light, in which the GPU samples and filters the sRGB textures
+struct LinearImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>
+}
+
+impl LinearImage {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 3]; width * height]
+        }
+    }
+
+    // clamped to the edges, like the samplers of the GPU renderer
+    fn get_pixel(&self, x: isize, y: isize) -> Color {
+        let x = x.max(0).min(self.width as isize - 1) as usize;
+        let y = y.max(0).min(self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    fn sample(&self, u: f32, v: f32, linear_filtering: bool) -> Color {
+        if linear_filtering {
+            let x = u * self.width as f32 - 0.5;
+            let y = v * self.height as f32 - 0.5;
+            let (left, top) = (x.floor() as isize, y.floor() as isize);
+            let (x_weight, y_weight) = (x - x.floor(), y - y.floor());
+            let top_color = mix(self.get_pixel(left, top), self.get_pixel(left + 1, top), x_weight);
+            let bottom_color = mix(self.get_pixel(left, top + 1), self.get_pixel(left + 1, top + 1), x_weight);
+            mix(top_color, bottom_color, y_weight)
+        } else {
+            self.get_pixel((u * self.width as f32).floor() as isize, (v * self.height as f32).floor() as isize)
+        }
+    }
+}
+
+fn mix(first: Color, second: Color, weight: f32) -> Color {
+    [
+        first[0] + (second[0] - first[0]) * weight,
+        first[1] + (second[1] - first[1]) * weight,
+        first[2] + (second[2] - first[2]) * weight
+    ]
+}
+
+struct SoftwarePass {
+    pass: Pass,
+    // the part of the source to draw, only the first pass crops the overscan
+    texture_rectangle: [f32; 4],
+    // the last pass draws to the window instead
+    target: Option<LinearImage>
+}
+
+impl SoftwarePass {
+    // runs the fragment shader on the first columns of a row of the output, x_offset being the position of the output in the window
+    fn shade_row<F: FnMut(usize, Color)>(&self, source: &LinearImage, output_size: (f32, f32), row: usize, columns: usize, x_offset: usize, mut write: F) {
+        let [texture_x, texture_y, texture_width, texture_height] = self.texture_rectangle;
+        let v = texture_y + (row as f32 + 0.5) / output_size.1 * texture_height;
+        // scale from the visible part of the source to the output
+        let scale = (output_size.0 / (source.width as f32 * texture_width), output_size.1 / (source.height as f32 * texture_height));
+        for column in 0..columns {
+            let u = texture_x + (column as f32 + 0.5) / output_size.0 * texture_width;
+            write(column, shade(&self.pass, source, u, v, x_offset + column, scale));
+        }
+    }
+}
+
+// the shader passes of the GPU renderer, run on the CPU for computers without a GPU adapter
+pub struct SoftwareRenderer {
+    surface: SoftwareSurface,
+    frame: LinearImage,
+    preset: Preset,
+    display_settings: DisplaySettings,
+    viewport: [f32; 4],
+    passes: Vec<SoftwarePass>,
+    decoding_table: Vec<f32>,
+    encoding_table: Vec<u8>
+}
+
+impl SoftwareRenderer {
+    pub fn new(window: &Window, frame_width: u32, frame_height: u32) -> Result<Self, String> {
+        let decoding_table = (0..=0xff).map(|value| srgb_to_linear(value as f32 / 255.0)).collect();
+        let encoding_table = (0..ENCODING_TABLE_SIZE).map(|value| (linear_to_srgb(value as f32 / (ENCODING_TABLE_SIZE - 1) as f32) * 255.0).round() as u8).collect();
+        let mut renderer = Self {
+            surface: SoftwareSurface::new(window)?,
+            frame: LinearImage::new(frame_width as _, frame_height as _),
+            preset: Preset::builtin("none").unwrap(),
+            display_settings: DisplaySettings::default(),
+            viewport: [0.0; 4],
+            passes: Vec::new(),
+            decoding_table,
+            encoding_table
+        };
+        renderer.create_passes();
+        Ok(renderer)
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.surface.resize(size);
+        self.create_passes();
+    }
+
+    pub fn set_frame_size(&mut self, frame_width: u32, frame_height: u32) {
+        self.frame = LinearImage::new(frame_width as _, frame_height as _);
+        self.create_passes();
+    }
+
+    pub fn set_preset(&mut self, preset: Preset) {
+        self.preset = preset;
+        self.create_passes();
+    }
+
+    pub fn set_display_settings(&mut self, display_settings: DisplaySettings) {
+        self.display_settings = display_settings;
+        self.create_passes();
+    }
+
+    // same sizes as the render targets of the GPU renderer
+    fn create_passes(&mut self) {
+        self.viewport = self.display_settings.get_viewport(self.surface.size.width, self.surface.size.height);
+        let viewport_size = (self.viewport[2], self.viewport[3]);
+        let mut source_size = (self.frame.width as f32, self.frame.height as f32);
+        self.passes.clear();
+        for (number, pass) in self.preset.passes.iter().enumerate() {
+            let is_last = number == self.preset.passes.len() - 1;
+            let texture_rectangle = if number == 0 {
+                self.display_settings.get_texture_rectangle()
+            } else {
+                [0.0, 0.0, 1.0, 1.0]
+            };
+            let output_size = match pass.scale {
+                Scale::Source(scale) if !is_last => {
+                    let width = source_size.0 * texture_rectangle[2] * scale;
+                    let height = source_size.1 * texture_rectangle[3] * scale;
+                    (width.round().max(1.0), height.round().max(1.0))
+                },
+                _ => viewport_size
+            };
+            let target = if is_last {
+                None
+            } else {
+                source_size = output_size;
+                Some(LinearImage::new(output_size.0 as _, output_size.1 as _))
+            };
+            self.passes.push(SoftwarePass {
+                pass: *pass,
+                texture_rectangle,
+                target
+            });
+        }
+    }
+
+    pub fn draw(&mut self, frame_buffer: &[u8]) {
+        // the window is minimized
+        if self.viewport[2] < 1.0 || self.viewport[3] < 1.0 {
+            return;
+        }
+        for (color, pixel) in self.frame.pixels.iter_mut().zip(frame_buffer.chunks(PIXEL_SIZE)) {
+            *color = [self.decoding_table[pixel[0] as usize], self.decoding_table[pixel[1] as usize], self.decoding_table[pixel[2] as usize]];
+        }
+
+        // the output of each pass is the input of the next one
+        let last_number = self.passes.len() - 1;
+        for number in 0..last_number {
+            let (previous_passes, passes) = self.passes.split_at_mut(number);
+            let source = previous_passes.last().and_then(|pass| pass.target.as_ref()).unwrap_or(&self.frame);
+            let mut target = passes[0].target.take().unwrap();
+            let output_size = (target.width as f32, target.height as f32);
+            let width = target.width;
+            for (row, line) in target.pixels.chunks_mut(width).enumerate() {
+                passes[0].shade_row(source, output_size, row, width, 0, |column, color| line[column] = color);
+            }
+            passes[0].target = Some(target);
+        }
+
+        let source = self.passes[..last_number].last().and_then(|pass| pass.target.as_ref()).unwrap_or(&self.frame);
+        let last_pass = &self.passes[last_number];
+        let viewport = self.viewport;
+        let encoding_table = &self.encoding_table;
+        let encode = |value: f32| encoding_table[(value.clamp(0.0, 1.0) * (ENCODING_TABLE_SIZE - 1) as f32).round() as usize] as u32;
+        self.surface.draw(|buffer, width, height| {
+            // black bars around the image
+            buffer.fill(0);
+            let [x, y, viewport_width, viewport_height] = viewport;
+            let (x, y) = (x.max(0.0) as usize, y.max(0.0) as usize);
+            let columns = (viewport_width as usize).min(width.saturating_sub(x));
+            for row in 0..(viewport_height as usize).min(height.saturating_sub(y)) {
+                let line = &mut buffer[(y + row) * width + x..(y + row) * width + x + columns];
+                last_pass.shade_row(source, (viewport_width, viewport_height), row, columns, x, |column, color| {
+                    line[column] = encode(color[0]) << 16 | encode(color[1]) << 8 | encode(color[2]);
+                });
+            }
+        });
+    }
+}
+
+
+// the fragment shaders of shaders/*.frag
+fn shade(pass: &Pass, source: &LinearImage, u: f32, v: f32, fragment_x: usize, scale: (f32, f32)) -> Color {
+    let sample = |u, v| source.sample(u, v, pass.linear_filtering);
+    match pass.shader {
+        Shader::Passthrough => sample(u, v),
+        Shader::Scanlines => {
+            // brightest in the middle of each source line, darkest between lines
+            let line = (v * source.height as f32).fract();
+            let intensity = 1.0 - SCANLINE_DARKNESS * (1.0 - (line * PI).sin());
+            let [red, green, blue] = sample(u, v);
+            [red * intensity, green * intensity, blue * intensity]
+        },
+        Shader::ApertureMask => {
+            // vertical red, green and blue stripes of an aperture grille, one output pixel wide each
+            let mut color = sample(u, v);
+            for (channel, value) in color.iter_mut().enumerate() {
+                let mask = if channel == fragment_x % 3 {
+                    1.0
+                } else {
+                    1.0 - MASK_DARKNESS
+                };
+                *value = (*value * mask * BRIGHTNESS_BOOST).min(1.0);
+            }
+            color
+        },
+        Shader::SharpBilinear => {
+            // nearest neighbour scaling by the largest integer factor, then bilinear interpolation on the edges of the texels only
+            let sharpen = |coordinate: f32, size: usize, scale: f32| {
+                let texel = coordinate * size as f32;
+                let scale = scale.floor().max(1.0);
+                let region_range = 0.5 - 0.5 / scale;
+                let center_distance = texel.fract() - 0.5;
+                let offset = (center_distance - center_distance.max(-region_range).min(region_range)) * scale + 0.5;
+                (texel.floor() + offset) / size as f32
+            };
+            sample(sharpen(u, source.width, scale.0), sharpen(v, source.height, scale.1))
+        }
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -1,3 +1,6 @@
+use palette::*;
+use ntsc::*;
+
 pub const FRAME_WIDTH: usize = 256;
 pub const FRAME_HEIGHT: usize = 240;
 
@@ -5,6 +8,9 @@ const PIXEL_SIZE: usize = 4;
 
 pub struct Screen {
     frame_buffer: Vec<u8>,
+    pixel_indices: Vec<u16>,
+    palette: Palette,
+    ntsc_filter: Option<NtscFilter>,
     draw_requested: bool
 }
 
@@ -12,6 +18,9 @@ impl Screen {
     pub fn new() -> Self {
         Self {
             frame_buffer: vec![0; FRAME_WIDTH * FRAME_HEIGHT * PIXEL_SIZE],
+            pixel_indices: vec![0; FRAME_WIDTH * FRAME_HEIGHT],
+            palette: Palette::new(),
+            ntsc_filter: None,
             draw_requested: false
         }
     }
@@ -20,26 +29,27 @@ impl Screen {
         &self.frame_buffer
     }
 
-    pub fn set_pixel(&mut self, row: usize, column: usize, color: usize) {
-        const PIXELS: [[u8; PIXEL_SIZE]; 0x40] = [[0x54, 0x54, 0x54, 0xff], [0x00, 0x1e, 0x74, 0xff], [0x08, 0x10, 0x90, 0xff], [0x30, 0x00, 0x88, 0xff], 
-                                                  [0x44, 0x00, 0x64, 0xff], [0x5c, 0x00, 0x30, 0xff], [0x54, 0x04, 0x00, 0xff], [0x3c, 0x18, 0x00, 0xff],
-                                                  [0x20, 0x2a, 0x00, 0xff], [0x08, 0x3a, 0x00, 0xff], [0x00, 0x40, 0x00, 0xff], [0x00, 0x3c, 0x00, 0xff], 
-                                                  [0x00, 0x32, 0x3c, 0xff], [0x00, 0x00, 0x00, 0xff], [0x00, 0x00, 0x00, 0xff], [0x00, 0x00, 0x00, 0xff],
-                                                  [0x98, 0x96, 0x98, 0xff], [0x08, 0x4c, 0xc4, 0xff], [0x30, 0x32, 0xec, 0xff], [0x5c, 0x1e, 0xe4, 0xff], 
-                                                  [0x88, 0x14, 0xb0, 0xff], [0xa0, 0x14, 0x64, 0xff], [0x98, 0x22, 0x20, 0xff], [0x78, 0x3c, 0x00, 0xff],
-                                                  [0x54, 0x5a, 0x00, 0xff], [0x28, 0x72, 0x00, 0xff], [0x08, 0x7c, 0x00, 0xff], [0x00, 0x76, 0x28, 0xff], 
-                                                  [0x00, 0x66, 0x78, 0xff], [0x00, 0x00, 0x00, 0xff], [0x00, 0x00, 0x00, 0xff], [0x00, 0x00, 0x00, 0xff],
-                                                  [0xec, 0xee, 0xec, 0xff], [0x4c, 0x9a, 0xec, 0xff], [0x78, 0x7c, 0xec, 0xff], [0xb0, 0x62, 0xec, 0xff], 
-                                                  [0xe4, 0x54, 0xec, 0xff], [0xec, 0x58, 0xb4, 0xff], [0xec, 0x6a, 0x64, 0xff], [0xd4, 0x88, 0x20, 0xff],
-                                                  [0xa0, 0xaa, 0x00, 0xff], [0x74, 0xc4, 0x00, 0xff], [0x4c, 0xd0, 0x20, 0xff], [0x38, 0xcc, 0x6c, 0xff], 
-                                                  [0x38, 0xb4, 0xcc, 0xff], [0x3c, 0x3c, 0x3c, 0xff], [0x00, 0x00, 0x00, 0xff], [0x00, 0x00, 0x00, 0xff],
-                                                  [0xec, 0xee, 0xec, 0xff], [0xa8, 0xcc, 0xec, 0xff], [0xbc, 0xbc, 0xec, 0xff], [0xd4, 0xb2, 0xec, 0xff], 
-                                                  [0xec, 0xae, 0xec, 0xff], [0xec, 0xae, 0xd4, 0xff], [0xec, 0xb4, 0xb0, 0xff], [0xe4, 0xc4, 0x90, 0xff],
-                                                  [0xcc, 0xd2, 0x78, 0xff], [0xb4, 0xde, 0x78, 0xff], [0xa8, 0xe2, 0x90, 0xff], [0x98, 0xe2, 0xb4, 0xff], 
-                                                  [0xa0, 0xd6, 0xe4, 0xff], [0xa0, 0xa2, 0xa0, 0xff], [0x00, 0x00, 0x00, 0xff], [0x00, 0x00, 0x00, 0xff]];
-        
-        let offset = (row * FRAME_WIDTH + column) * PIXEL_SIZE;
-        self.frame_buffer[offset..offset + PIXEL_SIZE].copy_from_slice(&PIXELS[color]);
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    // the NTSC filter replaces the palette lookup and makes the frame wider
+    pub fn set_ntsc_filter(&mut self, ntsc_filter: Option<NtscFilter>) {
+        self.ntsc_filter = ntsc_filter;
+        self.frame_buffer = vec![0; self.get_frame_width() * FRAME_HEIGHT * PIXEL_SIZE];
+    }
+
+    pub fn get_frame_width(&self) -> usize {
+        if self.ntsc_filter.is_some() {
+            NTSC_FRAME_WIDTH
+        } else {
+            FRAME_WIDTH
+        }
+    }
+
+    // index is a 9-bit value: the 3 emphasis bits of PPUMASK followed by the 6-bit color
+    pub fn set_pixel(&mut self, row: usize, column: usize, index: u16) {
+        self.pixel_indices[row * FRAME_WIDTH + column] = index;
     }
 
     pub fn is_draw_requested(&self) -> bool {
@@ -47,6 +57,13 @@ impl Screen {
     }
 
     pub fn request_draw(&mut self) {
+        if let Some(ref mut ntsc_filter) = self.ntsc_filter {
+            ntsc_filter.apply(&self.pixel_indices, &mut self.frame_buffer);
+        } else {
+            for (pixel, index) in self.frame_buffer.chunks_mut(PIXEL_SIZE).zip(&self.pixel_indices) {
+                pixel.copy_from_slice(&self.palette.get_rgba(*index));
+            }
+        }
         self.draw_requested = true;
     }
 
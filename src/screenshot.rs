@@ -0,0 +1,57 @@
+use std::{
+	fs,
+	io::Result,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+use screen::*;
+use png::*;
+
+// "<ROM name>_<UTC date and time>.<extension>" in the given directory, which is created if needed
+pub fn get_timestamped_filename(directory: &str, rom_filename: &str, extension: &str) -> Result<PathBuf> {
+	fs::create_dir_all(directory)?;
+	let rom_name = Path::new(rom_filename).file_stem().map_or("capture".into(), |stem| stem.to_string_lossy());
+	let name = format!("{}_{}", rom_name, get_timestamp());
+	let mut filename = Path::new(directory).join(format!("{}.{}", name, extension));
+	// several files in the same second
+	let mut number = 2;
+	while filename.exists() {
+		filename = Path::new(directory).join(format!("{}_{}.{}", name, number, extension));
+		number += 1;
+	}
+	Ok(filename)
+}
+
+// saves the frame as the emulator outputs it, before any scaling
+pub fn save_screenshot(screen: &Screen, directory: &str, rom_filename: &str) -> Result<PathBuf> {
+	let filename = get_timestamped_filename(directory, rom_filename, "png")?;
+	save_png(&filename.to_string_lossy(), screen.get_frame_width(), FRAME_HEIGHT, screen.get_frame_buffer())?;
+	Ok(filename)
+}
+
+fn get_timestamp() -> String {
+	let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+	let (year, month, day) = get_date(seconds / 86400);
+	let time = seconds % 86400;
+	format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+// converts days since 1970-01-01 to a Gregorian calendar date
+fn get_date(days: u64) -> (u64, u64, u64) {
+	// count from 0000-03-01 so that leap days end the years, in eras of 400 years
+	let days = days + 719_468;
+	let era = days / 146_097;
+	let day_of_era = days % 146_097;
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	};
+	let year = era * 400 + year_of_era + (month <= 2) as u64;
+	(year, month, day)
+}
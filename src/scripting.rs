@@ -0,0 +1,489 @@
+use std::{
+	cell::RefCell,
+	fs::{self, OpenOptions},
+	io::Write,
+	path::Path,
+	ptr,
+	rc::Rc
+};
+
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, Map, AST};
+
+use emulator::*;
+use cpu::*;
+use joypad::*;
+use screen::*;
+use accesses::*;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+// 3x5 glyphs of the characters from ' ' to '_', one bit per pixel from the top left
+const FONT: [u16; 64] = [
+	0b000_000_000_000_000, 0b010_010_010_000_010, 0b101_101_000_000_000, 0b101_111_101_111_101,
+	0b011_110_010_011_110, 0b101_001_010_100_101, 0b010_101_010_101_011, 0b010_010_000_000_000,
+	0b001_010_010_010_001, 0b100_010_010_010_100, 0b000_101_010_101_000, 0b000_010_111_010_000,
+	0b000_000_000_010_100, 0b000_000_111_000_000, 0b000_000_000_000_010, 0b001_001_010_100_100,
+	0b111_101_101_101_111, 0b010_110_010_010_111, 0b111_001_111_100_111, 0b111_001_111_001_111,
+	0b101_101_111_001_001, 0b111_100_111_001_111, 0b111_100_111_101_111, 0b111_001_001_001_001,
+	0b111_101_111_101_111, 0b111_101_111_001_111, 0b000_010_000_010_000, 0b000_010_000_010_100,
+	0b001_010_100_010_001, 0b000_111_000_111_000, 0b100_010_001_010_100, 0b111_001_011_000_010,
+	0b010_101_111_100_011, 0b111_101_111_101_101, 0b110_101_110_101_110, 0b111_100_100_100_111,
+	0b110_101_101_101_110, 0b111_100_111_100_111, 0b111_100_111_100_100, 0b111_100_101_101_111,
+	0b101_101_111_101_101, 0b111_010_010_010_111, 0b001_001_001_101_111, 0b101_101_110_101_101,
+	0b100_100_100_100_111, 0b101_111_111_101_101, 0b110_101_101_101_101, 0b010_101_101_101_010,
+	0b111_101_111_100_100, 0b010_101_101_110_011, 0b110_101_110_101_101, 0b011_100_010_001_110,
+	0b111_010_010_010_010, 0b101_101_101_101_111, 0b101_101_101_101_010, 0b101_101_111_111_101,
+	0b101_101_010_101_101, 0b101_101_010_010_010, 0b111_001_010_100_111, 0b011_010_010_010_011,
+	0b100_100_010_001_001, 0b110_010_010_010_110, 0b010_101_000_000_000, 0b000_000_000_000_111
+];
+const GLYPH_WIDTH: i64 = 3;
+const GLYPH_HEIGHT: i64 = 5;
+
+type ButtonAction = fn(&mut Joypad);
+
+// the buttons in the order of the joypad register
+const BUTTONS: [(&str, ButtonAction, ButtonAction); 8] = [
+	("a", Joypad::press_a_button, Joypad::release_a_button),
+	("b", Joypad::press_b_button, Joypad::release_b_button),
+	("select", Joypad::press_select_button, Joypad::release_select_button),
+	("start", Joypad::press_start_button, Joypad::release_start_button),
+	("up", Joypad::press_up_button, Joypad::release_up_button),
+	("down", Joypad::press_down_button, Joypad::release_down_button),
+	("left", Joypad::press_left_button, Joypad::release_left_button),
+	("right", Joypad::press_right_button, Joypad::release_right_button)
+];
+
+// a function of the script called on the accesses to an address range, or at the end of each frame
+#[derive(Clone)]
+struct Callback {
+	start: u16,
+	end: u16,
+	function: FnPtr,
+	// a callback raising an error isn't called anymore
+	failed: bool
+}
+
+impl Callback {
+	fn new(start: u16, end: u16, function: FnPtr) -> Self {
+		Self {
+			start,
+			end,
+			function,
+			failed: false
+		}
+	}
+}
+
+#[derive(Copy, Clone)]
+enum CallbackKind {
+	Frame,
+	Read,
+	Write,
+	Execute
+}
+
+#[derive(Default)]
+struct Callbacks {
+	frame: Vec<Callback>,
+	read: Vec<Callback>,
+	write: Vec<Callback>,
+	execute: Vec<Callback>
+}
+
+impl Callbacks {
+	fn get_mut(&mut self, kind: CallbackKind) -> &mut Vec<Callback> {
+		match kind {
+			CallbackKind::Frame => &mut self.frame,
+			CallbackKind::Read => &mut self.read,
+			CallbackKind::Write => &mut self.write,
+			CallbackKind::Execute => &mut self.execute
+		}
+	}
+}
+
+// drawn over the frame, in frame pixels and 0xRRGGBB colors
+enum Shape {
+	Text {
+		x: i64,
+		y: i64,
+		text: String,
+		color: [u8; 3]
+	},
+	Box {
+		x: i64,
+		y: i64,
+		width: i64,
+		height: i64,
+		color: [u8; 3],
+		filled: bool
+	}
+}
+
+// what the functions given to the script share with it
+struct ScriptState {
+	// only set while the script runs, the emulator not being used otherwise meanwhile
+	emulator: *mut Emulator,
+	callbacks: Callbacks,
+	// the buttons held by the script, in the order of the joypad register
+	buttons: u8,
+	// the shapes drawn during the current frame
+	shapes: Vec<Shape>
+}
+
+type SharedState = Rc<RefCell<ScriptState>>;
+
+fn with_emulator<T, F: FnOnce(&mut Emulator) -> T>(state: &SharedState, function: F) -> ScriptResult<T> {
+	let emulator = state.borrow().emulator;
+	if emulator.is_null() {
+		return Err("The emulator is only available while the script runs".into());
+	}
+	// the pointer comes from the emulator the script was called with, which waits for the script to return
+	Ok(function(unsafe { &mut *emulator }))
+}
+
+fn to_address(address: i64) -> ScriptResult<u16> {
+	if (0..=0xffff).contains(&address) {
+		Ok(address as u16)
+	} else {
+		Err(format!("Wrong address: {}", address).into())
+	}
+}
+
+fn to_byte(value: i64) -> ScriptResult<u8> {
+	if (0..=0xff).contains(&value) {
+		Ok(value as u8)
+	} else {
+		Err(format!("Wrong byte: {}", value).into())
+	}
+}
+
+fn to_color(color: i64) -> [u8; 3] {
+	[(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+// the index of the button in the joypad register
+fn get_button(name: &str) -> ScriptResult<usize> {
+	match BUTTONS.iter().position(|(button_name, _, _)| button_name.eq_ignore_ascii_case(name)) {
+		Some(index) => Ok(index),
+		None => Err(format!("Wrong button: {}", name).into())
+	}
+}
+
+// a Rhai script driving the emulator, calling back its functions on memory accesses and at the end of the frames
+pub struct Script {
+	engine: Engine,
+	ast: AST,
+	state: SharedState,
+	// the first step of a frame clears the shapes of the frame before and presses the held buttons
+	frame_started: bool
+}
+
+impl Script {
+	// compiles the script and runs its top level, which registers the callbacks
+	pub fn load<P: AsRef<Path>>(filename: P, emulator: &mut Emulator) -> Result<Self, String> {
+		let state = Rc::new(RefCell::new(ScriptState {
+			emulator: ptr::null_mut(),
+			callbacks: Callbacks::default(),
+			buttons: 0,
+			shapes: Vec::new()
+		}));
+		let engine = Self::create_engine(&state);
+		let ast = engine.compile_file(filename.as_ref().to_path_buf()).map_err(|error| error.to_string())?;
+		let script = Self {
+			engine,
+			ast,
+			state,
+			frame_started: false
+		};
+		script.with_emulator(emulator, |script| script.engine.run_ast(&script.ast)).map_err(|error| error.to_string())?;
+		Ok(script)
+	}
+
+	fn create_engine(state: &SharedState) -> Engine {
+		let mut engine = Engine::new();
+		engine.on_print(|text| info!("{}", text));
+		engine.on_debug(|text, _, position| debug!("{} {}", position, text));
+		engine.register_type_with_name::<Rc<SaveState>>("SaveState");
+
+		// memory and registers
+		let shared = state.clone();
+		engine.register_fn("read", move |address: i64| -> ScriptResult<i64> {
+			let address = to_address(address)?;
+			with_emulator(&shared, |emulator| Cpu::read8_debug(emulator, address) as i64)
+		});
+		let shared = state.clone();
+		engine.register_fn("write", move |address: i64, value: i64| -> ScriptResult<()> {
+			let (address, value) = (to_address(address)?, to_byte(value)?);
+			with_emulator(&shared, |emulator| Cpu::write_debug(emulator, address, value))
+		});
+		let shared = state.clone();
+		engine.register_fn("registers", move || -> ScriptResult<Map> {
+			let registers = with_emulator(&shared, |emulator| emulator.cpu.get_registers())?;
+			let mut map = Map::new();
+			for (name, value) in [("a", registers.a as i64), ("x", registers.x as i64), ("y", registers.y as i64), ("pc", registers.pc as i64), ("s", registers.s as i64), ("p", registers.p as i64)] {
+				map.insert(name.into(), Dynamic::from(value));
+			}
+			Ok(map)
+		});
+		let shared = state.clone();
+		engine.register_fn("frame", move || -> ScriptResult<i64> {
+			with_emulator(&shared, |emulator| emulator.ppu.get_frame_counter() as i64)
+		});
+
+		// the held buttons are pressed again at the start of each frame, the frontend releasing them otherwise
+		let shared = state.clone();
+		engine.register_fn("press", move |name: &str| -> ScriptResult<()> {
+			let index = get_button(name)?;
+			shared.borrow_mut().buttons |= 1 << index;
+			with_emulator(&shared, |emulator| (BUTTONS[index].1)(&mut emulator.joypad))
+		});
+		let shared = state.clone();
+		engine.register_fn("release", move |name: &str| -> ScriptResult<()> {
+			let index = get_button(name)?;
+			shared.borrow_mut().buttons &= !(1 << index);
+			with_emulator(&shared, |emulator| (BUTTONS[index].2)(&mut emulator.joypad))
+		});
+
+		// save states, kept in memory
+		let shared = state.clone();
+		engine.register_fn("save_state", move || -> ScriptResult<Rc<SaveState>> {
+			with_emulator(&shared, |emulator| Rc::new(emulator.save_state()))
+		});
+		let shared = state.clone();
+		engine.register_fn("load_state", move |save_state: Rc<SaveState>| -> ScriptResult<()> {
+			with_emulator(&shared, |emulator| emulator.load_state(&save_state))
+		});
+
+		// files
+		engine.register_fn("write_file", |filename: &str, text: &str| -> ScriptResult<()> {
+			fs::write(filename, text).map_err(|error| format!("Couldn't write {}: {}", filename, error).into())
+		});
+		engine.register_fn("append_file", |filename: &str, text: &str| -> ScriptResult<()> {
+			OpenOptions::new().create(true).append(true).open(filename)
+				.and_then(|mut file| file.write_all(text.as_bytes()))
+				.map_err(|error| format!("Couldn't write {}: {}", filename, error).into())
+		});
+
+		// overlay
+		let shared = state.clone();
+		engine.register_fn("draw_text", move |x: i64, y: i64, text: &str, color: i64| {
+			shared.borrow_mut().shapes.push(Shape::Text {
+				x,
+				y,
+				text: text.to_string(),
+				color: to_color(color)
+			});
+		});
+		let shared = state.clone();
+		engine.register_fn("draw_box", move |x: i64, y: i64, width: i64, height: i64, color: i64| {
+			shared.borrow_mut().shapes.push(Shape::Box {
+				x,
+				y,
+				width,
+				height,
+				color: to_color(color),
+				filled: false
+			});
+		});
+		let shared = state.clone();
+		engine.register_fn("fill_box", move |x: i64, y: i64, width: i64, height: i64, color: i64| {
+			shared.borrow_mut().shapes.push(Shape::Box {
+				x,
+				y,
+				width,
+				height,
+				color: to_color(color),
+				filled: true
+			});
+		});
+
+		// callbacks, on an address or an address range
+		let shared = state.clone();
+		engine.register_fn("on_frame", move |function: FnPtr| {
+			shared.borrow_mut().callbacks.frame.push(Callback::new(0, 0xffff, function));
+		});
+		for (name, kind) in [("on_read", CallbackKind::Read), ("on_write", CallbackKind::Write), ("on_exec", CallbackKind::Execute)] {
+			let shared = state.clone();
+			engine.register_fn(name, move |address: i64, function: FnPtr| -> ScriptResult<()> {
+				let address = to_address(address)?;
+				shared.borrow_mut().callbacks.get_mut(kind).push(Callback::new(address, address, function));
+				Ok(())
+			});
+			let shared = state.clone();
+			engine.register_fn(name, move |start: i64, end: i64, function: FnPtr| -> ScriptResult<()> {
+				let (start, end) = (to_address(start)?, to_address(end)?);
+				shared.borrow_mut().callbacks.get_mut(kind).push(Callback::new(start, end, function));
+				Ok(())
+			});
+		}
+
+		engine
+	}
+
+	// presses the buttons held by the script, the others being left to the player
+	fn press_buttons(emulator: &mut Emulator, buttons: u8) {
+		for (index, (_, press, _)) in BUTTONS.iter().enumerate() {
+			if buttons & (1 << index) != 0 {
+				press(&mut emulator.joypad);
+			}
+		}
+	}
+
+	fn with_emulator<T, F: FnOnce(&Self) -> T>(&self, emulator: &mut Emulator, function: F) -> T {
+		self.state.borrow_mut().emulator = emulator;
+		let result = function(self);
+		self.state.borrow_mut().emulator = ptr::null_mut();
+		result
+	}
+
+	// calls the callbacks of the kind whose range has the address, with the given arguments
+	fn call_back(&self, emulator: &mut Emulator, kind: CallbackKind, address: u16, arguments: &[i64]) {
+		// the callbacks may register others, so they are called from a copy
+		let callbacks = self.state.borrow_mut().callbacks.get_mut(kind).clone();
+		for (index, callback) in callbacks.iter().enumerate() {
+			if callback.failed || address < callback.start || address > callback.end {
+				continue;
+			}
+			let arguments: Vec<Dynamic> = arguments.iter().map(|&argument| Dynamic::from(argument)).collect();
+			let result = self.with_emulator(emulator, |script| callback.function.call::<Dynamic>(&script.engine, &script.ast, arguments));
+			if let Err(error) = result {
+				error!("A script callback failed and won't be called anymore: {}", error);
+				self.state.borrow_mut().callbacks.get_mut(kind)[index].failed = true;
+			}
+		}
+	}
+
+	// runs an instruction, calling the execute callbacks before it and the read and write ones after it
+	pub fn step(&mut self, emulator: &mut Emulator) {
+		if !self.frame_started {
+			let buttons = {
+				let mut state = self.state.borrow_mut();
+				state.shapes.clear();
+				state.buttons
+			};
+			Self::press_buttons(emulator, buttons);
+			self.frame_started = true;
+		}
+
+		let (has_execute_callbacks, has_memory_callbacks) = {
+			let callbacks = &self.state.borrow().callbacks;
+			(!callbacks.execute.is_empty(), !callbacks.read.is_empty() || !callbacks.write.is_empty())
+		};
+		if has_memory_callbacks != emulator.script_accesses.is_enabled() {
+			emulator.script_accesses.set_enabled(has_memory_callbacks);
+		}
+		if has_execute_callbacks {
+			let address = Cpu::get_next_instruction_address(emulator);
+			self.call_back(emulator, CallbackKind::Execute, address, &[address as i64]);
+		}
+
+		emulator.step();
+
+		if has_memory_callbacks {
+			for access in emulator.script_accesses.take() {
+				let kind = match access.kind {
+					AccessKind::Read => CallbackKind::Read,
+					AccessKind::Write => CallbackKind::Write,
+					AccessKind::Execute => continue
+				};
+				self.call_back(emulator, kind, access.address, &[access.address as i64, access.value as i64]);
+			}
+		}
+	}
+
+	// runs until the PPU has a new frame to draw, then calls the frame callbacks
+	pub fn run_frame(&mut self, emulator: &mut Emulator) {
+		while !emulator.screen.is_draw_requested() {
+			self.step(emulator);
+		}
+		self.end_frame(emulator);
+	}
+
+	// calls the frame callbacks, for frames run by the debugger
+	pub fn end_frame(&mut self, emulator: &mut Emulator) {
+		let frame = emulator.ppu.get_frame_counter() as i64;
+		self.call_back(emulator, CallbackKind::Frame, 0, &[frame]);
+		self.frame_started = false;
+	}
+
+	// the frame buffer with the shapes drawn by the script over it, none without shapes
+	pub fn draw_overlay(&self, frame_buffer: &[u8], frame_width: usize) -> Option<Vec<u8>> {
+		let state = self.state.borrow();
+		if state.shapes.is_empty() {
+			return None;
+		}
+		let mut overlay = Overlay {
+			frame_buffer: frame_buffer.to_vec(),
+			frame_width
+		};
+		for shape in state.shapes.iter() {
+			match *shape {
+				Shape::Text {
+					x,
+					y,
+					ref text,
+					color
+				} => overlay.draw_text(x, y, text, color),
+				Shape::Box {
+					x,
+					y,
+					width,
+					height,
+					color,
+					filled
+				} => overlay.draw_box(x, y, width, height, color, filled)
+			}
+		}
+		Some(overlay.frame_buffer)
+	}
+}
+
+// a copy of the frame buffer, wider than the frame with the NTSC filter
+struct Overlay {
+	frame_buffer: Vec<u8>,
+	frame_width: usize
+}
+
+impl Overlay {
+	// the pixel of the frame, stretched over the columns of a wider frame buffer
+	fn set_pixel(&mut self, x: i64, y: i64, color: [u8; 3]) {
+		if x < 0 || x >= FRAME_WIDTH as i64 || y < 0 || y >= FRAME_HEIGHT as i64 {
+			return;
+		}
+		let (x, y) = (x as usize, y as usize);
+		for column in x * self.frame_width / FRAME_WIDTH..(x + 1) * self.frame_width / FRAME_WIDTH {
+			let offset = (y * self.frame_width + column) * 4;
+			self.frame_buffer[offset..offset + 3].copy_from_slice(&color);
+		}
+	}
+
+	// lowercase letters are drawn in uppercase, the characters the font doesn't have as '?'
+	fn draw_text(&mut self, x: i64, y: i64, text: &str, color: [u8; 3]) {
+		for (index, character) in text.chars().enumerate() {
+			let character = match character.to_ascii_uppercase() {
+				character @ ' ' ..= '_' => character,
+				_ => '?'
+			};
+			let glyph = FONT[character as usize - ' ' as usize];
+			let glyph_x = x + index as i64 * (GLYPH_WIDTH + 1);
+			for row in 0..GLYPH_HEIGHT {
+				for column in 0..GLYPH_WIDTH {
+					let bit = (GLYPH_HEIGHT - 1 - row) * GLYPH_WIDTH + GLYPH_WIDTH - 1 - column;
+					if (glyph >> bit) & 1 != 0 {
+						self.set_pixel(glyph_x + column, y + row, color);
+					}
+				}
+			}
+		}
+	}
+
+	fn draw_box(&mut self, x: i64, y: i64, width: i64, height: i64, color: [u8; 3], filled: bool) {
+		for row in y..y + height {
+			for column in x..x + width {
+				if filled || row == y || row == y + height - 1 || column == x || column == x + width - 1 {
+					self.set_pixel(column, row, color);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,18 @@
+#version 450
+
+layout(location = 0) in vec2 vertexTextureCoordinates;
+layout(location = 0) out vec4 fragmentColor;
+
+layout(set = 0, binding = 0) uniform texture2D _texture;
+layout(set = 0, binding = 1) uniform sampler _sampler;
+
+const float MASK_DARKNESS = 0.3;
+const float BRIGHTNESS_BOOST = 1.2;
+
+void main() {
+    vec4 color = texture(sampler2D(_texture, _sampler), vertexTextureCoordinates);
+    // vertical red, green and blue stripes of an aperture grille, one output pixel wide each
+    vec3 mask = vec3(1.0 - MASK_DARKNESS);
+    mask[int(gl_FragCoord.x) % 3] = 1.0;
+    fragmentColor = vec4(min(color.rgb * mask * BRIGHTNESS_BOOST, 1.0), 1);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout(location = 0) in vec2 vertexTextureCoordinates;
+layout(location = 0) out vec4 fragmentColor;
+
+layout(set = 0, binding = 0) uniform texture2D _texture;
+layout(set = 0, binding = 1) uniform sampler _sampler;
+layout(set = 0, binding = 2) uniform Sizes {
+    vec2 sourceSize;
+    vec2 outputSize;
+    vec2 textureOffset;
+    vec2 textureSize;
+};
+
+const float PI = 3.14159265;
+const float DARKNESS = 0.4;
+
+void main() {
+    vec4 color = texture(sampler2D(_texture, _sampler), vertexTextureCoordinates);
+    // brightest in the middle of each source line, darkest between lines
+    float line = fract(vertexTextureCoordinates.y * sourceSize.y);
+    float intensity = 1.0 - DARKNESS * (1.0 - sin(line * PI));
+    fragmentColor = vec4(color.rgb * intensity, 1);
+}
@@ -20,7 +20,15 @@ const vec2 textureCoordinates[6] = vec2[6](
 
 layout(location = 0) out vec2 vertexTextureCoordinates;
 
+// the visible part of the source texture, to crop the overscan
+layout(set = 0, binding = 2) uniform Sizes {
+    vec2 sourceSize;
+    vec2 outputSize;
+    vec2 textureOffset;
+    vec2 textureSize;
+};
+
 void main() {
     gl_Position = vec4(positions[gl_VertexIndex], 0, 1);
-    vertexTextureCoordinates = textureCoordinates[gl_VertexIndex];
+    vertexTextureCoordinates = textureOffset + textureCoordinates[gl_VertexIndex] * textureSize;
 }
@@ -0,0 +1,23 @@
+#version 450
+
+layout(location = 0) in vec2 vertexTextureCoordinates;
+layout(location = 0) out vec4 fragmentColor;
+
+layout(set = 0, binding = 0) uniform texture2D _texture;
+layout(set = 0, binding = 1) uniform sampler _sampler;
+layout(set = 0, binding = 2) uniform Sizes {
+    vec2 sourceSize;
+    vec2 outputSize;
+    vec2 textureOffset;
+    vec2 textureSize;
+};
+
+// nearest neighbour scaling by the largest integer factor, then bilinear interpolation on the edges of the texels only
+void main() {
+    vec2 texel = vertexTextureCoordinates * sourceSize;
+    vec2 scale = max(floor(outputSize / (sourceSize * textureSize)), 1.0);
+    vec2 regionRange = 0.5 - 0.5 / scale;
+    vec2 centerDistance = fract(texel) - 0.5;
+    vec2 offset = (centerDistance - clamp(centerDistance, -regionRange, regionRange)) * scale + 0.5;
+    fragmentColor = texture(sampler2D(_texture, _sampler), (floor(texel) + offset) / sourceSize);
+}
@@ -0,0 +1,245 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io::{Error, ErrorKind, Result},
+	path::Path
+};
+
+use disasm::*;
+
+const HEADER_SIZE: usize = 16;
+// the PRG ROM banks of the FCEUX name lists
+const NL_BANK_SIZE: usize = 0x4000;
+const PRG_ROM_START: u16 = 0x8000;
+const PRG_RAM_START: u16 = 0x6000;
+
+struct Symbol {
+	name: String,
+	// where the symbol is when its bank is mapped, unknown for the PRG ROM offsets of Mesen
+	address: Option<u16>,
+	// for the code and data in PRG ROM, which can be mapped at different addresses or share theirs with other banks
+	prg_rom_offset: Option<usize>
+}
+
+// names of the RAM, registers and PRG ROM locations, from ca65 debug info (.dbg), FCEUX name lists (.nl) or Mesen label files (.mlb)
+#[derive(Default)]
+pub struct SymbolTable {
+	symbols: Vec<Symbol>,
+	// the first symbol of a location or a name wins
+	addresses: HashMap<u16, usize>,
+	prg_rom_offsets: HashMap<usize, usize>,
+	names: HashMap<String, usize>
+}
+
+impl SymbolTable {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// the files named after the ROM: game.dbg, game.mlb, and game.nes.ram.nl with game.nes.0.nl, game.nes.1.nl... for each bank
+	pub fn load_for_rom(rom_filename: &str) -> Self {
+		let mut symbols = Self::new();
+		let path = Path::new(rom_filename);
+		let mut filenames = vec![path.with_extension("dbg"), path.with_extension("mlb"), Path::new(&format!("{}.ram.nl", rom_filename)).to_path_buf()];
+		filenames.extend((0..0x100).map(|bank| Path::new(&format!("{}.{:X}.nl", rom_filename, bank)).to_path_buf()).take_while(|filename| filename.exists()));
+		for filename in filenames.iter().filter(|filename| filename.exists()) {
+			if let Err(error) = symbols.load(&filename.to_string_lossy()) {
+				error!("Couldn't load the symbols of {}: {}", filename.display(), error);
+			}
+		}
+		if !symbols.is_empty() {
+			info!("Symbols: {}", symbols.len());
+		}
+		symbols
+	}
+
+	// the format is given by the extension
+	pub fn load(&mut self, filename: &str) -> Result<()> {
+		let contents = fs::read_to_string(filename)?;
+		match Path::new(filename).extension().and_then(|extension| extension.to_str()) {
+			Some("dbg") => self.parse_dbg(&contents),
+			Some("mlb") => self.parse_mlb(&contents),
+			Some("nl") => {
+				// game.nes.ram.nl or game.nes.<hexadecimal bank>.nl
+				let bank = Path::new(filename).file_stem().and_then(|stem| Path::new(stem).extension()).and_then(|bank| bank.to_str()).unwrap_or("");
+				let bank = match bank {
+					"ram" => None,
+					bank => Some(usize::from_str_radix(bank, 16).map_err(|_| invalid_data(format!("No bank number in the name of {}", filename)))?)
+				};
+				self.parse_nl(&contents, bank)
+			},
+			_ => Err(invalid_data(format!("Unknown symbol file format: {}", filename)))
+		}
+	}
+
+	// a symbol without an address nor a PRG ROM offset is left out
+	pub fn add(&mut self, name: &str, address: Option<u16>, prg_rom_offset: Option<usize>) {
+		let index = self.symbols.len();
+		match (prg_rom_offset, address) {
+			(Some(offset), _) => {
+				self.prg_rom_offsets.entry(offset).or_insert(index);
+			},
+			(None, Some(address)) => {
+				self.addresses.entry(address).or_insert(index);
+			},
+			(None, None) => return
+		}
+		self.names.entry(name.to_string()).or_insert(index);
+		self.symbols.push(Symbol {
+			name: name.to_string(),
+			address,
+			prg_rom_offset
+		});
+	}
+
+	pub fn len(&self) -> usize {
+		self.symbols.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.symbols.is_empty()
+	}
+
+	// the name of a CPU address, the PRG ROM offset mapped there telling the banks apart
+	pub fn get_label(&self, address: u16, prg_rom_offset: Option<usize>) -> Option<&str> {
+		prg_rom_offset.and_then(|offset| self.prg_rom_offsets.get(&offset)).or_else(|| self.addresses.get(&address)).map(|&index| self.symbols[index].name.as_str())
+	}
+
+	// the CPU address of a name, where its bank is mapped if it is, with a function giving the PRG ROM offset of an address
+	pub fn get_address<F: Fn(u16) -> Option<usize>>(&self, name: &str, get_prg_rom_offset: F) -> Option<u16> {
+		let symbol = &self.symbols[*self.names.get(name)?];
+		let offset = match (symbol.prg_rom_offset, symbol.address) {
+			(Some(offset), Some(address)) if get_prg_rom_offset(address) == Some(offset) => return Some(address),
+			(Some(offset), _) => offset,
+			(None, address) => return address
+		};
+		// the bank may be mapped elsewhere, or not at all; the highest address is the fixed bank of most mappers
+		(PRG_ROM_START..=0xffff).rev().find(|&address| get_prg_rom_offset(address) == Some(offset)).or(symbol.address)
+	}
+
+	// labels of the addresses, with a function giving the PRG ROM offset of an address
+	pub fn with_mapping<F: Fn(u16) -> Option<usize>>(&self, get_prg_rom_offset: F) -> MappedLabels<'_, F> {
+		MappedLabels {
+			symbols: self,
+			get_prg_rom_offset
+		}
+	}
+
+	// lines like "sym id=0,name="reset",addrsize=absolute,scope=0,def=1,val=0xC000,seg=0,type=lab", the segments giving the offsets in the output file
+	fn parse_dbg(&mut self, contents: &str) -> Result<()> {
+		let mut segments = HashMap::new();
+		let mut labels = Vec::new();
+		for line in contents.lines() {
+			let (kind, attributes) = match line.split_once('\t') {
+				Some((kind, attributes)) => (kind, parse_dbg_attributes(attributes)),
+				None => continue
+			};
+			match kind {
+				"seg" => {
+					let id = get_dbg_number(&attributes, "id", line)?;
+					let start = get_dbg_number(&attributes, "start", line)?;
+					let file_offset = match attributes.get("ooffs") {
+						Some(_) => Some(get_dbg_number(&attributes, "ooffs", line)?),
+						None => None
+					};
+					segments.insert(id, (start, file_offset));
+				},
+				// equates are left out, they are mostly constants
+				"sym" if attributes.get("type") == Some(&"lab") && attributes.contains_key("val") => {
+					let name = attributes.get("name").ok_or_else(|| invalid_data(format!("Symbol without a name: {}", line)))?;
+					let value = get_dbg_number(&attributes, "val", line)?;
+					let segment = match attributes.get("seg") {
+						Some(_) => Some(get_dbg_number(&attributes, "seg", line)?),
+						None => None
+					};
+					labels.push((name.trim_matches('"').to_string(), value, segment));
+				},
+				_ => {}
+			}
+		}
+		for (name, value, segment) in labels {
+			// the labels of segments written after the header of the ROM are in PRG ROM
+			let prg_rom_offset = match segment.and_then(|segment| segments.get(&segment)) {
+				Some(&(start, Some(file_offset))) if file_offset >= HEADER_SIZE && value >= start => Some(file_offset - HEADER_SIZE + value - start),
+				_ => None
+			};
+			self.add(&name, Some(value as _), prg_rom_offset);
+		}
+		Ok(())
+	}
+
+	// lines like "P:1F3A:reset:comment", "R:0010:counter" or "NesPrgRom:1F3A:reset" for Mesen 2, with "start-end" for arrays
+	fn parse_mlb(&mut self, contents: &str) -> Result<()> {
+		for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+			let mut fields = line.splitn(4, ':');
+			let (kind, address, name) = match (fields.next(), fields.next(), fields.next()) {
+				(Some(kind), Some(address), Some(name)) => (kind, address, name),
+				_ => return Err(invalid_data(format!("Wrong label line: {}", line)))
+			};
+			// comments have their own lines
+			if name.is_empty() {
+				continue;
+			}
+			let start = address.split('-').next().unwrap();
+			let offset = usize::from_str_radix(start, 16).map_err(|_| invalid_data(format!("Wrong label line: {}", line)))?;
+			match kind {
+				"P" | "NesPrgRom" => self.add(name, None, Some(offset)),
+				"R" | "NesInternalRam" | "G" | "NesMemory" => self.add(name, Some(offset as _), None),
+				"S" | "NesSaveRam" | "W" | "NesWorkRam" => self.add(name, Some(PRG_RAM_START + offset as u16), None),
+				// CHR ROM and the other memories of the PPU
+				_ => {}
+			}
+		}
+		Ok(())
+	}
+
+	// lines like "$C000#reset#comment", with "$0300/10" for arrays
+	fn parse_nl(&mut self, contents: &str, bank: Option<usize>) -> Result<()> {
+		for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+			let mut fields = line.split('#');
+			let (address, name) = match (fields.next(), fields.next()) {
+				(Some(address), Some(name)) => (address, name),
+				_ => return Err(invalid_data(format!("Wrong name line: {}", line)))
+			};
+			let address = address.trim_start_matches('$').split('/').next().unwrap();
+			let address = u16::from_str_radix(address, 16).map_err(|_| invalid_data(format!("Wrong name line: {}", line)))?;
+			let prg_rom_offset = match bank {
+				Some(bank) if address >= PRG_ROM_START => Some(bank * NL_BANK_SIZE + (address as usize % NL_BANK_SIZE)),
+				_ => None
+			};
+			self.add(name, Some(address), prg_rom_offset);
+		}
+		Ok(())
+	}
+}
+
+// labels of the CPU address space, bank-aware
+pub struct MappedLabels<'a, F> {
+	symbols: &'a SymbolTable,
+	get_prg_rom_offset: F
+}
+
+impl<'a, F: Fn(u16) -> Option<usize>> Labels for MappedLabels<'a, F> {
+	fn get_label(&self, address: u16) -> Option<&str> {
+		self.symbols.get_label(address, (self.get_prg_rom_offset)(address))
+	}
+}
+
+fn invalid_data(message: String) -> Error {
+	Error::new(ErrorKind::InvalidData, message)
+}
+
+// "id=0,name="reset",val=0xC000", the quoted values having no commas in practice
+fn parse_dbg_attributes(attributes: &str) -> HashMap<&str, &str> {
+	attributes.split(',').filter_map(|attribute| attribute.split_once('=')).collect()
+}
+
+// decimal or hexadecimal with a "0x" prefix
+fn get_dbg_number(attributes: &HashMap<&str, &str>, name: &str, line: &str) -> Result<usize> {
+	let value = attributes.get(name).ok_or_else(|| invalid_data(format!("Missing {} in {}", name, line)))?;
+	let number = match value.strip_prefix("0x") {
+		Some(digits) => usize::from_str_radix(digits, 16),
+		None => value.parse()
+	};
+	number.map_err(|_| invalid_data(format!("Wrong {} in {}", name, line)))
+}
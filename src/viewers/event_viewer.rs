@@ -0,0 +1,114 @@
+use winit::event::VirtualKeyCode;
+
+use mu::{
+    emulator::*,
+    palette::*,
+    screen::*,
+    region::*,
+    events::*
+};
+
+use super::*;
+
+const DOTS_PER_SCANLINE: usize = 341;
+const PIXEL_SIZE: usize = 4;
+
+const HBLANK_COLOR: [u8; 4] = [0x20, 0x20, 0x20, 0xff];
+const VBLANK_COLOR: [u8; 4] = [0x10, 0x10, 0x30, 0xff];
+
+// the registers and the color of their events
+const REGISTER_COLORS: [(u16, u16, [u8; 4]); 10] = [
+    (0x2000, 0x2000, [0xff, 0x40, 0x40, 0xff]), // PPUCTRL
+    (0x2001, 0x2001, [0xff, 0xa0, 0x20, 0xff]), // PPUMASK
+    (0x2002, 0x2002, [0xff, 0xff, 0x40, 0xff]), // PPUSTATUS
+    (0x2003, 0x2004, [0xc0, 0x80, 0xff, 0xff]), // OAMADDR and OAMDATA
+    (0x2005, 0x2005, [0x40, 0xff, 0x40, 0xff]), // PPUSCROLL
+    (0x2006, 0x2006, [0x40, 0xff, 0xff, 0xff]), // PPUADDR
+    (0x2007, 0x2007, [0x40, 0x80, 0xff, 0xff]), // PPUDATA
+    (0x4014, 0x4014, [0xff, 0x40, 0xff, 0xff]), // OAMDMA
+    (0x4000, 0x4017, [0xc0, 0xc0, 0xc0, 0xff]), // APU and joypads
+    (0x8000, 0xffff, [0xff, 0xff, 0xff, 0xff])  // mapper
+];
+
+// the register accesses of the last frame at their dot and scanline, over the frame picture
+pub struct EventViewer {
+    scanline_count: usize,
+    vblank_scanline: usize,
+    show_reads: bool
+}
+
+impl EventViewer {
+    pub fn new(region: Region) -> Self {
+        Self {
+            scanline_count: region.get_scanline_count() as _,
+            vblank_scanline: region.get_vblank_scanline() as _,
+            show_reads: true
+        }
+    }
+
+    fn draw_background(&self, screen: &Screen, image: &mut Image) {
+        let frame_buffer = screen.get_frame_buffer();
+        for scanline in 0..self.scanline_count {
+            for dot in 0..DOTS_PER_SCANLINE {
+                let color = if scanline < FRAME_HEIGHT && (1..=FRAME_WIDTH).contains(&dot) {
+                    // the picture is dimmed so that the events stand out, the NTSC filter making it wider
+                    if screen.get_frame_width() == FRAME_WIDTH {
+                        let offset = (scanline * FRAME_WIDTH + dot - 1) * PIXEL_SIZE;
+                        let pixel = &frame_buffer[offset..offset + PIXEL_SIZE];
+                        [pixel[0] / 3, pixel[1] / 3, pixel[2] / 3, 0xff]
+                    } else {
+                        [0x30, 0x30, 0x30, 0xff]
+                    }
+                } else if scanline >= self.vblank_scanline && scanline < self.scanline_count - 1 {
+                    VBLANK_COLOR
+                } else {
+                    HBLANK_COLOR
+                };
+                image.set_pixel(dot, scanline, color);
+            }
+        }
+    }
+}
+
+impl Viewer for EventViewer {
+    fn get_title(&self) -> String {
+        if self.show_reads {
+            "Events - R hides the reads".to_string()
+        } else {
+            "Events - R shows the reads".to_string()
+        }
+    }
+
+    fn get_image_size(&self) -> (usize, usize) {
+        (DOTS_PER_SCANLINE, self.scanline_count)
+    }
+
+    fn draw(&mut self, emulator: &Emulator, _palette: &Palette, image: &mut Image) {
+        self.draw_background(&emulator.screen, image);
+        let events = match emulator.event_log {
+            Some(ref event_log) => event_log.get_last_frame(emulator.ppu.get_frame_counter()),
+            None => &[]
+        };
+        for event in events.iter().filter(|event| self.show_reads || event.kind == EventKind::Write) {
+            let color = REGISTER_COLORS.iter().find(|(start, end, _)| (*start..=*end).contains(&event.address)).map_or(HBLANK_COLOR, |(_, _, color)| *color);
+            // reads are drawn darker
+            let color = match event.kind {
+                EventKind::Read => [color[0] / 2, color[1] / 2, color[2] / 2, 0xff],
+                EventKind::Write => color
+            };
+            // 3x3 marks, cut at the edges
+            let (dot, scanline) = (event.dot as usize, event.scanline as usize);
+            for y in scanline.saturating_sub(1)..(scanline + 2).min(self.scanline_count) {
+                for x in dot.saturating_sub(1)..(dot + 2).min(DOTS_PER_SCANLINE) {
+                    image.set_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode) {
+        if key == VirtualKeyCode::R {
+            self.show_reads = !self.show_reads;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use mu::{
+    ppu::*,
+    palette::*
+};
+
+pub const TILE_SIZE: usize = 8;
+
+const PIXEL_SIZE: usize = 4;
+
+const TEXT_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const CHARACTER_WIDTH: usize = 3;
+pub const CHARACTER_HEIGHT: usize = 5;
+const CHARACTER_SPACING: usize = 1;
+
+// 3x5 pixel hexadecimal digits, one bit per pixel from the top left
+const HEX_DIGITS: [u16; 16] = [
+    0b111_101_101_101_111, 0b010_110_010_010_111, 0b111_001_111_100_111, 0b111_001_111_001_111,
+    0b101_101_111_001_001, 0b111_100_111_001_111, 0b111_100_111_101_111, 0b111_001_001_001_001,
+    0b111_101_111_101_111, 0b111_101_111_001_111, 0b111_101_111_101_101, 0b110_101_110_101_110,
+    0b111_100_100_100_111, 0b110_101_101_101_110, 0b111_100_111_100_111, 0b111_100_111_100_100
+];
+
+// RGBA image that a viewer draws into
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut image = Self {
+            width,
+            height,
+            pixels: vec![0; width * height * PIXEL_SIZE]
+        };
+        image.clear();
+        image
+    }
+
+    // opaque black
+    pub fn clear(&mut self) {
+        for pixel in self.pixels.chunks_mut(PIXEL_SIZE) {
+            pixel.copy_from_slice(&[0, 0, 0, 0xff]);
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        let offset = (y * self.width + x) * PIXEL_SIZE;
+        self.pixels[offset..offset + PIXEL_SIZE].copy_from_slice(&color);
+    }
+
+    pub fn fill_rectangle(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 4]) {
+        for row in y..y + height {
+            for column in x..x + width {
+                self.set_pixel(column, row, color);
+            }
+        }
+    }
+
+    // 1 pixel outline which continues on the opposite edge when it goes past one
+    pub fn draw_wrapping_rectangle(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 4]) {
+        for offset in 0..width {
+            let column = (x + offset) % self.width;
+            self.set_pixel(column, y % self.height, color);
+            self.set_pixel(column, (y + height - 1) % self.height, color);
+        }
+        for offset in 0..height {
+            let row = (y + offset) % self.height;
+            self.set_pixel(x % self.width, row, color);
+            self.set_pixel((x + width - 1) % self.width, row, color);
+        }
+    }
+
+    // draws a 8x8 tile with the 4 colors of the palette starting at palette_address, flipped by the bits 6 and 7 of sprite attributes
+    pub fn draw_tile(&mut self, ppu: &Ppu, palette: &Palette, tile_address: u16, palette_address: u16, (x, y): (usize, usize), attributes: u8) {
+        let horizontal_flip = (attributes & 0x40) != 0;
+        let vertical_flip = (attributes & 0x80) != 0;
+        for pixel_row in 0..TILE_SIZE {
+            let low_byte = ppu.read_memory_debug(tile_address + pixel_row as u16);
+            let high_byte = ppu.read_memory_debug(tile_address + pixel_row as u16 + 8);
+            for pixel_column in 0..TILE_SIZE {
+                let low_bit = (low_byte >> (7 - pixel_column)) & 1;
+                let high_bit = (high_byte >> (7 - pixel_column)) & 1;
+                let color_number = (high_bit << 1) | low_bit;
+                let color = ppu.read_memory_debug(palette_address + color_number as u16);
+                let column = if horizontal_flip {
+                    TILE_SIZE - 1 - pixel_column
+                } else {
+                    pixel_column
+                };
+                let row = if vertical_flip {
+                    TILE_SIZE - 1 - pixel_row
+                } else {
+                    pixel_row
+                };
+                self.set_pixel(x + column, y + row, get_color(palette, color));
+            }
+        }
+    }
+
+    // draws a byte as 2 hexadecimal digits, and returns the width of the text
+    pub fn draw_hex(&mut self, x: usize, y: usize, value: u8) -> usize {
+        for (index, digit) in [value >> 4, value & 0xf].iter().enumerate() {
+            let glyph = HEX_DIGITS[*digit as usize];
+            let digit_x = x + index * (CHARACTER_WIDTH + CHARACTER_SPACING);
+            for row in 0..CHARACTER_HEIGHT {
+                for column in 0..CHARACTER_WIDTH {
+                    let bit = (CHARACTER_HEIGHT - 1 - row) * CHARACTER_WIDTH + CHARACTER_WIDTH - 1 - column;
+                    if (glyph >> bit) & 1 != 0 {
+                        self.set_pixel(digit_x + column, y + row, TEXT_COLOR);
+                    }
+                }
+            }
+        }
+        2 * (CHARACTER_WIDTH + CHARACTER_SPACING)
+    }
+}
+
+// color is a palette RAM value
+pub fn get_color(palette: &Palette, color: u8) -> [u8; 4] {
+    palette.get_rgba((color & 0x3f) as _)
+}
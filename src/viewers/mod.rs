@@ -0,0 +1,161 @@
+mod image;
+mod nametable_viewer;
+mod pattern_table_viewer;
+mod palette_viewer;
+mod oam_viewer;
+mod event_viewer;
+
+pub use self::image::*;
+pub use self::nametable_viewer::*;
+pub use self::pattern_table_viewer::*;
+pub use self::palette_viewer::*;
+pub use self::oam_viewer::*;
+pub use self::event_viewer::*;
+
+use winit::{
+    event::*,
+    event_loop::EventLoopWindowTarget,
+    window::{Window, WindowBuilder, WindowId},
+    dpi::PhysicalSize
+};
+
+use mu::{
+    emulator::*,
+    palette::*,
+    region::*
+};
+
+use renderer::*;
+
+// debug view of the PPU state, drawn in its own window at every VBlank
+pub trait Viewer {
+    fn get_title(&self) -> String;
+
+    fn get_image_size(&self) -> (usize, usize);
+
+    // initial size of the window compared to the image
+    fn get_scale(&self) -> u32 {
+        2
+    }
+
+    fn draw(&mut self, emulator: &Emulator, palette: &Palette, image: &mut Image);
+
+    // keys pressed while the viewer window has the focus
+    fn handle_key(&mut self, _key: VirtualKeyCode) {}
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ViewerKind {
+    Nametables,
+    PatternTables,
+    Palette,
+    Oam,
+    Events
+}
+
+impl ViewerKind {
+    // the event viewer has a row per scanline of the region
+    fn create_viewer(self, region: Region) -> Box<dyn Viewer> {
+        match self {
+            ViewerKind::Nametables => Box::new(NametableViewer),
+            ViewerKind::PatternTables => Box::new(PatternTableViewer::new()),
+            ViewerKind::Palette => Box::new(PaletteViewer),
+            ViewerKind::Oam => Box::new(OamViewer),
+            ViewerKind::Events => Box::new(EventViewer::new(region))
+        }
+    }
+}
+
+struct ViewerWindow {
+    kind: ViewerKind,
+    viewer: Box<dyn Viewer>,
+    image: Image,
+    // the surface must be dropped before its window
+    surface: ViewerSurface,
+    window: Window
+}
+
+// the open viewer windows, which share the device of the main renderer
+pub struct Viewers {
+    windows: Vec<ViewerWindow>
+}
+
+impl Viewers {
+    pub fn new() -> Self {
+        Self {
+            windows: Vec::new()
+        }
+    }
+
+    // opens the viewer, or closes it if it is already open
+    pub fn toggle(&mut self, kind: ViewerKind, region: Region, event_loop: &EventLoopWindowTarget<()>, renderer: &Renderer) {
+        if let Some(index) = self.windows.iter().position(|window| window.kind == kind) {
+            self.windows.remove(index);
+            return;
+        }
+        let viewer = kind.create_viewer(region);
+        let (width, height) = viewer.get_image_size();
+        let scale = viewer.get_scale();
+        let window = WindowBuilder::new()
+            .with_title(viewer.get_title())
+            .with_inner_size(PhysicalSize::new(width as u32 * scale, height as u32 * scale))
+            .build(event_loop)
+            .unwrap();
+        let surface = match renderer.create_viewer_surface(&window, width as _, height as _) {
+            Ok(surface) => surface,
+            Err(error) => {
+                error!("Couldn't open the viewer: {}", error);
+                return;
+            }
+        };
+        self.windows.push(ViewerWindow {
+            kind,
+            viewer,
+            image: Image::new(width, height),
+            surface,
+            window
+        });
+    }
+
+    pub fn handle_window_event(&mut self, window_id: WindowId, event: &WindowEvent, renderer: &Renderer) {
+        let index = match self.windows.iter().position(|window| window.window.id() == window_id) {
+            Some(index) => index,
+            None => return
+        };
+        match event {
+            WindowEvent::CloseRequested => {
+                self.windows.remove(index);
+            },
+            WindowEvent::Resized(size) => renderer.resize_viewer_surface(&mut self.windows[index].surface, *size),
+            WindowEvent::ScaleFactorChanged {
+                ref new_inner_size,
+                ..
+            } => renderer.resize_viewer_surface(&mut self.windows[index].surface, **new_inner_size),
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => {
+                let viewer_window = &mut self.windows[index];
+                viewer_window.viewer.handle_key(*key);
+                viewer_window.window.set_title(&viewer_window.viewer.get_title());
+            },
+            _ => {}
+        }
+    }
+
+    pub fn is_open(&self, kind: ViewerKind) -> bool {
+        self.windows.iter().any(|window| window.kind == kind)
+    }
+
+    pub fn update(&mut self, emulator: &Emulator, palette: &Palette, renderer: &Renderer) {
+        for viewer_window in self.windows.iter_mut() {
+            viewer_window.viewer.draw(emulator, palette, &mut viewer_window.image);
+            let image = &viewer_window.image;
+            renderer.draw_viewer_surface(&mut viewer_window.surface, &image.pixels, image.width, image.height);
+        }
+    }
+}
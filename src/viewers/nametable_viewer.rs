@@ -0,0 +1,65 @@
+use mu::{
+    emulator::*,
+    palette::*,
+    screen::*
+};
+
+use super::*;
+
+const WIDTH: usize = FRAME_WIDTH * 2;
+const HEIGHT: usize = FRAME_HEIGHT * 2;
+
+const SCROLL_COLOR: [u8; 4] = [0xff, 0x00, 0xff, 0xff];
+
+// the 4 nametables, with the part shown on the screen outlined
+pub struct NametableViewer;
+
+impl Viewer for NametableViewer {
+    fn get_title(&self) -> String {
+        "Nametables".to_string()
+    }
+
+    fn get_image_size(&self) -> (usize, usize) {
+        (WIDTH, HEIGHT)
+    }
+
+    fn get_scale(&self) -> u32 {
+        1
+    }
+
+    fn draw(&mut self, emulator: &Emulator, palette: &Palette, image: &mut Image) {
+        let ppu = &emulator.ppu;
+        let pattern_address = 0x1000 * ((ppu.get_ppuctrl() >> 4) & 1) as u16;
+        for y in 0..HEIGHT as u16 {
+            let tile_row = y / 8;
+            let pixel_row = y % 8;
+            let attribute_row = (tile_row % 30) / 4;
+            for x in 0..WIDTH as u16 {
+                let tile_column = x / 8;
+                let pixel_column = x % 8;
+                let attribute_column = (tile_column % 32) / 4;
+                let nametable_address: u16 = 0x2000 + 0x400 * (tile_column / 32 + (tile_row / 30) * 2);
+                let attribute_table_address = nametable_address + 0x3c0;
+                let attribute = ppu.read_memory_debug(attribute_table_address + attribute_row * 8 + attribute_column);
+                let palette_number = ((attribute >> (4 * (((tile_row % 30) / 2) % 2))) >> (2 * (((tile_column % 32) / 2) % 2))) & 0b11;
+                let tile_number_address = nametable_address + (tile_row % 30) * 32 + tile_column % 32;
+                let tile_number = ppu.read_memory_debug(tile_number_address);
+                let low_byte = ppu.read_memory_debug(pattern_address + (tile_number as u16) * 16 + pixel_row);
+                let high_byte = ppu.read_memory_debug(pattern_address + (tile_number as u16) * 16 + pixel_row + 8);
+                let low_bit = (low_byte >> (7 - pixel_column)) & 1;
+                let high_bit = (high_byte >> (7 - pixel_column)) & 1;
+                let color_number = (high_bit << 1) | low_bit;
+                let color_address = if color_number == 0 {
+                    0 // backdrop color
+                } else {
+                    4 * palette_number as u16 + color_number as u16
+                } + 0x3f00;
+                let color = ppu.read_memory_debug(color_address);
+                image.set_pixel(x as _, y as _, get_color(palette, color));
+            }
+        }
+
+        let (scroll_x, scroll_y) = ppu.get_scroll_position();
+        image.draw_wrapping_rectangle(scroll_x as _, scroll_y as _, FRAME_WIDTH, FRAME_HEIGHT, SCROLL_COLOR);
+    }
+}
@@ -0,0 +1,67 @@
+use mu::{
+    emulator::*,
+    palette::*
+};
+
+use super::*;
+
+// each sprite with its Y coordinate and tile number, then its attributes and X coordinate
+const CELL_WIDTH: usize = 32;
+const CELL_HEIGHT: usize = 20;
+const WIDTH: usize = 8 * CELL_WIDTH;
+const HEIGHT: usize = 8 * CELL_HEIGHT;
+
+const TEXT_OFFSET: usize = 2 + TILE_SIZE + 2;
+const LINE_HEIGHT: usize = CHARACTER_HEIGHT + 2;
+
+pub struct OamViewer;
+
+impl Viewer for OamViewer {
+    fn get_title(&self) -> String {
+        "OAM".to_string()
+    }
+
+    fn get_image_size(&self) -> (usize, usize) {
+        (WIDTH, HEIGHT)
+    }
+
+    fn draw(&mut self, emulator: &Emulator, palette: &Palette, image: &mut Image) {
+        let ppu = &emulator.ppu;
+        image.clear();
+        let ppuctrl = ppu.get_ppuctrl();
+        let sprite_height = if (ppuctrl & 0x20) == 0 {
+            8
+        } else {
+            16
+        };
+        for (number, sprite) in ppu.get_oam().chunks(4).enumerate() {
+            let (sprite_y, tile_number, attributes, sprite_x) = (sprite[0], sprite[1], sprite[2], sprite[3]);
+            let x = (number % 8) * CELL_WIDTH;
+            let y = (number / 8) * CELL_HEIGHT;
+
+            let palette_address = 0x3f10 + 4 * (attributes & 0b11) as u16;
+            let (pattern_address, first_tile) = if sprite_height == 8 {
+                (0x1000 * ((ppuctrl >> 3) & 1) as u16, tile_number as u16)
+            } else {
+                // 8x16 sprites select their pattern table with bit 0 of the tile number
+                (0x1000 * (tile_number & 1) as u16, (tile_number & 0xfe) as u16)
+            };
+            for tile in 0..sprite_height / 8 {
+                // flipping a 8x16 sprite vertically also swaps its tiles
+                let row = if (attributes & 0x80) != 0 {
+                    sprite_height / 8 - 1 - tile
+                } else {
+                    tile
+                };
+                let tile_address = pattern_address + (first_tile + tile as u16) * 16;
+                image.draw_tile(ppu, palette, tile_address, palette_address, (x + 2, y + 2 + row * TILE_SIZE), attributes);
+            }
+
+            let text_x = x + TEXT_OFFSET;
+            let width = image.draw_hex(text_x, y + 3, sprite_y);
+            image.draw_hex(text_x + width + 3, y + 3, tile_number);
+            image.draw_hex(text_x, y + 3 + LINE_HEIGHT, attributes);
+            image.draw_hex(text_x + width + 3, y + 3 + LINE_HEIGHT, sprite_x);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use mu::{
+    emulator::*,
+    palette::*
+};
+
+use super::*;
+
+const SWATCH_SIZE: usize = 16;
+const ROW_HEIGHT: usize = SWATCH_SIZE + 1 + CHARACTER_HEIGHT + 2;
+const WIDTH: usize = 16 * SWATCH_SIZE;
+const HEIGHT: usize = 2 * ROW_HEIGHT;
+
+// the background palettes on the first row and the sprite palettes on the second one, with their values
+pub struct PaletteViewer;
+
+impl Viewer for PaletteViewer {
+    fn get_title(&self) -> String {
+        "Palette RAM".to_string()
+    }
+
+    fn get_image_size(&self) -> (usize, usize) {
+        (WIDTH, HEIGHT)
+    }
+
+    fn draw(&mut self, emulator: &Emulator, palette: &Palette, image: &mut Image) {
+        let ppu = &emulator.ppu;
+        image.clear();
+        for entry in 0..32 {
+            let color = ppu.read_memory_debug(0x3f00 + entry as u16);
+            let x = (entry % 16) * SWATCH_SIZE;
+            let y = (entry / 16) * ROW_HEIGHT;
+            image.fill_rectangle(x, y, SWATCH_SIZE, SWATCH_SIZE, get_color(palette, color));
+            image.draw_hex(x + 4, y + SWATCH_SIZE + 1, color);
+        }
+    }
+}
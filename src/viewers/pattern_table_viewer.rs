@@ -0,0 +1,60 @@
+use winit::event::VirtualKeyCode;
+
+use mu::{
+    emulator::*,
+    palette::*
+};
+
+use super::*;
+
+// 16x16 tiles
+const TABLE_SIZE: usize = 16 * TILE_SIZE;
+const WIDTH: usize = 2 * TABLE_SIZE + TILE_SIZE;
+const HEIGHT: usize = TABLE_SIZE;
+
+// 0 to 3 select a background palette and 4 to 7 a sprite palette
+const PALETTE_KEYS: [VirtualKeyCode; 8] = [
+    VirtualKeyCode::Key0, VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6, VirtualKeyCode::Key7
+];
+
+pub struct PatternTableViewer {
+    palette_number: u8
+}
+
+impl PatternTableViewer {
+    pub fn new() -> Self {
+        Self {
+            palette_number: 0
+        }
+    }
+}
+
+impl Viewer for PatternTableViewer {
+    fn get_title(&self) -> String {
+        format!("Pattern Tables - Palette {}", self.palette_number)
+    }
+
+    fn get_image_size(&self) -> (usize, usize) {
+        (WIDTH, HEIGHT)
+    }
+
+    fn draw(&mut self, emulator: &Emulator, palette: &Palette, image: &mut Image) {
+        let ppu = &emulator.ppu;
+        let palette_address = 0x3f00 + 4 * self.palette_number as u16;
+        for table in 0..2 {
+            for tile_number in 0..256 {
+                let x = table * (TABLE_SIZE + TILE_SIZE) + (tile_number % 16) * TILE_SIZE;
+                let y = (tile_number / 16) * TILE_SIZE;
+                let tile_address = 0x1000 * table as u16 + 16 * tile_number as u16;
+                image.draw_tile(ppu, palette, tile_address, palette_address, (x, y), 0);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode) {
+        if let Some(number) = PALETTE_KEYS.iter().position(|palette_key| *palette_key == key) {
+            self.palette_number = number as u8;
+        }
+    }
+}